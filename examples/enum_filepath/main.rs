@@ -3,15 +3,16 @@ use dwrite_rs::factory::{Factory, FactoryType};
 fn main() -> anyhow::Result<()> {
     let factory = Factory::new(FactoryType::Shared)?;
     let collection = factory.get_system_font_collection(true)?;
-    for family in &collection {
-        for font in &family {
-            if font.is_simulation() {
-                continue;
-            }
-
+    // リモートフォントなどでパスを取得できないフォントは飛ばして，最後にまとめて表示する．
+    let report = collection.for_each_font(|_, font| {
+        if !font.is_simulation() {
             let filepath = font.get_filepath()?;
             println!("{}", filepath);
         }
+        Ok(())
+    });
+    if !report.is_complete() {
+        eprint!("{}", report);
     }
     Ok(())
 }
@@ -1,11 +1,16 @@
+// DirectWrite を直接使うため Windows でのみ動作する．
+#[cfg(windows)]
 use dwrite_rs::{
     factory::{Factory, FactoryType},
     font::{Font, InformationalStringId, Simulations},
     localized_strings::LocalizedStrings,
 };
+#[cfg(windows)]
 use gdi_rs::UTF16String;
+#[cfg(windows)]
 use std::io::Write;
 
+#[cfg(windows)]
 fn get_string_id(font: &Font, id: InformationalStringId) -> anyhow::Result<String> {
     let strings = font.get_informational_strings(id)?;
     match strings {
@@ -14,16 +19,18 @@ fn get_string_id(font: &Font, id: InformationalStringId) -> anyhow::Result<Strin
     }
 }
 
+#[cfg(windows)]
 fn get_string(strings: &LocalizedStrings) -> anyhow::Result<String> {
     match strings.get("ja-jp")? {
         Some(string) => Ok(string),
         None => match strings.get("en-us")? {
             Some(string) => Ok(string),
-            None => strings.get_string(0),
+            None => Ok(strings.get_string(0)?),
         },
     }
 }
 
+#[cfg(windows)]
 fn main() -> anyhow::Result<()> {
     println!("GDI と DWrite のフォント列挙の名前をすべて表示する");
     let factory = Factory::new(FactoryType::Shared)?;
@@ -54,8 +61,10 @@ fn main() -> anyhow::Result<()> {
     writeln!(&mut file, "{}", strings)?;
 
     for family in &collection {
+        let family = family?;
         let family_name = get_string(&family.get_family_names()?)?;
         for font in &family {
+            let font = font?;
             if font.get_simulations() != Simulations::None {
                 continue;
             }
@@ -114,3 +123,8 @@ fn main() -> anyhow::Result<()> {
 
     Ok(())
 }
+
+#[cfg(not(windows))]
+fn main() {
+    eprintln!("This example requires DirectWrite.");
+}
@@ -1,14 +1,21 @@
+// DirectWrite を直接使うため Windows でのみ動作する．
+#[cfg(windows)]
 use std::collections::{HashMap, HashSet};
+#[cfg(windows)]
 use std::io::Write;
 
+#[cfg(windows)]
 use dwrite_rs::{
     factory::{Factory, FactoryType},
     font::{Font, InformationalStringId},
     localized_strings::LocalizedStrings,
 };
+#[cfg(windows)]
 use gdi_rs::{enum_font_families_ex, is_vertical, UTF16String};
+#[cfg(windows)]
 use windows::Win32::Graphics::Gdi::DEFAULT_CHARSET;
 
+#[cfg(windows)]
 #[derive(PartialEq, PartialOrd, Eq, Hash)]
 struct FontInfo {
     face_name: String,
@@ -16,16 +23,18 @@ struct FontInfo {
     italic: bool,
 }
 
+#[cfg(windows)]
 fn get_string(strings: &LocalizedStrings) -> anyhow::Result<String> {
     match strings.get("ja-jp")? {
         Some(string) => Ok(string),
         None => match strings.get("en-us")? {
             Some(string) => Ok(string),
-            None => strings.get_string(0),
+            None => Ok(strings.get_string(0)?),
         },
     }
 }
 
+#[cfg(windows)]
 fn get_string_id(font: &Font, id: InformationalStringId) -> anyhow::Result<String> {
     let strings = font.get_informational_strings(id)?;
     match strings {
@@ -34,6 +43,7 @@ fn get_string_id(font: &Font, id: InformationalStringId) -> anyhow::Result<Strin
     }
 }
 
+#[cfg(windows)]
 fn main() -> anyhow::Result<()> {
     let mut lffacenames = HashSet::<[u16; 32]>::new();
     enum_font_families_ex([0; 32], DEFAULT_CHARSET, |args| {
@@ -108,7 +118,6 @@ fn main() -> anyhow::Result<()> {
         let font = interop.create_font_from_logfont(&logfont);
         match font {
             Ok(font) => {
-                let font = Font(font);
                 let family = font.get_font_family()?;
 
                 let face_name = get_string(&font.get_face_names()?)?;
@@ -177,3 +186,8 @@ fn main() -> anyhow::Result<()> {
 
     Ok(())
 }
+
+#[cfg(not(windows))]
+fn main() {
+    eprintln!("This example requires DirectWrite.");
+}
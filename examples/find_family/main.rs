@@ -6,12 +6,12 @@ fn main() -> anyhow::Result<()> {
     let family = collection.find_family("ＭＳ Ｐゴシック").unwrap();
     let family_names = family.get_family_names()?;
     for family_name in &family_names {
-        println!("{}", family_name);
+        println!("{}", family_name?);
     }
     let family = collection.find_family("MS PGothic").unwrap();
     let family_names = family.get_family_names()?;
     for family_name in &family_names {
-        println!("{}", family_name);
+        println!("{}", family_name?);
     }
     Ok(())
 }
@@ -1,5 +1,8 @@
+// DirectWrite を直接使うため Windows でのみ動作する．
+#[cfg(windows)]
 use std::{cmp::Ordering, io::Write};
 
+#[cfg(windows)]
 use windows::{
     core::PCWSTR,
     Win32::Graphics::DirectWrite::{
@@ -9,6 +12,7 @@ use windows::{
 };
 
 // (string, locale) の tuple を返す．
+#[cfg(windows)]
 fn get_strings(strings: &IDWriteLocalizedStrings) -> anyhow::Result<Vec<LocaleName>> {
     let mut v = Vec::new();
     unsafe {
@@ -33,22 +37,26 @@ fn get_strings(strings: &IDWriteLocalizedStrings) -> anyhow::Result<Vec<LocaleNa
     Ok(v)
 }
 
+#[cfg(windows)]
 struct LocaleName {
     name: String,
     locale: String,
 }
 
+#[cfg(windows)]
 impl LocaleName {
     fn to_string(&self) -> String {
         format!("{} ({})", self.name, self.locale)
     }
 }
 
+#[cfg(windows)]
 struct FamilyInfo {
     names: Vec<LocaleName>,
     faces: Vec<Vec<LocaleName>>,
 }
 
+#[cfg(windows)]
 fn sort(s: &mut Vec<LocaleName>) {
     s.sort_by(|a, b| {
         if a.locale == "ja-jp" {
@@ -65,6 +73,7 @@ fn sort(s: &mut Vec<LocaleName>) {
     })
 }
 
+#[cfg(windows)]
 fn join(s: &[LocaleName]) -> String {
     s.iter()
         .map(|s| s.to_string())
@@ -72,6 +81,7 @@ fn join(s: &[LocaleName]) -> String {
         .join(", ")
 }
 
+#[cfg(windows)]
 impl FamilyInfo {
     fn to_string(&self) -> String {
         let names = join(&self.names);
@@ -84,6 +94,7 @@ impl FamilyInfo {
     }
 }
 
+#[cfg(windows)]
 fn run(check_for_updates: bool, filename: &str) -> anyhow::Result<()> {
     unsafe {
         let factory: IDWriteFactory = DWriteCreateFactory(DWRITE_FACTORY_TYPE_ISOLATED)?;
@@ -131,8 +142,14 @@ fn run(check_for_updates: bool, filename: &str) -> anyhow::Result<()> {
     Ok(())
 }
 
+#[cfg(windows)]
 fn main() -> anyhow::Result<()> {
     run(false, "false.txt")?;
     run(true, "true.txt")?;
     Ok(())
 }
+
+#[cfg(not(windows))]
+fn main() {
+    eprintln!("This example requires DirectWrite.");
+}
@@ -1,12 +1,17 @@
+// DirectWrite を直接使うため Windows でのみ動作する．
+#[cfg(windows)]
 use std::{cmp::Ordering, io::Write};
 
+#[cfg(windows)]
 use dwrite_rs::localized_strings::LocalizedString;
+#[cfg(windows)]
 use windows::Win32::Graphics::DirectWrite::{
     DWriteCreateFactory, IDWriteFactory3, IDWriteLocalizedStrings, DWRITE_FACTORY_TYPE_ISOLATED,
     DWRITE_FONT_SIMULATIONS_NONE,
 };
 
 // (string, locale) の tuple を返す．
+#[cfg(windows)]
 fn get_strings(strings: &IDWriteLocalizedStrings) -> anyhow::Result<Vec<LocalizedString>> {
     let mut v = Vec::new();
     unsafe {
@@ -19,11 +24,13 @@ fn get_strings(strings: &IDWriteLocalizedStrings) -> anyhow::Result<Vec<Localize
     Ok(v)
 }
 
+#[cfg(windows)]
 struct FamilyInfo {
     names: Vec<LocalizedString>,
     faces: Vec<Vec<LocalizedString>>,
 }
 
+#[cfg(windows)]
 fn sort(s: &mut Vec<LocalizedString>) {
     s.sort_by(|a, b| {
         if a.locale == "ja-jp" {
@@ -40,6 +47,7 @@ fn sort(s: &mut Vec<LocalizedString>) {
     })
 }
 
+#[cfg(windows)]
 fn join(s: &[LocalizedString]) -> String {
     s.iter()
         .map(|s| s.to_string())
@@ -47,6 +55,7 @@ fn join(s: &[LocalizedString]) -> String {
         .join(", ")
 }
 
+#[cfg(windows)]
 impl FamilyInfo {
     fn to_string(&self) -> String {
         let names = join(&self.names);
@@ -59,6 +68,7 @@ impl FamilyInfo {
     }
 }
 
+#[cfg(windows)]
 fn run(
     include_downloadable_fonts: bool,
     check_for_updates: bool,
@@ -124,6 +134,7 @@ fn run(
     Ok(())
 }
 
+#[cfg(windows)]
 fn main() -> anyhow::Result<()> {
     run(false, false, "false_false.txt")?;
     run(false, true, "false_true.txt")?;
@@ -131,3 +142,8 @@ fn main() -> anyhow::Result<()> {
     run(true, true, "true_true.txt")?;
     Ok(())
 }
+
+#[cfg(not(windows))]
+fn main() {
+    eprintln!("This example requires DirectWrite.");
+}
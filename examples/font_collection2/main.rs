@@ -1,6 +1,10 @@
+// DirectWrite を直接使うため Windows でのみ動作する．
+#[cfg(windows)]
 use std::{cmp::Ordering, io::Write};
 
+#[cfg(windows)]
 use dwrite_rs::localized_strings::LocalizedString;
+#[cfg(windows)]
 use windows::Win32::Graphics::DirectWrite::{
     DWriteCreateFactory, IDWriteFactory6, IDWriteLocalizedStrings, DWRITE_FACTORY_TYPE_ISOLATED,
     DWRITE_FONT_FAMILY_MODEL, DWRITE_FONT_FAMILY_MODEL_TYPOGRAPHIC,
@@ -8,6 +12,7 @@ use windows::Win32::Graphics::DirectWrite::{
 };
 
 // (string, locale) の tuple を返す．
+#[cfg(windows)]
 fn get_strings(strings: &IDWriteLocalizedStrings) -> anyhow::Result<Vec<LocalizedString>> {
     let mut v = Vec::new();
     unsafe {
@@ -20,11 +25,13 @@ fn get_strings(strings: &IDWriteLocalizedStrings) -> anyhow::Result<Vec<Localize
     Ok(v)
 }
 
+#[cfg(windows)]
 struct FamilyInfo {
     names: Vec<LocalizedString>,
     faces: Vec<Vec<LocalizedString>>,
 }
 
+#[cfg(windows)]
 fn sort(s: &mut Vec<LocalizedString>) {
     s.sort_by(|a, b| {
         if a.locale == "ja-jp" {
@@ -41,6 +48,7 @@ fn sort(s: &mut Vec<LocalizedString>) {
     })
 }
 
+#[cfg(windows)]
 fn join(s: &[LocalizedString]) -> String {
     s.iter()
         .map(|s| s.to_string())
@@ -48,6 +56,7 @@ fn join(s: &[LocalizedString]) -> String {
         .join(", ")
 }
 
+#[cfg(windows)]
 impl FamilyInfo {
     fn to_string(&self) -> String {
         let names = join(&self.names);
@@ -60,11 +69,13 @@ impl FamilyInfo {
     }
 }
 
+#[cfg(windows)]
 enum Model {
     Typo,
     WSS,
 }
 
+#[cfg(windows)]
 impl Model {
     fn get_model(&self) -> DWRITE_FONT_FAMILY_MODEL {
         match self {
@@ -74,6 +85,7 @@ impl Model {
     }
 }
 
+#[cfg(windows)]
 fn run(
     include_downloadable_fonts: bool,
     check_for_updates: bool,
@@ -142,6 +154,7 @@ fn run(
     Ok(())
 }
 
+#[cfg(windows)]
 fn main() -> anyhow::Result<()> {
     run(false, false, Model::Typo, "typo.txt")?;
     run(false, true, Model::Typo, "update_typo.txt")?;
@@ -154,3 +167,8 @@ fn main() -> anyhow::Result<()> {
     run(true, true, Model::WSS, "download_update_wss.txt")?;
     Ok(())
 }
+
+#[cfg(not(windows))]
+fn main() {
+    eprintln!("This example requires DirectWrite.");
+}
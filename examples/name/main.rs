@@ -1,11 +1,16 @@
+// DirectWrite を直接使うため Windows でのみ動作する．
+#[cfg(windows)]
 use std::io::Write;
 
+#[cfg(windows)]
 use dwrite_rs::{
     factory::{Factory, FactoryType},
     font::{Font, InformationalStringId, Simulations},
 };
+#[cfg(windows)]
 use gdi_rs::UTF16String;
 
+#[cfg(windows)]
 fn get_string(font: &Font, id: InformationalStringId) -> anyhow::Result<String> {
     let strings = font.get_informational_strings(id)?;
     match strings {
@@ -13,13 +18,14 @@ fn get_string(font: &Font, id: InformationalStringId) -> anyhow::Result<String>
             Some(string) => Ok(string),
             None => match strings.get("en-us")? {
                 Some(string) => Ok(string),
-                None => strings.get_string(0),
+                None => Ok(strings.get_string(0)?),
             },
         },
         None => Ok("".to_string()),
     }
 }
 
+#[cfg(windows)]
 fn main() -> anyhow::Result<()> {
     let factory = Factory::new(FactoryType::Shared)?;
     let gdi_interop = factory.get_gdi_interop()?;
@@ -72,3 +78,8 @@ fn main() -> anyhow::Result<()> {
     }
     Ok(())
 }
+
+#[cfg(not(windows))]
+fn main() {
+    eprintln!("This example requires DirectWrite.");
+}
@@ -1,3 +1,5 @@
+// DirectWrite を直接使うため Windows でのみ動作する．
+#[cfg(windows)]
 use windows::{
     core::w,
     Foundation::Numerics::Matrix3x2,
@@ -29,6 +31,7 @@ use windows::{
     },
 };
 
+#[cfg(windows)]
 fn get_dpi() -> (f32, f32) {
     unsafe {
         let screen = GetDC(None);
@@ -40,6 +43,7 @@ fn get_dpi() -> (f32, f32) {
 }
 
 // https://github.com/microsoft/Windows-classic-samples/blob/ac06e54a15e9a62443e400fffff190fb978ea586/Samples/Win7Samples/multimedia/DirectWrite/HelloWorld/SimpleText.cpp
+#[cfg(windows)]
 fn main() -> anyhow::Result<()> {
     unsafe {
         let (dpi_scale_x, dpi_scale_y) = get_dpi();
@@ -165,6 +169,7 @@ fn main() -> anyhow::Result<()> {
     Ok(())
 }
 
+#[cfg(windows)]
 extern "system" fn wndproc(window: HWND, message: u32, wparam: WPARAM, lparam: LPARAM) -> LRESULT {
     unsafe {
         match message {
@@ -180,3 +185,8 @@ extern "system" fn wndproc(window: HWND, message: u32, wparam: WPARAM, lparam: L
         }
     }
 }
+
+#[cfg(not(windows))]
+fn main() {
+    eprintln!("This example requires DirectWrite.");
+}
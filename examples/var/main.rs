@@ -8,7 +8,7 @@ fn get_string(strings: &LocalizedStrings) -> anyhow::Result<String> {
         Some(string) => Ok(string),
         None => match strings.get("en-us")? {
             Some(string) => Ok(string),
-            None => strings.get_string(0),
+            None => Ok(strings.get_string(0)?),
         },
     }
 }
@@ -17,15 +17,30 @@ fn main() -> anyhow::Result<()> {
     let factory = Factory::new(FactoryType::Shared)?;
     let collection = factory.get_system_font_collection(true)?;
     for family in &collection {
+        let family = family?;
         let family_name = get_string(&family.get_family_names()?)?;
         for font in &family {
+            let font = font?;
             if font.is_simulation() {
                 continue;
             }
 
             if font.is_variable_font()? {
                 let face_name = get_string(&font.get_face_names()?)?;
-                println!("{} {}", family_name, face_name);
+                let axes: Vec<String> = font
+                    .get_variation_axes()?
+                    .iter()
+                    .filter(|axis| !axis.is_hidden())
+                    .map(|axis| axis.to_string())
+                    .collect();
+                let instance_count = font.get_named_instances()?.len();
+                println!(
+                    "{} {}\t{}\t{} instances",
+                    family_name,
+                    face_name,
+                    axes.join(", "),
+                    instance_count
+                );
             }
         }
     }
@@ -0,0 +1,68 @@
+use dwrite_rs::{
+    factory::{Factory, FactoryType},
+    sfnt::{tag_to_string, Sfnt, HEAD},
+    woff,
+    woff2::{self, GLYF, LOCA},
+};
+
+// 展開したフォントのテーブルが元のテーブルと同じバイト列か確かめる．
+// WOFF2 は glyf と loca を復元し直し，head の checksumAdjustment と flags を書き換えるので，
+// これらは存在だけを確かめる．
+fn compare_tables(original: &[u8], decoded: &[u8], rewritten: &[u32]) -> anyhow::Result<()> {
+    let original = Sfnt::parse(original)?;
+    let decoded = Sfnt::parse(decoded)?;
+    anyhow::ensure!(
+        original.tables.len() == decoded.tables.len(),
+        "table count differs ({} and {})",
+        original.tables.len(),
+        decoded.tables.len()
+    );
+    for record in &original.tables {
+        let table = decoded.get_font_table(record.tag)?;
+        let is_same = if rewritten.contains(&record.tag) {
+            table.is_some()
+        } else {
+            original.get_font_table(record.tag)? == table
+        };
+        anyhow::ensure!(
+            is_same,
+            "'{}' table differs after round trip",
+            tag_to_string(record.tag)
+        );
+    }
+    Ok(())
+}
+
+// システムフォントを WOFF と WOFF2 に変換し，展開すると元のテーブルに戻ることを確かめる．
+fn main() -> anyhow::Result<()> {
+    let factory = Factory::new(FactoryType::Shared)?;
+    let collection = factory.get_system_font_collection(false)?;
+    let report = collection.for_each_font(|_, font| {
+        if font.is_simulation() {
+            return Ok(());
+        }
+        let data = font.read_face_data()?;
+        let woff_data = woff::encode(&data)?;
+        let woff2_data = woff2::encode(&data)?;
+        let woff_decoded = woff::decode(&woff_data)?;
+        let woff2_decoded = woff2::decode(&woff2_data)?;
+        let check = compare_tables(&data, &woff_decoded, &[])
+            .and_then(|_| compare_tables(&data, &woff2_decoded, &[GLYF, LOCA, HEAD]));
+        if let Err(e) = check {
+            eprintln!("{}: {}", font.get_filepath()?, e);
+        }
+        println!(
+            "{}\t{}\t{}\t{}\t{}",
+            font.get_filepath()?,
+            font.get_face_index()?,
+            data.len(),
+            woff_data.len(),
+            woff2_data.len()
+        );
+        Ok(())
+    });
+    if !report.is_complete() {
+        eprint!("{}", report);
+    }
+    Ok(())
+}
@@ -0,0 +1,134 @@
+use std::{any::Any, sync::Arc};
+
+use crate::{
+    cmap::{Cmap, CoverageSet, CMAP},
+    error::{Error, Result},
+    family_model::FontFamilyModel,
+    font::{Font, InformationalStringId, Simulations, Stretch, Style, Weight},
+    font_collection::FontCollection,
+    font_family::FontFamily,
+    font_file::FontFileReference,
+    localized_strings::LocalizedStrings,
+};
+
+// Factory などの公開型はこれらの trait を実装したバックエンドを包む．
+// Windows では DirectWrite の COM インターフェースが，それ以外では portable モジュールの型が実装する．
+
+pub trait FactoryBackend {
+    fn get_system_font_collection(&self, check_for_updates: bool) -> Result<FontCollection>;
+
+    // 既定の実装は WSS モデルだけを扱う．
+    fn get_system_font_collection_with_model(
+        &self,
+        _include_downloadable_fonts: bool,
+        model: FontFamilyModel,
+    ) -> Result<FontCollection> {
+        match model {
+            FontFamilyModel::WeightStretchStyle => self.get_system_font_collection(false),
+            FontFamilyModel::Typographic => Err(Error::NotSupported(
+                "the backend does not support the typographic family model".to_string(),
+            )),
+        }
+    }
+
+    fn create_font_collection(
+        &self,
+        _files: &[FontFileReference],
+        _model: FontFamilyModel,
+    ) -> Result<FontCollection> {
+        Err(Error::NotSupported(
+            "the backend cannot create a font collection from files".to_string(),
+        ))
+    }
+
+    fn as_any(&self) -> &dyn Any;
+}
+
+pub trait FontCollectionBackend {
+    fn get_font_family_count(&self) -> u32;
+
+    fn get_font_family(&self, index: u32) -> Result<FontFamily>;
+
+    fn find_family_name(&self, family_name: &str) -> Result<(u32, bool)>;
+
+    fn as_any(&self) -> &dyn Any;
+}
+
+pub trait FontFamilyBackend {
+    fn get_family_names(&self) -> Result<LocalizedStrings>;
+
+    fn get_font_count(&self) -> u32;
+
+    fn get_font(&self, index: u32) -> Result<Font>;
+
+    fn as_any(&self) -> &dyn Any;
+}
+
+pub trait FontBackend {
+    fn get_font_family(&self) -> Result<FontFamily>;
+
+    fn get_simulations(&self) -> Simulations;
+
+    fn get_weight(&self) -> Weight;
+
+    fn get_style(&self) -> Style;
+
+    fn get_stretch(&self) -> Stretch;
+
+    fn get_face_names(&self) -> Result<LocalizedStrings>;
+
+    fn get_informational_strings(
+        &self,
+        informational_string_id: InformationalStringId,
+    ) -> Result<Option<LocalizedStrings>>;
+
+    fn get_filepath(&self) -> Result<String>;
+
+    // 既定の実装は get_filepath のローカルファイルを返す．
+    fn get_file_reference(&self) -> Result<FontFileReference> {
+        Ok(FontFileReference::Local(self.get_filepath()?.into()))
+    }
+
+    // コレクション (.ttc, .otc) 内のフェイスの位置．単体のフォントファイルでは 0．
+    fn get_face_index(&self) -> Result<u32> {
+        Ok(0)
+    }
+
+    fn is_variable_font(&self) -> Result<bool>;
+
+    // テーブルが存在しない場合は None を返す．
+    fn get_font_table(&self, tag: u32) -> Result<Option<Vec<u8>>>;
+
+    // 既定の実装は呼ぶたびに cmap テーブルを読む．フォントごとに結果を持っておけるバックエンドは上書きする．
+    fn get_cmap(&self) -> Result<Option<Arc<Cmap>>> {
+        match self.get_font_table(CMAP)? {
+            Some(table) => Ok(Some(Arc::new(Cmap::parse(&table)?))),
+            None => Ok(None),
+        }
+    }
+
+    fn has_character(&self, c: u32) -> Result<bool> {
+        Ok(self.get_cmap()?.is_some_and(|cmap| cmap.has_character(c)))
+    }
+
+    fn coverage(&self) -> Result<CoverageSet> {
+        match self.get_cmap()? {
+            Some(cmap) => Ok(cmap.coverage()),
+            None => Ok(CoverageSet::new()),
+        }
+    }
+
+    fn as_any(&self) -> &dyn Any;
+}
+
+pub trait LocalizedStringsBackend {
+    fn get_count(&self) -> u32;
+
+    fn get_string(&self, index: u32) -> Result<String>;
+
+    fn get_locale_name(&self, index: u32) -> Result<String>;
+
+    fn find_locale_name(&self, locale: &str) -> Result<Option<u32>>;
+
+    fn as_any(&self) -> &dyn Any;
+}
@@ -0,0 +1,818 @@
+use crate::{
+    error::Result,
+    name::{char_to_mac_roman, mac_roman_to_char},
+    sfnt::{make_tag, Reader, Sfnt},
+    woff,
+};
+
+// cmap テーブル (format 0, 2, 4, 6, 10, 12, 13, 14) と文字のカバレッジ．
+
+pub const CMAP: u32 = make_tag(b"cmap");
+
+// DirectWrite と同じく，上にあるものほど優先して使う (platform ID, encoding ID)．
+const PREFERRED_ENCODINGS: [(u16, u16); 10] = [
+    (3, 10),
+    (0, 6),
+    (0, 4),
+    (3, 1),
+    (0, 3),
+    (0, 2),
+    (0, 1),
+    (0, 0),
+    (3, 0),
+    (1, 0),
+];
+
+// 文字の集合を，重ならない範囲 (両端を含む) の昇順の列で表す．
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct CoverageSet {
+    ranges: Vec<(u32, u32)>,
+}
+
+impl CoverageSet {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // 範囲は順不同で重なっていてもよい．
+    pub fn from_ranges<I: IntoIterator<Item = (u32, u32)>>(ranges: I) -> Self {
+        let mut ranges: Vec<(u32, u32)> = ranges
+            .into_iter()
+            .filter(|(start, end)| start <= end)
+            .collect();
+        ranges.sort_unstable();
+        let mut merged: Vec<(u32, u32)> = Vec::with_capacity(ranges.len());
+        for (start, end) in ranges {
+            match merged.last_mut() {
+                Some(last) if start <= last.1.saturating_add(1) => last.1 = last.1.max(end),
+                _ => merged.push((start, end)),
+            }
+        }
+        Self { ranges: merged }
+    }
+
+    pub fn ranges(&self) -> &[(u32, u32)] {
+        &self.ranges
+    }
+
+    pub fn contains(&self, c: u32) -> bool {
+        let index = self.ranges.partition_point(|&(_, end)| end < c);
+        match self.ranges.get(index) {
+            Some(&(start, _)) => start <= c,
+            None => false,
+        }
+    }
+
+    // 含まれる文字の数．
+    pub fn len(&self) -> usize {
+        self.ranges
+            .iter()
+            .map(|(start, end)| (end - start) as usize + 1)
+            .sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.ranges.is_empty()
+    }
+
+    pub fn union(&self, other: &Self) -> Self {
+        Self::from_ranges(self.ranges.iter().chain(&other.ranges).copied())
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = u32> + '_ {
+        self.ranges.iter().flat_map(|&(start, end)| start..=end)
+    }
+}
+
+// 連続した文字を集めて範囲にする．
+#[derive(Default)]
+struct RangeBuilder {
+    ranges: Vec<(u32, u32)>,
+}
+
+impl RangeBuilder {
+    fn push(&mut self, c: u32) {
+        match self.ranges.last_mut() {
+            Some(last) if last.1.checked_add(1) == Some(c) => last.1 = c,
+            _ => self.ranges.push((c, c)),
+        }
+    }
+
+    fn push_range(&mut self, start: u32, end: u32) {
+        self.ranges.push((start, end));
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct SubHeader {
+    pub first_code: u16,
+    pub entry_count: u16,
+    pub id_delta: i16,
+    // glyph_id_array の中の位置．
+    pub glyph_index: usize,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Segment {
+    pub start_code: u16,
+    pub end_code: u16,
+    pub id_delta: i16,
+    // None なら id_delta だけを使う．それ以外は glyph_id_array の中の位置．
+    pub glyph_index: Option<usize>,
+}
+
+// (開始文字, 終了文字, 開始グリフ)．format 13 では全部の文字が同じグリフになる．
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct SequentialMapGroup {
+    pub start_char_code: u32,
+    pub end_char_code: u32,
+    pub glyph_id: u32,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct VariationSelector {
+    pub var_selector: u32,
+    // 基本の cmap と同じグリフを使う異体字シーケンス．(開始文字, 追加の数)．
+    pub default_uvs: Vec<(u32, u8)>,
+    // (文字, グリフ)．
+    pub non_default_uvs: Vec<(u32, u16)>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum CmapSubtable {
+    Format0 {
+        language: u16,
+        glyph_id_array: Vec<u8>,
+    },
+    Format2 {
+        language: u16,
+        sub_header_keys: Vec<u16>,
+        sub_headers: Vec<SubHeader>,
+        glyph_id_array: Vec<u16>,
+    },
+    Format4 {
+        language: u16,
+        segments: Vec<Segment>,
+        glyph_id_array: Vec<u16>,
+    },
+    Format6 {
+        language: u16,
+        first_code: u16,
+        glyph_id_array: Vec<u16>,
+    },
+    Format10 {
+        language: u32,
+        start_char_code: u32,
+        glyph_id_array: Vec<u16>,
+    },
+    Format12 {
+        language: u32,
+        groups: Vec<SequentialMapGroup>,
+    },
+    Format13 {
+        language: u32,
+        groups: Vec<SequentialMapGroup>,
+    },
+    Format14 {
+        var_selectors: Vec<VariationSelector>,
+    },
+    // format 8 など，読まないフォーマット．
+    Unsupported {
+        format: u16,
+    },
+}
+
+fn read_u16_array(reader: &mut Reader, count: usize) -> Result<Vec<u16>> {
+    (0..count).map(|_| reader.read_u16()).collect()
+}
+
+fn read_groups(reader: &mut Reader) -> Result<Vec<SequentialMapGroup>> {
+    let num_groups = reader.read_u32()?;
+    (0..num_groups)
+        .map(|_| {
+            Ok(SequentialMapGroup {
+                start_char_code: reader.read_u32()?,
+                end_char_code: reader.read_u32()?,
+                glyph_id: reader.read_u32()?,
+            })
+        })
+        .collect()
+}
+
+impl CmapSubtable {
+    // data はサブテーブルの先頭から cmap テーブルの最後まで．
+    pub fn parse(data: &[u8]) -> Result<Self> {
+        let mut reader = Reader::table(CMAP, data);
+        let format = reader.read_u16()?;
+        match format {
+            0 => {
+                // length
+                reader.skip(2)?;
+                let language = reader.read_u16()?;
+                let glyph_id_array = reader.read_bytes(256)?.to_vec();
+                Ok(Self::Format0 {
+                    language,
+                    glyph_id_array,
+                })
+            }
+            2 => {
+                let length = reader.read_u16()? as usize;
+                let language = reader.read_u16()?;
+                let sub_header_keys = read_u16_array(&mut reader, 256)?;
+                let sub_header_count =
+                    sub_header_keys.iter().map(|key| key / 8).max().unwrap_or(0) as usize + 1;
+                let sub_headers_offset = reader.offset();
+                let glyph_id_array_offset = sub_headers_offset + sub_header_count * 8;
+                let mut sub_headers = Vec::with_capacity(sub_header_count);
+                for _ in 0..sub_header_count {
+                    let first_code = reader.read_u16()?;
+                    let entry_count = reader.read_u16()?;
+                    let id_delta = reader.read_i16()?;
+                    // idRangeOffset はこのフィールド自身の位置からのオフセット．
+                    let id_range_offset = reader.offset() + reader.read_u16()? as usize;
+                    let glyph_index = id_range_offset
+                        .checked_sub(glyph_id_array_offset)
+                        .ok_or_else(|| reader.error("invalid idRangeOffset in format 2"))?
+                        / 2;
+                    sub_headers.push(SubHeader {
+                        first_code,
+                        entry_count,
+                        id_delta,
+                        glyph_index,
+                    });
+                }
+                let end = length.clamp(glyph_id_array_offset, data.len());
+                let glyph_id_array =
+                    read_u16_array(&mut reader, (end - glyph_id_array_offset) / 2)?;
+                Ok(Self::Format2 {
+                    language,
+                    sub_header_keys,
+                    sub_headers,
+                    glyph_id_array,
+                })
+            }
+            4 => {
+                let length = reader.read_u16()? as usize;
+                let language = reader.read_u16()?;
+                let seg_count = reader.read_u16()? as usize / 2;
+                // searchRange, entrySelector, rangeShift
+                reader.skip(6)?;
+                let end_codes = read_u16_array(&mut reader, seg_count)?;
+                // reservedPad
+                reader.skip(2)?;
+                let start_codes = read_u16_array(&mut reader, seg_count)?;
+                let id_deltas = read_u16_array(&mut reader, seg_count)?;
+                let id_range_offsets = read_u16_array(&mut reader, seg_count)?;
+                let segments = (0..seg_count)
+                    .map(|i| Segment {
+                        start_code: start_codes[i],
+                        end_code: end_codes[i],
+                        id_delta: id_deltas[i] as i16,
+                        // idRangeOffset は idRangeOffset[i] の位置からのオフセットなので，
+                        // glyphIdArray の先頭からの位置に直す．
+                        glyph_index: match id_range_offsets[i] {
+                            0 => None,
+                            offset => (offset as usize / 2 + i).checked_sub(seg_count),
+                        },
+                    })
+                    .collect();
+                // length が壊れているフォントがあるので，テーブルの最後までを上限にする．
+                let glyph_id_array_offset = reader.offset();
+                let end = if length > glyph_id_array_offset {
+                    length.min(data.len())
+                } else {
+                    data.len()
+                };
+                let glyph_id_array =
+                    read_u16_array(&mut reader, (end - glyph_id_array_offset) / 2)?;
+                Ok(Self::Format4 {
+                    language,
+                    segments,
+                    glyph_id_array,
+                })
+            }
+            6 => {
+                // length
+                reader.skip(2)?;
+                let language = reader.read_u16()?;
+                let first_code = reader.read_u16()?;
+                let entry_count = reader.read_u16()? as usize;
+                let glyph_id_array = read_u16_array(&mut reader, entry_count)?;
+                Ok(Self::Format6 {
+                    language,
+                    first_code,
+                    glyph_id_array,
+                })
+            }
+            10 => {
+                // reserved, length
+                reader.skip(6)?;
+                let language = reader.read_u32()?;
+                let start_char_code = reader.read_u32()?;
+                let num_chars = reader.read_u32()? as usize;
+                let glyph_id_array = read_u16_array(&mut reader, num_chars)?;
+                Ok(Self::Format10 {
+                    language,
+                    start_char_code,
+                    glyph_id_array,
+                })
+            }
+            12 | 13 => {
+                // reserved, length
+                reader.skip(6)?;
+                let language = reader.read_u32()?;
+                let groups = read_groups(&mut reader)?;
+                if format == 12 {
+                    Ok(Self::Format12 { language, groups })
+                } else {
+                    Ok(Self::Format13 { language, groups })
+                }
+            }
+            14 => {
+                // length
+                reader.skip(4)?;
+                let num_var_selector_records = reader.read_u32()?;
+                let mut var_selectors = vec![];
+                for _ in 0..num_var_selector_records {
+                    let var_selector = reader.read_u24()?;
+                    let default_uvs_offset = reader.read_u32()? as usize;
+                    let non_default_uvs_offset = reader.read_u32()? as usize;
+                    let mut default_uvs = vec![];
+                    if default_uvs_offset != 0 {
+                        let mut reader = reader.jump(default_uvs_offset)?;
+                        for _ in 0..reader.read_u32()? {
+                            default_uvs.push((reader.read_u24()?, reader.read_u8()?));
+                        }
+                    }
+                    let mut non_default_uvs = vec![];
+                    if non_default_uvs_offset != 0 {
+                        let mut reader = reader.jump(non_default_uvs_offset)?;
+                        for _ in 0..reader.read_u32()? {
+                            non_default_uvs.push((reader.read_u24()?, reader.read_u16()?));
+                        }
+                    }
+                    var_selectors.push(VariationSelector {
+                        var_selector,
+                        default_uvs,
+                        non_default_uvs,
+                    });
+                }
+                Ok(Self::Format14 { var_selectors })
+            }
+            _ => Ok(Self::Unsupported { format }),
+        }
+    }
+
+    pub fn format(&self) -> u16 {
+        match self {
+            Self::Format0 { .. } => 0,
+            Self::Format2 { .. } => 2,
+            Self::Format4 { .. } => 4,
+            Self::Format6 { .. } => 6,
+            Self::Format10 { .. } => 10,
+            Self::Format12 { .. } => 12,
+            Self::Format13 { .. } => 13,
+            Self::Format14 { .. } => 14,
+            Self::Unsupported { format } => *format,
+        }
+    }
+
+    // サブテーブルのエンコーディングの文字コードからグリフを引く．0 (.notdef) は None．
+    pub fn get_glyph(&self, code: u32) -> Option<u16> {
+        let glyph = match self {
+            Self::Format0 { glyph_id_array, .. } => *glyph_id_array.get(code as usize)? as u16,
+            Self::Format2 {
+                sub_header_keys,
+                sub_headers,
+                glyph_id_array,
+                ..
+            } => {
+                let (key, low) = if code < 0x100 {
+                    // 1 バイト文字は subHeader 0 を使う．
+                    if sub_header_keys[code as usize] != 0 {
+                        return None;
+                    }
+                    (0, code as u16)
+                } else if code < 0x10000 {
+                    let key = sub_header_keys[(code >> 8) as usize] / 8;
+                    if key == 0 {
+                        return None;
+                    }
+                    (key, (code & 0xFF) as u16)
+                } else {
+                    return None;
+                };
+                let sub_header = sub_headers.get(key as usize)?;
+                let index = low.checked_sub(sub_header.first_code)?;
+                if index >= sub_header.entry_count {
+                    return None;
+                }
+                match *glyph_id_array.get(sub_header.glyph_index + index as usize)? {
+                    0 => 0,
+                    glyph => glyph.wrapping_add(sub_header.id_delta as u16),
+                }
+            }
+            Self::Format4 {
+                segments,
+                glyph_id_array,
+                ..
+            } => {
+                if code > 0xFFFF {
+                    return None;
+                }
+                let code = code as u16;
+                let index = segments.partition_point(|segment| segment.end_code < code);
+                let segment = segments.get(index)?;
+                if code < segment.start_code {
+                    return None;
+                }
+                match segment.glyph_index {
+                    None => code.wrapping_add(segment.id_delta as u16),
+                    Some(glyph_index) => {
+                        let index = glyph_index + (code - segment.start_code) as usize;
+                        match *glyph_id_array.get(index)? {
+                            0 => 0,
+                            glyph => glyph.wrapping_add(segment.id_delta as u16),
+                        }
+                    }
+                }
+            }
+            Self::Format6 {
+                first_code,
+                glyph_id_array,
+                ..
+            } => {
+                let index = code.checked_sub(*first_code as u32)?;
+                *glyph_id_array.get(index as usize)?
+            }
+            Self::Format10 {
+                start_char_code,
+                glyph_id_array,
+                ..
+            } => {
+                let index = code.checked_sub(*start_char_code)?;
+                *glyph_id_array.get(index as usize)?
+            }
+            Self::Format12 { groups, .. } | Self::Format13 { groups, .. } => {
+                let index = groups.partition_point(|group| group.end_char_code < code);
+                let group = groups.get(index)?;
+                if code < group.start_char_code {
+                    return None;
+                }
+                let glyph = if matches!(self, Self::Format12 { .. }) {
+                    group.glyph_id.checked_add(code - group.start_char_code)?
+                } else {
+                    group.glyph_id
+                };
+                u16::try_from(glyph).ok()?
+            }
+            Self::Format14 { .. } | Self::Unsupported { .. } => return None,
+        };
+        if glyph == 0 {
+            None
+        } else {
+            Some(glyph)
+        }
+    }
+
+    // グリフが割り当てられている文字コードの集合．
+    pub fn coverage(&self) -> CoverageSet {
+        let mut builder = RangeBuilder::default();
+        match self {
+            Self::Format0 { .. } => {
+                for code in 0..0x100 {
+                    if self.get_glyph(code).is_some() {
+                        builder.push(code);
+                    }
+                }
+            }
+            Self::Format2 {
+                sub_header_keys, ..
+            } => {
+                for high in 0..0x100_u32 {
+                    let codes = if sub_header_keys[high as usize] == 0 {
+                        high..high + 1
+                    } else {
+                        high << 8..(high + 1) << 8
+                    };
+                    for code in codes {
+                        if self.get_glyph(code).is_some() {
+                            builder.push(code);
+                        }
+                    }
+                }
+            }
+            Self::Format4 { segments, .. } => {
+                for segment in segments {
+                    for code in segment.start_code..=segment.end_code {
+                        if self.get_glyph(code as u32).is_some() {
+                            builder.push(code as u32);
+                        }
+                    }
+                }
+            }
+            Self::Format6 {
+                first_code,
+                glyph_id_array,
+                ..
+            } => push_array(&mut builder, *first_code as u32, glyph_id_array),
+            Self::Format10 {
+                start_char_code,
+                glyph_id_array,
+                ..
+            } => push_array(&mut builder, *start_char_code, glyph_id_array),
+            Self::Format12 { groups, .. } => {
+                for group in groups {
+                    // get_glyph と同じく，.notdef になる先頭の文字と，グリフ ID が 0xFFFF を超える文字は除く．
+                    let Some(max_offset) = 0xFFFF_u32.checked_sub(group.glyph_id) else {
+                        continue;
+                    };
+                    let start = if group.glyph_id == 0 {
+                        group.start_char_code.saturating_add(1)
+                    } else {
+                        group.start_char_code
+                    };
+                    let end = group
+                        .end_char_code
+                        .min(group.start_char_code.saturating_add(max_offset));
+                    builder.push_range(start, end);
+                }
+            }
+            Self::Format13 { groups, .. } => {
+                for group in groups
+                    .iter()
+                    .filter(|group| group.glyph_id != 0 && group.glyph_id <= 0xFFFF)
+                {
+                    builder.push_range(group.start_char_code, group.end_char_code);
+                }
+            }
+            Self::Format14 { .. } | Self::Unsupported { .. } => {}
+        }
+        CoverageSet::from_ranges(builder.ranges)
+    }
+}
+
+fn push_array(builder: &mut RangeBuilder, first_code: u32, glyph_id_array: &[u16]) {
+    for (i, &glyph) in glyph_id_array.iter().enumerate() {
+        // 壊れたフォントでは文字コードが u32 を超えることがある．
+        let Some(code) = first_code.checked_add(i as u32) else {
+            break;
+        };
+        if glyph != 0 {
+            builder.push(code);
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct EncodingRecord {
+    pub platform_id: u16,
+    pub encoding_id: u16,
+    // Cmap::subtables の位置．同じオフセットを指すレコードは同じサブテーブルを共有する．
+    pub subtable_index: usize,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Cmap {
+    pub version: u16,
+    pub encoding_records: Vec<EncodingRecord>,
+    pub subtables: Vec<CmapSubtable>,
+}
+
+impl Cmap {
+    pub fn parse(data: &[u8]) -> Result<Self> {
+        let mut reader = Reader::table(CMAP, data);
+        let version = reader.read_u16()?;
+        let num_tables = reader.read_u16()?;
+        let mut offsets: Vec<u32> = vec![];
+        let mut subtables = vec![];
+        let mut encoding_records = Vec::with_capacity(num_tables as usize);
+        for _ in 0..num_tables {
+            let platform_id = reader.read_u16()?;
+            let encoding_id = reader.read_u16()?;
+            let offset = reader.read_u32()?;
+            let subtable_index = match offsets.iter().position(|&o| o == offset) {
+                Some(index) => index,
+                None => {
+                    // 読めないサブテーブルのレコードは無いものとして，他のサブテーブルを使う．
+                    let subtable = reader
+                        .jump(offset as usize)
+                        .and_then(|subtable| CmapSubtable::parse(&data[subtable.offset()..]));
+                    let Ok(subtable) = subtable else {
+                        continue;
+                    };
+                    subtables.push(subtable);
+                    offsets.push(offset);
+                    subtables.len() - 1
+                }
+            };
+            encoding_records.push(EncodingRecord {
+                platform_id,
+                encoding_id,
+                subtable_index,
+            });
+        }
+        Ok(Self {
+            version,
+            encoding_records,
+            subtables,
+        })
+    }
+
+    pub fn from_font_data(data: &[u8]) -> Result<Option<Self>> {
+        let data = woff::decode_font_data(data)?;
+        match Sfnt::parse(&data)?.get_font_table(CMAP)? {
+            Some(table) => Ok(Some(Self::parse(table)?)),
+            None => Ok(None),
+        }
+    }
+
+    pub fn get_subtable(&self, platform_id: u16, encoding_id: u16) -> Option<&CmapSubtable> {
+        self.encoding_records
+            .iter()
+            .find(|record| record.platform_id == platform_id && record.encoding_id == encoding_id)
+            .map(|record| &self.subtables[record.subtable_index])
+    }
+
+    // 文字の検索に使うサブテーブルと，その (platform ID, encoding ID)．
+    pub fn get_best_subtable(&self) -> Option<((u16, u16), &CmapSubtable)> {
+        PREFERRED_ENCODINGS
+            .iter()
+            .find_map(|&(platform_id, encoding_id)| {
+                let subtable = self.get_subtable(platform_id, encoding_id)?;
+                if matches!(
+                    subtable,
+                    CmapSubtable::Format14 { .. } | CmapSubtable::Unsupported { .. }
+                ) {
+                    None
+                } else {
+                    Some(((platform_id, encoding_id), subtable))
+                }
+            })
+    }
+
+    // シンボルフォント (3, 0) では U+0020..=U+00FF を U+F020..=U+F0FF としても探す．
+    pub fn get_glyph(&self, c: u32) -> Option<u16> {
+        let (encoding, subtable) = self.get_best_subtable()?;
+        match encoding {
+            (3, 0) => subtable.get_glyph(c).or_else(|| match c {
+                0x20..=0xFF => subtable.get_glyph(c + 0xF000),
+                _ => None,
+            }),
+            (1, 0) => subtable.get_glyph(char_to_mac_roman(char::from_u32(c)?)? as u32),
+            _ => subtable.get_glyph(c),
+        }
+    }
+
+    pub fn has_character(&self, c: u32) -> bool {
+        self.get_glyph(c).is_some()
+    }
+
+    // Unicode のコードポイントの集合．get_glyph と同じ変換をする．
+    pub fn coverage(&self) -> CoverageSet {
+        let Some((encoding, subtable)) = self.get_best_subtable() else {
+            return CoverageSet::new();
+        };
+        let coverage = subtable.coverage();
+        match encoding {
+            (3, 0) => {
+                let symbols = coverage
+                    .ranges()
+                    .iter()
+                    .filter_map(|&(start, end)| {
+                        let start = start.max(0xF020);
+                        let end = end.min(0xF0FF);
+                        (start <= end).then(|| (start - 0xF000, end - 0xF000))
+                    })
+                    .collect::<Vec<_>>();
+                coverage.union(&CoverageSet::from_ranges(symbols))
+            }
+            // Mac Roman は 1 バイトなので，format 6 などの 0xFF を超える文字コードは使えない．
+            (1, 0) => CoverageSet::from_ranges(
+                coverage
+                    .iter()
+                    .filter_map(|code| u8::try_from(code).ok())
+                    .map(|code| mac_roman_to_char(code) as u32)
+                    .map(|c| (c, c)),
+            ),
+            _ => coverage,
+        }
+    }
+
+    // 異体字セレクタ付きのグリフ．format 14 が無い，または対応していないシーケンスは None．
+    pub fn get_variant_glyph(&self, c: u32, var_selector: u32) -> Option<u16> {
+        let CmapSubtable::Format14 { var_selectors } = self.get_subtable(0, 5)? else {
+            return None;
+        };
+        let record = var_selectors
+            .iter()
+            .find(|record| record.var_selector == var_selector)?;
+        if let Some(&(_, glyph)) = record.non_default_uvs.iter().find(|(u, _)| *u == c) {
+            return Some(glyph);
+        }
+        let is_default = record
+            .default_uvs
+            .iter()
+            .any(|&(start, count)| start <= c && c <= start + count as u32);
+        if is_default {
+            self.get_glyph(c)
+        } else {
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn group(start_char_code: u32, end_char_code: u32, glyph_id: u32) -> SequentialMapGroup {
+        SequentialMapGroup {
+            start_char_code,
+            end_char_code,
+            glyph_id,
+        }
+    }
+
+    // 壊れたフォントでもオーバーフローせず，coverage と get_glyph が一致する．
+    #[test]
+    fn format12_glyph_overflow() {
+        let subtable = CmapSubtable::Format12 {
+            language: 0,
+            groups: vec![
+                group(0x20, 0x30, 0xFFF8),
+                group(0x40, 0x50, 0x10000),
+                group(0x60, u32::MAX, u32::MAX - 4),
+            ],
+        };
+        assert_eq!(subtable.get_glyph(0x27), Some(0xFFFF));
+        assert_eq!(subtable.get_glyph(0x28), None);
+        assert_eq!(subtable.get_glyph(0x40), None);
+        assert_eq!(subtable.get_glyph(0x70), None);
+        let coverage = subtable.coverage();
+        assert_eq!(coverage.ranges(), &[(0x20, 0x27)]);
+        for code in (0..0x80).chain(u32::MAX - 8..=u32::MAX) {
+            assert_eq!(coverage.contains(code), subtable.get_glyph(code).is_some());
+        }
+    }
+
+    #[test]
+    fn format10_code_overflow() {
+        let subtable = CmapSubtable::Format10 {
+            language: 0,
+            start_char_code: u32::MAX - 1,
+            glyph_id_array: vec![1, 2, 3, 4],
+        };
+        assert_eq!(subtable.get_glyph(u32::MAX), Some(2));
+        assert_eq!(subtable.coverage().ranges(), &[(u32::MAX - 1, u32::MAX)]);
+    }
+
+    // Mac Roman の format 6 で 0xFF を超える文字コードは，別の文字として数えない．
+    #[test]
+    fn mac_roman_coverage() {
+        let cmap = Cmap {
+            version: 0,
+            encoding_records: vec![EncodingRecord {
+                platform_id: 1,
+                encoding_id: 0,
+                subtable_index: 0,
+            }],
+            subtables: vec![CmapSubtable::Format6 {
+                language: 0,
+                first_code: 0xFE,
+                glyph_id_array: vec![1, 2, 3, 4],
+            }],
+        };
+        let coverage = cmap.coverage();
+        assert_eq!(coverage.len(), 2);
+        assert!(!coverage.contains(0x00) && !coverage.contains(0x01));
+        for c in coverage.iter() {
+            assert!(cmap.has_character(c));
+        }
+    }
+
+    // 読めないサブテーブルのレコードだけを無視する．
+    #[test]
+    fn skip_broken_subtables() {
+        let mut data = vec![];
+        for value in [0_u16, 3, 3, 10, 0, 28, 3, 1, 0, 48, 0, 3, 0x7FFF, 0] {
+            data.extend_from_slice(&value.to_be_bytes());
+        }
+        // 100 グループあるはずの format 12．
+        for value in [12_u16, 0, 0, 100, 0, 0, 0, 100, 0, 0] {
+            data.extend_from_slice(&value.to_be_bytes());
+        }
+        // 'A' と 'B' の format 6．
+        for value in [6_u16, 14, 0, 0x41, 2, 1, 2] {
+            data.extend_from_slice(&value.to_be_bytes());
+        }
+        let cmap = Cmap::parse(&data).unwrap();
+        assert_eq!(cmap.encoding_records.len(), 1);
+        assert_eq!(cmap.subtables.len(), 1);
+        assert_eq!(cmap.get_best_subtable().unwrap().0, (3, 1));
+        assert_eq!(cmap.get_glyph(0x42), Some(2));
+        assert_eq!(cmap.coverage().ranges(), &[(0x41, 0x42)]);
+        // ヘッダーが壊れている場合はエラーにする．
+        assert!(Cmap::parse(&data[..10]).is_err());
+    }
+}
@@ -0,0 +1,242 @@
+use core::fmt;
+use std::path::PathBuf;
+
+use crate::sfnt::tag_to_string;
+
+// DirectWrite の HRESULT．
+pub const DWRITE_E_FILEFORMAT: i32 = 0x88985000_u32 as i32;
+pub const DWRITE_E_UNEXPECTED: i32 = 0x88985001_u32 as i32;
+pub const DWRITE_E_NOFONT: i32 = 0x88985002_u32 as i32;
+pub const DWRITE_E_FILENOTFOUND: i32 = 0x88985003_u32 as i32;
+pub const DWRITE_E_FILEACCESS: i32 = 0x88985004_u32 as i32;
+pub const DWRITE_E_FONTCOLLECTIONOBSOLETE: i32 = 0x88985005_u32 as i32;
+pub const DWRITE_E_ALREADYREGISTERED: i32 = 0x88985006_u32 as i32;
+pub const DWRITE_E_UNSUPPORTEDOPERATION: i32 = 0x88985009_u32 as i32;
+pub const DWRITE_E_REMOTEFONT: i32 = 0x8898500D_u32 as i32;
+pub const DWRITE_E_DOWNLOADCANCELLED: i32 = 0x8898500E_u32 as i32;
+pub const DWRITE_E_DOWNLOADFAILED: i32 = 0x8898500F_u32 as i32;
+pub const DWRITE_E_TOOMANYDOWNLOADS: i32 = 0x88985010_u32 as i32;
+
+pub const E_NOTIMPL: i32 = 0x80004001_u32 as i32;
+pub const E_POINTER: i32 = 0x80004003_u32 as i32;
+pub const E_INVALIDARG: i32 = 0x80070057_u32 as i32;
+// HRESULT_FROM_WIN32(ERROR_FILE_NOT_FOUND), HRESULT_FROM_WIN32(ERROR_PATH_NOT_FOUND)
+pub const E_FILE_NOT_FOUND: i32 = 0x80070002_u32 as i32;
+pub const E_PATH_NOT_FOUND: i32 = 0x80070003_u32 as i32;
+// HRESULT_FROM_WIN32(ERROR_ACCESS_DENIED)
+pub const E_ACCESSDENIED: i32 = 0x80070005_u32 as i32;
+
+#[derive(Debug)]
+pub enum Error {
+    // フォントリソースがリモートにあり，まだダウンロードされていない (0x8898500D)．
+    RemoteFont,
+    // フォントファイルが見つからない，または読めない．
+    // DirectWrite から返された場合は path も source も分からないことがある．
+    FileAccess {
+        path: Option<PathBuf>,
+        source: std::io::Error,
+    },
+    // TrueType/OpenType として読めない形式のファイル．
+    UnsupportedFileType(String),
+    // テーブルディレクトリなどフォントファイル自体が壊れている．
+    InvalidFont(String),
+    MissingTable(u32),
+    MalformedTable {
+        tag: u32,
+        reason: String,
+    },
+    StringConversion(String),
+    IndexOutOfRange {
+        index: u32,
+        count: u32,
+    },
+    // 使っているバックエンドでは使えない機能．
+    NotSupported(String),
+    // スナップショットなどテキスト形式のデータを読めない．
+    Parse(String),
+    // 上記以外の HRESULT をそのまま返す．
+    HResult {
+        code: i32,
+        message: String,
+    },
+}
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+impl Error {
+    pub fn file_access<P: Into<PathBuf>>(path: P, source: std::io::Error) -> Self {
+        Self::FileAccess {
+            path: Some(path.into()),
+            source,
+        }
+    }
+
+    pub fn malformed_table<S: Into<String>>(tag: u32, reason: S) -> Self {
+        Self::MalformedTable {
+            tag,
+            reason: reason.into(),
+        }
+    }
+
+    // HRESULT を対応するエラーに変換する．Windows 以外でも使える．
+    pub fn from_hresult(code: i32, message: String) -> Self {
+        match code {
+            DWRITE_E_REMOTEFONT => Self::RemoteFont,
+            DWRITE_E_FILENOTFOUND | E_FILE_NOT_FOUND | E_PATH_NOT_FOUND => Self::FileAccess {
+                path: None,
+                source: std::io::Error::new(std::io::ErrorKind::NotFound, message),
+            },
+            E_ACCESSDENIED => Self::FileAccess {
+                path: None,
+                source: std::io::Error::new(std::io::ErrorKind::PermissionDenied, message),
+            },
+            DWRITE_E_FILEACCESS => Self::FileAccess {
+                path: None,
+                source: std::io::Error::other(message),
+            },
+            DWRITE_E_FILEFORMAT => Self::InvalidFont(message),
+            _ => Self::HResult { code, message },
+        }
+    }
+
+    // DirectWrite が返すのと同じ HRESULT．
+    pub fn hresult(&self) -> i32 {
+        match self {
+            Self::RemoteFont => DWRITE_E_REMOTEFONT,
+            Self::FileAccess { source, .. } => match source.kind() {
+                std::io::ErrorKind::NotFound => DWRITE_E_FILENOTFOUND,
+                _ => DWRITE_E_FILEACCESS,
+            },
+            Self::UnsupportedFileType(_)
+            | Self::InvalidFont(_)
+            | Self::MissingTable(_)
+            | Self::MalformedTable { .. } => DWRITE_E_FILEFORMAT,
+            Self::StringConversion(_) | Self::IndexOutOfRange { .. } | Self::Parse(_) => {
+                E_INVALIDARG
+            }
+            Self::NotSupported(_) => E_NOTIMPL,
+            Self::HResult { code, .. } => *code,
+        }
+    }
+
+    pub fn is_remote_font(&self) -> bool {
+        matches!(self, Self::RemoteFont)
+    }
+
+    pub fn is_not_found(&self) -> bool {
+        match self {
+            Self::FileAccess { source, .. } => source.kind() == std::io::ErrorKind::NotFound,
+            _ => false,
+        }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::RemoteFont => write!(
+                f,
+                "the font resource is remote and has not been downloaded (0x8898500D)"
+            ),
+            Self::FileAccess {
+                path: Some(path),
+                source,
+            } => write!(f, "cannot access {}: {}", path.display(), source),
+            Self::FileAccess { path: None, source } => {
+                write!(f, "cannot access the font file: {}", source)
+            }
+            Self::UnsupportedFileType(reason) => write!(f, "unsupported file type: {}", reason),
+            Self::InvalidFont(reason) => write!(f, "invalid font: {}", reason),
+            Self::MissingTable(tag) => write!(f, "'{}' table is missing", tag_to_string(*tag)),
+            Self::MalformedTable { tag, reason } => {
+                write!(f, "malformed '{}' table: {}", tag_to_string(*tag), reason)
+            }
+            Self::StringConversion(reason) => write!(f, "string conversion failed: {}", reason),
+            Self::IndexOutOfRange { index, count } => {
+                write!(f, "index {} is out of range (count {})", index, count)
+            }
+            Self::NotSupported(reason) => write!(f, "not supported: {}", reason),
+            Self::Parse(reason) => write!(f, "parse error: {}", reason),
+            Self::HResult { code, message } => write!(f, "{} ({:#010X})", message, code),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::FileAccess { source, .. } => Some(source),
+            _ => None,
+        }
+    }
+}
+
+impl From<std::string::FromUtf16Error> for Error {
+    fn from(value: std::string::FromUtf16Error) -> Self {
+        Self::StringConversion(value.to_string())
+    }
+}
+
+#[cfg(windows)]
+impl From<windows::core::Error> for Error {
+    fn from(value: windows::core::Error) -> Self {
+        Self::from_hresult(value.code().0, value.message().to_string())
+    }
+}
+
+// COM インターフェースを実装するときに，DirectWrite へ HRESULT として返す．
+#[cfg(windows)]
+impl From<Error> for windows::core::Error {
+    fn from(value: Error) -> Self {
+        windows::core::Error::new(windows::core::HRESULT(value.hresult()), value.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sfnt::make_tag;
+
+    // HRESULT から変換したエラーは DirectWrite と同じ HRESULT に戻る．
+    #[test]
+    fn hresult_round_trip() {
+        for code in [
+            DWRITE_E_REMOTEFONT,
+            DWRITE_E_FILENOTFOUND,
+            DWRITE_E_FILEACCESS,
+            DWRITE_E_FILEFORMAT,
+            DWRITE_E_NOFONT,
+        ] {
+            let error = Error::from_hresult(code, "error".to_string());
+            assert_eq!(error.hresult(), code, "{:#010X}", code);
+        }
+        let error = Error::from_hresult(E_PATH_NOT_FOUND, "error".to_string());
+        assert!(error.is_not_found());
+        assert_eq!(error.hresult(), DWRITE_E_FILENOTFOUND);
+        let error = Error::from_hresult(E_ACCESSDENIED, "error".to_string());
+        assert!(!error.is_not_found());
+        assert_eq!(error.hresult(), DWRITE_E_FILEACCESS);
+        assert!(Error::from_hresult(DWRITE_E_REMOTEFONT, String::new()).is_remote_font());
+        assert_eq!(
+            Error::IndexOutOfRange { index: 1, count: 1 }.hresult(),
+            E_INVALIDARG
+        );
+        assert_eq!(Error::NotSupported(String::new()).hresult(), E_NOTIMPL);
+    }
+
+    #[test]
+    fn display() {
+        let error = Error::file_access(
+            "a.ttf",
+            std::io::Error::new(std::io::ErrorKind::NotFound, "not found"),
+        );
+        assert_eq!(error.to_string(), "cannot access a.ttf: not found");
+        assert!(std::error::Error::source(&error).is_some());
+        let error = Error::malformed_table(make_tag(b"name"), "too short");
+        assert_eq!(error.to_string(), "malformed 'name' table: too short");
+        let error = Error::from_hresult(DWRITE_E_NOFONT, "no font".to_string());
+        assert_eq!(error.to_string(), "no font (0x88985002)");
+        let error = Error::from(String::from_utf16(&[0xD800]).unwrap_err());
+        assert_eq!(error.hresult(), E_INVALIDARG);
+    }
+}
@@ -1,12 +1,31 @@
-use anyhow::Context;
-use windows::Win32::Graphics::DirectWrite::{
-    DWriteCreateFactory, IDWriteFactory, IDWriteFontCollection, IDWriteGdiInterop,
-    DWRITE_FACTORY_TYPE_ISOLATED, DWRITE_FACTORY_TYPE_SHARED,
+use std::path::PathBuf;
+
+#[cfg(windows)]
+use std::any::Any;
+
+#[cfg(windows)]
+use windows::{
+    core::Interface,
+    Win32::Graphics::DirectWrite::{
+        DWriteCreateFactory, IDWriteFactory, IDWriteFactory6, IDWriteFontCollection,
+        IDWriteFontCollectionLoader, IDWriteFontFileLoader, IDWriteGdiInterop,
+        DWRITE_FACTORY_TYPE_ISOLATED, DWRITE_FACTORY_TYPE_SHARED,
+    },
 };
 
-use crate::{font_collection::FontCollection, gdi_interop::GdiInterop};
+use crate::{
+    backend::FactoryBackend, error::Result, family_model::FontFamilyModel,
+    font_collection::FontCollection, font_collection_builder::FontCollectionBuilder,
+    font_file::FontFileReference,
+};
+#[cfg(windows)]
+use crate::{
+    error::{Error, DWRITE_E_ALREADYREGISTERED},
+    font_collection_loader,
+    gdi_interop::GdiInterop,
+};
 
-pub struct Factory(pub IDWriteFactory);
+pub struct Factory(Box<dyn FactoryBackend>);
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum FactoryType {
@@ -14,39 +33,184 @@ pub enum FactoryType {
     Shared,
 }
 
-pub fn create_factory(factory_type: FactoryType) -> anyhow::Result<IDWriteFactory> {
+#[cfg(windows)]
+pub fn create_factory(factory_type: FactoryType) -> Result<IDWriteFactory> {
     match factory_type {
         FactoryType::Isolated => Ok(unsafe { DWriteCreateFactory(DWRITE_FACTORY_TYPE_ISOLATED) }?),
         FactoryType::Shared => Ok(unsafe { DWriteCreateFactory(DWRITE_FACTORY_TYPE_SHARED) }?),
     }
 }
 
+#[cfg(windows)]
 pub fn get_system_font_collection(
     factory: &IDWriteFactory,
     check_for_updates: bool,
-) -> anyhow::Result<IDWriteFontCollection> {
+) -> Result<IDWriteFontCollection> {
     let mut collection = None;
     unsafe { factory.GetSystemFontCollection(&mut collection, check_for_updates) }?;
-    collection.context("Collection is none")
+    collection.ok_or_else(|| Error::HResult {
+        code: crate::error::DWRITE_E_UNEXPECTED,
+        message: "Collection is none".to_string(),
+    })
+}
+
+// IDWriteFactory6 が必要なので，Windows 10 1803 より前では NotSupported を返す．
+#[cfg(windows)]
+pub fn get_factory6(factory: &IDWriteFactory) -> Result<IDWriteFactory6> {
+    factory
+        .cast()
+        .map_err(|_| Error::NotSupported("font family models require IDWriteFactory6".to_string()))
 }
 
-pub fn get_gdi_interop(factory: &IDWriteFactory) -> anyhow::Result<IDWriteGdiInterop> {
+#[cfg(windows)]
+pub fn get_system_font_collection_with_model(
+    factory: &IDWriteFactory,
+    include_downloadable_fonts: bool,
+    model: FontFamilyModel,
+) -> Result<IDWriteFontCollection> {
+    let factory = get_factory6(factory)?;
+    let collection =
+        unsafe { factory.GetSystemFontCollection3(include_downloadable_fonts, model.into()) }?;
+    Ok(collection.cast()?)
+}
+
+#[cfg(windows)]
+pub fn get_gdi_interop(factory: &IDWriteFactory) -> Result<IDWriteGdiInterop> {
     unsafe { Ok(factory.GetGdiInterop()?) }
 }
 
+// 同じローダーを登録し直したときの DWRITE_E_ALREADYREGISTERED は無視する．
+#[cfg(windows)]
+pub fn register_font_file_loader(
+    factory: &IDWriteFactory,
+    loader: &IDWriteFontFileLoader,
+) -> Result<()> {
+    match unsafe { factory.RegisterFontFileLoader(loader) } {
+        Err(e) if e.code().0 != DWRITE_E_ALREADYREGISTERED => Err(e.into()),
+        _ => Ok(()),
+    }
+}
+
+#[cfg(windows)]
+pub fn register_font_collection_loader(
+    factory: &IDWriteFactory,
+    loader: &IDWriteFontCollectionLoader,
+) -> Result<()> {
+    match unsafe { factory.RegisterFontCollectionLoader(loader) } {
+        Err(e) if e.code().0 != DWRITE_E_ALREADYREGISTERED => Err(e.into()),
+        _ => Ok(()),
+    }
+}
+
+#[cfg(windows)]
+impl FactoryBackend for IDWriteFactory {
+    fn get_system_font_collection(&self, check_for_updates: bool) -> Result<FontCollection> {
+        get_system_font_collection(self, check_for_updates).map(FontCollection::new)
+    }
+
+    fn get_system_font_collection_with_model(
+        &self,
+        include_downloadable_fonts: bool,
+        model: FontFamilyModel,
+    ) -> Result<FontCollection> {
+        get_system_font_collection_with_model(self, include_downloadable_fonts, model)
+            .map(FontCollection::new)
+    }
+
+    // WSS モデルは Windows 7 でも使えるコレクションローダーで，typographic モデルはフォントセットで作る．
+    fn create_font_collection(
+        &self,
+        files: &[FontFileReference],
+        model: FontFamilyModel,
+    ) -> Result<FontCollection> {
+        let collection = match model {
+            FontFamilyModel::WeightStretchStyle => {
+                font_collection_loader::create_font_collection(self, files)?
+            }
+            FontFamilyModel::Typographic => {
+                font_collection_loader::create_font_collection_from_font_set(self, files, model)?
+            }
+        };
+        Ok(FontCollection::new(collection))
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
 impl Factory {
-    pub fn new(factory_type: FactoryType) -> anyhow::Result<Self> {
-        Ok(Self(create_factory(factory_type)?))
+    // Windows では DirectWrite を，それ以外ではフォントファイルを直接読む portable バックエンドを使う．
+    #[cfg(windows)]
+    pub fn new(factory_type: FactoryType) -> Result<Self> {
+        Ok(Self::from_backend(create_factory(factory_type)?))
+    }
+
+    #[cfg(not(windows))]
+    pub fn new(_factory_type: FactoryType) -> Result<Self> {
+        Ok(Self::portable())
+    }
+
+    // Windows でも DirectWrite を使わずにフォントファイルを読む．
+    pub fn portable() -> Self {
+        Self::from_backend(crate::portable::PortableFactory::new())
+    }
+
+    pub fn from_backend<T: FactoryBackend + 'static>(backend: T) -> Self {
+        Self(Box::new(backend))
+    }
+
+    pub fn backend(&self) -> &dyn FactoryBackend {
+        self.0.as_ref()
+    }
+
+    #[cfg(windows)]
+    pub fn as_dwrite(&self) -> Option<&IDWriteFactory> {
+        self.0.as_any().downcast_ref()
+    }
+
+    pub fn get_system_font_collection(&self, check_for_updates: bool) -> Result<FontCollection> {
+        self.0.get_system_font_collection(check_for_updates)
+    }
+
+    // include_downloadable_fonts はダウンロードされていないフォントも含める．
+    pub fn get_system_font_collection_with_model(
+        &self,
+        include_downloadable_fonts: bool,
+        model: FontFamilyModel,
+    ) -> Result<FontCollection> {
+        self.0
+            .get_system_font_collection_with_model(include_downloadable_fonts, model)
+    }
+
+    // ローカルのファイルやメモリ上のデータからフォントコレクションを作る．
+    pub fn create_font_collection(&self, files: &[FontFileReference]) -> Result<FontCollection> {
+        self.create_font_collection_with_model(files, FontFamilyModel::WeightStretchStyle)
+    }
+
+    pub fn create_font_collection_with_model(
+        &self,
+        files: &[FontFileReference],
+        model: FontFamilyModel,
+    ) -> Result<FontCollection> {
+        self.0.create_font_collection(files, model)
     }
 
-    pub fn get_system_font_collection(
+    // ディレクトリ以下のフォントファイルからフォントコレクションを作る．
+    pub fn create_font_collection_from_directory<P: Into<PathBuf>>(
         &self,
-        check_for_updates: bool,
-    ) -> anyhow::Result<FontCollection> {
-        get_system_font_collection(&self.0, check_for_updates).map(FontCollection)
+        directory: P,
+    ) -> Result<FontCollection> {
+        FontCollectionBuilder::new()
+            .add_directory(directory)
+            .build(self)
     }
 
-    pub fn get_gdi_interop(&self) -> anyhow::Result<GdiInterop> {
-        get_gdi_interop(&self.0).map(GdiInterop)
+    #[cfg(windows)]
+    pub fn get_gdi_interop(&self) -> Result<GdiInterop> {
+        let factory = self.as_dwrite().ok_or_else(|| {
+            Error::NotSupported("GDI interop requires the DirectWrite backend".to_string())
+        })?;
+        get_gdi_interop(factory).map(GdiInterop)
     }
 }
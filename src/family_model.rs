@@ -0,0 +1,748 @@
+#[cfg(windows)]
+use windows::Win32::Graphics::DirectWrite::{
+    DWRITE_FONT_FAMILY_MODEL, DWRITE_FONT_FAMILY_MODEL_TYPOGRAPHIC,
+    DWRITE_FONT_FAMILY_MODEL_WEIGHT_STRETCH_STYLE,
+};
+
+use crate::{
+    font::{Stretch, Style, Weight},
+    fvar::{Fvar, NamedInstance},
+    localized_strings::LocalizedString,
+    name::NameTable,
+    stat::Stat,
+};
+
+// フォントをファミリーにまとめる方法．DWRITE_FONT_FAMILY_MODEL と同じ．
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum FontFamilyModel {
+    // 名前 ID 16, 17 でまとめる．可変フォントは一つのファミリーになる．
+    Typographic,
+    // weight, stretch, style だけが異なるフォントをまとめる．GetSystemFontCollection の既定．
+    #[default]
+    WeightStretchStyle,
+}
+
+#[cfg(windows)]
+impl From<FontFamilyModel> for DWRITE_FONT_FAMILY_MODEL {
+    fn from(value: FontFamilyModel) -> Self {
+        match value {
+            FontFamilyModel::Typographic => DWRITE_FONT_FAMILY_MODEL_TYPOGRAPHIC,
+            FontFamilyModel::WeightStretchStyle => DWRITE_FONT_FAMILY_MODEL_WEIGHT_STRETCH_STYLE,
+        }
+    }
+}
+
+// 名前 ID 16, 17 を使い，無ければ 1, 2 を使う．
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct TypographicNames {
+    pub family_names: Vec<LocalizedString>,
+    pub face_names: Vec<LocalizedString>,
+}
+
+impl TypographicNames {
+    pub fn from_name_table(names: &NameTable) -> Self {
+        let mut family_names = names.get_names(TYPOGRAPHIC_FAMILY_NAME);
+        let mut face_names = names.get_names(TYPOGRAPHIC_SUBFAMILY_NAME);
+        if family_names.is_empty() {
+            family_names = names.get_names(FAMILY_NAME);
+            face_names = names.get_names(SUBFAMILY_NAME);
+        }
+        if face_names.is_empty() {
+            face_names = names.get_names(SUBFAMILY_NAME);
+        }
+        Self {
+            family_names,
+            face_names,
+        }
+    }
+
+    // 可変フォントの名前付きインスタンスは get_named_instance_face_names をフェイス名にする．
+    pub fn from_named_instance(
+        names: &NameTable,
+        fvar: &Fvar,
+        instance: &NamedInstance,
+        stat: Option<&Stat>,
+    ) -> Self {
+        let mut typographic_names = Self::from_name_table(names);
+        let face_names = get_named_instance_face_names(names, fvar, instance, stat);
+        if !face_names.is_empty() {
+            typographic_names.face_names = face_names;
+        }
+        typographic_names
+    }
+}
+
+// 名前付きインスタンスのフェイス名．STAT テーブルがあれば座標から組み立て ("Display Light" など)，
+// 無いか名前を組み立てられなければ fvar の subfamilyNameID を使う．
+pub fn get_named_instance_face_names(
+    names: &NameTable,
+    fvar: &Fvar,
+    instance: &NamedInstance,
+    stat: Option<&Stat>,
+) -> Vec<LocalizedString> {
+    let style_names = stat
+        .map(|stat| stat.get_named_instance_style_names(names, fvar, instance))
+        .unwrap_or_default();
+    if style_names.is_empty() {
+        instance.get_subfamily_names(names)
+    } else {
+        style_names
+    }
+}
+
+// DirectWrite の weight-stretch-style (WSS) ファミリーモデル．
+// ファミリー名は name ID 21, 16, 1 の順に探し，ファミリー名の末尾やサブファミリー名にある
+// weight, stretch, style の語はフェイス名に，それ以外の語はファミリー名に移す．
+//
+// Arial Black (ID 1) + Regular (ID 2) -> Arial + Black
+// Segoe UI Variable (ID 16) + Display Light (ID 17) -> Segoe UI Variable Display + Light
+
+pub const WWS_FAMILY_NAME: u16 = 21;
+pub const WWS_SUBFAMILY_NAME: u16 = 22;
+pub const TYPOGRAPHIC_FAMILY_NAME: u16 = 16;
+pub const TYPOGRAPHIC_SUBFAMILY_NAME: u16 = 17;
+pub const FAMILY_NAME: u16 = 1;
+pub const SUBFAMILY_NAME: u16 = 2;
+
+// 名前に現れる weight, stretch, style の語．Regular はフェイス名が他に無いときだけ使う．
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum WssTerm {
+    Weight(Weight),
+    Stretch(Stretch),
+    Style(Style),
+    Regular,
+}
+
+// 空白を除いて小文字にした語．"Extra Bold" のような二語の語も一語にしてから探す．
+const TERMS: [(&str, WssTerm); 43] = [
+    ("thin", WssTerm::Weight(Weight(100))),
+    ("hairline", WssTerm::Weight(Weight(100))),
+    ("extralight", WssTerm::Weight(Weight(200))),
+    ("ultralight", WssTerm::Weight(Weight(200))),
+    ("light", WssTerm::Weight(Weight(300))),
+    ("semilight", WssTerm::Weight(Weight(350))),
+    ("demilight", WssTerm::Weight(Weight(350))),
+    ("medium", WssTerm::Weight(Weight(500))),
+    ("semibold", WssTerm::Weight(Weight(600))),
+    ("demibold", WssTerm::Weight(Weight(600))),
+    ("demi", WssTerm::Weight(Weight(600))),
+    ("bold", WssTerm::Weight(Weight(700))),
+    ("extrabold", WssTerm::Weight(Weight(800))),
+    ("ultrabold", WssTerm::Weight(Weight(800))),
+    ("heavy", WssTerm::Weight(Weight(900))),
+    ("black", WssTerm::Weight(Weight(900))),
+    ("extrablack", WssTerm::Weight(Weight(950))),
+    ("ultrablack", WssTerm::Weight(Weight(950))),
+    ("ultracondensed", WssTerm::Stretch(Stretch::UltraCondensed)),
+    ("ultracond", WssTerm::Stretch(Stretch::UltraCondensed)),
+    ("extracondensed", WssTerm::Stretch(Stretch::ExtraCondensed)),
+    ("extracond", WssTerm::Stretch(Stretch::ExtraCondensed)),
+    ("compressed", WssTerm::Stretch(Stretch::ExtraCondensed)),
+    ("condensed", WssTerm::Stretch(Stretch::Condensed)),
+    ("cond", WssTerm::Stretch(Stretch::Condensed)),
+    ("narrow", WssTerm::Stretch(Stretch::Condensed)),
+    ("semicondensed", WssTerm::Stretch(Stretch::SemiCondensed)),
+    ("semicond", WssTerm::Stretch(Stretch::SemiCondensed)),
+    ("semiexpanded", WssTerm::Stretch(Stretch::SemiExpanded)),
+    ("semiextended", WssTerm::Stretch(Stretch::SemiExpanded)),
+    ("expanded", WssTerm::Stretch(Stretch::Expanded)),
+    ("extended", WssTerm::Stretch(Stretch::Expanded)),
+    ("wide", WssTerm::Stretch(Stretch::Expanded)),
+    ("extraexpanded", WssTerm::Stretch(Stretch::ExtraExpanded)),
+    ("extraextended", WssTerm::Stretch(Stretch::ExtraExpanded)),
+    ("ultraexpanded", WssTerm::Stretch(Stretch::UltraExpanded)),
+    ("ultraextended", WssTerm::Stretch(Stretch::UltraExpanded)),
+    ("italic", WssTerm::Style(Style::Italic)),
+    ("oblique", WssTerm::Style(Style::Oblique)),
+    ("slanted", WssTerm::Style(Style::Oblique)),
+    ("regular", WssTerm::Regular),
+    ("normal", WssTerm::Regular),
+    ("book", WssTerm::Regular),
+];
+
+pub fn parse_wss_term(word: &str) -> Option<WssTerm> {
+    let word: String = word
+        .chars()
+        .filter(|c| !c.is_whitespace() && *c != '-')
+        .collect::<String>()
+        .to_lowercase();
+    TERMS
+        .iter()
+        .find(|(term, _)| *term == word)
+        .map(|(_, term)| *term)
+}
+
+// 名前を語に分ける．二語で一つの語になるものはまとめる．
+fn split_words(name: &str) -> Vec<(String, Option<WssTerm>)> {
+    let tokens: Vec<&str> = name.split_whitespace().collect();
+    let mut words = vec![];
+    let mut i = 0;
+    while i < tokens.len() {
+        if let Some(next) = tokens.get(i + 1) {
+            let pair = format!("{} {}", tokens[i], next);
+            if let Some(term) = parse_wss_term(&pair) {
+                words.push((pair, Some(term)));
+                i += 2;
+                continue;
+            }
+        }
+        words.push((tokens[i].to_string(), parse_wss_term(tokens[i])));
+        i += 1;
+    }
+    words
+}
+
+fn join(words: &[(String, Option<WssTerm>)]) -> String {
+    words
+        .iter()
+        .map(|(word, _)| word.as_str())
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+// ファミリー名の末尾の語を取り除く．全ての語を取り除くことはしない．
+// Franklin Gothic Book のように Regular の語で終わるファミリー名はそのまま使う．
+fn split_trailing_terms(name: &str) -> (String, Vec<(String, Option<WssTerm>)>) {
+    let mut words = split_words(name);
+    let mut terms = vec![];
+    while words.len() > 1
+        && words
+            .last()
+            .is_some_and(|(_, term)| term.is_some_and(|term| term != WssTerm::Regular))
+    {
+        terms.insert(0, words.pop().unwrap());
+    }
+    (join(&words), terms)
+}
+
+// en-us を優先し，無ければ最初の名前を使う．
+fn get_primary(names: &[LocalizedString]) -> Option<&LocalizedString> {
+    names
+        .iter()
+        .find(|name| name.locale.eq_ignore_ascii_case("en-us"))
+        .or(names.first())
+}
+
+fn en_us(string: String) -> LocalizedString {
+    LocalizedString {
+        string,
+        locale: "en-us".to_string(),
+    }
+}
+
+// WSS モデルでのファミリー名とフェイス名，名前から読み取った weight, stretch, style．
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct WssNames {
+    pub family_names: Vec<LocalizedString>,
+    pub face_names: Vec<LocalizedString>,
+    pub weight: Option<Weight>,
+    pub stretch: Option<Stretch>,
+    pub style: Option<Style>,
+}
+
+impl WssNames {
+    pub fn from_name_table(names: &NameTable) -> Self {
+        let wws_family_names = names.get_names(WWS_FAMILY_NAME);
+        if !wws_family_names.is_empty() {
+            let mut face_names = names.get_names(WWS_SUBFAMILY_NAME);
+            if face_names.is_empty() {
+                face_names = names.get_names(TYPOGRAPHIC_SUBFAMILY_NAME);
+            }
+            if face_names.is_empty() {
+                face_names = names.get_names(SUBFAMILY_NAME);
+            }
+            // WWS ファミリー名は語を移さずにそのまま使う．
+            let mut wss_names = Self::from_family_and_faces(vec![], face_names.clone(), true);
+            wss_names.family_names = wws_family_names;
+            if !face_names.is_empty() {
+                wss_names.face_names = face_names;
+            }
+            return wss_names;
+        }
+        let typographic_family_names = names.get_names(TYPOGRAPHIC_FAMILY_NAME);
+        if !typographic_family_names.is_empty() {
+            let face_names = names.get_names(TYPOGRAPHIC_SUBFAMILY_NAME);
+            return Self::from_family_and_faces(typographic_family_names, face_names, false);
+        }
+        Self::from_family_and_faces(
+            names.get_names(FAMILY_NAME),
+            names.get_names(SUBFAMILY_NAME),
+            true,
+        )
+    }
+
+    // 名前付きインスタンスのフェイス名を名前 ID 17 の代わりに使う．
+    pub fn from_named_instance(
+        names: &NameTable,
+        fvar: &Fvar,
+        instance: &NamedInstance,
+        stat: Option<&Stat>,
+    ) -> Self {
+        let mut family_names = names.get_names(TYPOGRAPHIC_FAMILY_NAME);
+        if family_names.is_empty() {
+            family_names = names.get_names(FAMILY_NAME);
+        }
+        let face_names = get_named_instance_face_names(names, fvar, instance, stat);
+        Self::from_family_and_faces(family_names, face_names, false)
+    }
+
+    // legacy (name ID 1, 2) のときはファミリー名の末尾の語をフェイス名に移し，
+    // それ以外のときはサブファミリー名の weight, stretch, style 以外の語をファミリー名に移す．
+    fn from_family_and_faces(
+        family_names: Vec<LocalizedString>,
+        face_names: Vec<LocalizedString>,
+        legacy: bool,
+    ) -> Self {
+        let mut terms = vec![];
+        let mut family_names = family_names;
+        if legacy {
+            if let Some(primary) = get_primary(&family_names) {
+                terms = split_trailing_terms(&primary.string).1;
+            }
+            if !terms.is_empty() {
+                for name in &mut family_names {
+                    name.string = split_trailing_terms(&name.string).0;
+                }
+            }
+        }
+        let face_words = get_primary(&face_names)
+            .map(|name| split_words(&name.string))
+            .unwrap_or_default();
+        let (face_terms, others): (Vec<_>, Vec<_>) = if legacy {
+            (face_words, vec![])
+        } else {
+            face_words.into_iter().partition(|(_, term)| term.is_some())
+        };
+        if !others.is_empty() {
+            let suffix = join(&others);
+            for name in &mut family_names {
+                name.string = format!("{} {}", name.string, suffix);
+            }
+        }
+        let has_family_terms = !terms.is_empty();
+        terms.extend(face_terms);
+
+        let mut names = Self {
+            family_names,
+            ..Default::default()
+        };
+        for (_, term) in &terms {
+            match term {
+                Some(WssTerm::Weight(weight)) => names.weight = Some(*weight),
+                Some(WssTerm::Stretch(stretch)) => names.stretch = Some(*stretch),
+                Some(WssTerm::Style(style)) => names.style = Some(*style),
+                _ => {}
+            }
+        }
+        // フェイス名を作り直す必要がなければ，全てのロケールのフェイス名をそのまま使う．
+        names.face_names = if !has_family_terms && others.is_empty() && !face_names.is_empty() {
+            face_names
+        } else {
+            vec![en_us(get_face_name(&terms))]
+        };
+        names
+    }
+
+    // OS/2 などから読んだ値が既定値のときだけ，名前から読み取った値を使う．
+    pub fn apply(
+        &self,
+        weight: Weight,
+        style: Style,
+        stretch: Stretch,
+    ) -> (Weight, Style, Stretch) {
+        let weight = match self.weight {
+            Some(name_weight) if weight == Weight(400) => name_weight,
+            _ => weight,
+        };
+        let style = match self.style {
+            Some(name_style) if style == Style::Normal => name_style,
+            _ => style,
+        };
+        let stretch = match self.stretch {
+            Some(name_stretch) if stretch == Stretch::Normal => name_stretch,
+            _ => stretch,
+        };
+        (weight, style, stretch)
+    }
+}
+
+// stretch, weight, style, その他の語の順に並べる．語が無ければ Regular．
+fn get_face_name(terms: &[(String, Option<WssTerm>)]) -> String {
+    let order = |term: &Option<WssTerm>| match term {
+        Some(WssTerm::Stretch(_)) => 0,
+        Some(WssTerm::Weight(_)) => 1,
+        Some(WssTerm::Style(_)) => 2,
+        _ => 3,
+    };
+    let mut terms: Vec<_> = terms
+        .iter()
+        .filter(|(_, term)| *term != Some(WssTerm::Regular))
+        .collect();
+    terms.sort_by_key(|(_, term)| order(term));
+    if terms.is_empty() {
+        "Regular".to_string()
+    } else {
+        terms
+            .iter()
+            .map(|(word, _)| word.as_str())
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        fvar::{WDTH, WGHT},
+        name::{NameRecord, PLATFORM_WINDOWS},
+        sfnt::make_tag,
+        stat::ELIDABLE_AXIS_VALUE_NAME,
+        test_fonts::{axis_value, fvar, stat},
+    };
+
+    // (名前 ID, ロケール, 文字列) から name テーブルを作る．
+    fn name_table(records: &[(u16, &str, &str)]) -> NameTable {
+        NameTable {
+            format: 0,
+            records: records
+                .iter()
+                .map(|(name_id, locale, string)| NameRecord {
+                    platform_id: PLATFORM_WINDOWS,
+                    encoding_id: 1,
+                    language_id: 0x0409,
+                    name_id: *name_id,
+                    locale: Some(locale.to_string()),
+                    string: Some(string.to_string()),
+                })
+                .collect(),
+            lang_tags: vec![],
+        }
+    }
+
+    fn names(strings: &[LocalizedString]) -> Vec<String> {
+        strings
+            .iter()
+            .map(|name| format!("{}:{}", name.locale, name.string))
+            .collect()
+    }
+
+    fn wss(family: &str, subfamily: &str) -> WssNames {
+        WssNames::from_name_table(&name_table(&[
+            (FAMILY_NAME, "en-us", family),
+            (SUBFAMILY_NAME, "en-us", subfamily),
+        ]))
+    }
+
+    #[test]
+    fn trailing_terms() {
+        let split = |name| {
+            let (family, terms) = split_trailing_terms(name);
+            (family, join(&terms))
+        };
+        assert_eq!(
+            split("Arial Black"),
+            ("Arial".to_string(), "Black".to_string())
+        );
+        assert_eq!(
+            split("Franklin Gothic Medium Cond"),
+            ("Franklin Gothic".to_string(), "Medium Cond".to_string())
+        );
+        assert_eq!(
+            split("Foo Extra Bold"),
+            ("Foo".to_string(), "Extra Bold".to_string())
+        );
+        // Regular の語と，語だけの名前はそのまま．
+        assert_eq!(
+            split("Franklin Gothic Book"),
+            ("Franklin Gothic Book".to_string(), String::new())
+        );
+        assert_eq!(split("Black"), ("Black".to_string(), String::new()));
+        assert_eq!(split("Bold Sans"), ("Bold Sans".to_string(), String::new()));
+    }
+
+    // wss.txt の Arial, Franklin Gothic, Segoe UI などと同じ名前になる．
+    #[test]
+    fn legacy_names() {
+        for (family, subfamily, wss_family, wss_face) in [
+            ("Arial", "Regular", "Arial", "Regular"),
+            ("Arial Black", "Regular", "Arial", "Black"),
+            ("Arial Narrow", "Bold Italic", "Arial", "Narrow Bold Italic"),
+            (
+                "Franklin Gothic Book",
+                "Italic",
+                "Franklin Gothic Book",
+                "Italic",
+            ),
+            (
+                "Franklin Gothic Medium Cond",
+                "Regular",
+                "Franklin Gothic",
+                "Cond Medium",
+            ),
+            (
+                "Franklin Gothic Demi",
+                "Italic",
+                "Franklin Gothic",
+                "Demi Italic",
+            ),
+            ("Segoe UI Semibold", "Italic", "Segoe UI", "Semibold Italic"),
+            ("Segoe UI Black", "Italic", "Segoe UI", "Black Italic"),
+        ] {
+            let names = wss(family, subfamily);
+            assert_eq!(
+                (
+                    names.family_names[0].string.as_str(),
+                    names.face_names[0].string.as_str()
+                ),
+                (wss_family, wss_face),
+                "{} {}",
+                family,
+                subfamily
+            );
+        }
+        let names = wss("Arial Narrow", "Bold Italic");
+        assert_eq!(
+            (names.weight, names.stretch, names.style),
+            (
+                Some(Weight(700)),
+                Some(Stretch::Condensed),
+                Some(Style::Italic)
+            )
+        );
+    }
+
+    #[test]
+    fn localized_names() {
+        // 語を移さなければ，全てのロケールのフェイス名をそのまま使う．
+        let names = WssNames::from_name_table(&name_table(&[
+            (FAMILY_NAME, "en-us", "Arial"),
+            (SUBFAMILY_NAME, "en-us", "Bold"),
+            (SUBFAMILY_NAME, "de-de", "Fett"),
+        ]));
+        assert_eq!(names.face_names.len(), 2);
+        assert_eq!(names.face_names[1].string, "Fett");
+        // 移す場合は en-us の名前だけになる．
+        let names = WssNames::from_name_table(&name_table(&[
+            (FAMILY_NAME, "en-us", "Arial Black"),
+            (FAMILY_NAME, "ja-jp", "アリアル Black"),
+            (SUBFAMILY_NAME, "en-us", "Regular"),
+            (SUBFAMILY_NAME, "de-de", "Standard"),
+        ]));
+        assert_eq!(
+            names
+                .family_names
+                .iter()
+                .map(|n| n.string.as_str())
+                .collect::<Vec<_>>(),
+            vec!["Arial", "アリアル"]
+        );
+        assert_eq!(names.face_names.len(), 1);
+        assert_eq!(names.face_names[0].string, "Black");
+    }
+
+    #[test]
+    fn typographic_and_wws_names() {
+        // 名前 ID 17 の weight, stretch, style 以外の語はファミリー名に移す．
+        let names = WssNames::from_name_table(&name_table(&[
+            (FAMILY_NAME, "en-us", "Segoe UI Variable Display Light"),
+            (SUBFAMILY_NAME, "en-us", "Regular"),
+            (TYPOGRAPHIC_FAMILY_NAME, "en-us", "Segoe UI Variable"),
+            (TYPOGRAPHIC_SUBFAMILY_NAME, "en-us", "Display Light"),
+        ]));
+        assert_eq!(names.family_names[0].string, "Segoe UI Variable Display");
+        assert_eq!(names.face_names[0].string, "Light");
+        assert_eq!(names.weight, Some(Weight(300)));
+        // WWS ファミリー名はそのまま使う．
+        let names = WssNames::from_name_table(&name_table(&[
+            (FAMILY_NAME, "en-us", "Sitka Small"),
+            (TYPOGRAPHIC_FAMILY_NAME, "en-us", "Sitka"),
+            (TYPOGRAPHIC_SUBFAMILY_NAME, "en-us", "Small Bold"),
+            (WWS_FAMILY_NAME, "en-us", "Sitka Small"),
+            (WWS_SUBFAMILY_NAME, "en-us", "Bold"),
+        ]));
+        assert_eq!(names.family_names[0].string, "Sitka Small");
+        assert_eq!(names.face_names[0].string, "Bold");
+        assert_eq!(names.weight, Some(Weight(700)));
+    }
+
+    #[test]
+    fn family_and_faces() {
+        let from = |family: &str, face: &str, legacy| {
+            let names = WssNames::from_family_and_faces(
+                vec![en_us(family.to_string())],
+                vec![en_us(face.to_string())],
+                legacy,
+            );
+            (names.family_names, names.face_names)
+        };
+        let (family, face) = from("Sitka", "Subheading Semibold Italic", false);
+        assert_eq!(names(&family), vec!["en-us:Sitka Subheading"]);
+        assert_eq!(names(&face), vec!["en-us:Semibold Italic"]);
+        // weight, stretch, style の語だけならフェイス名はそのまま．
+        let (family, face) = from("Bahnschrift", "Light SemiCondensed", false);
+        assert_eq!(names(&family), vec!["en-us:Bahnschrift"]);
+        assert_eq!(names(&face), vec!["en-us:Light SemiCondensed"]);
+        // 語が無ければ Regular．
+        let (family, face) = from("Sitka", "Banner", false);
+        assert_eq!(names(&family), vec!["en-us:Sitka Banner"]);
+        assert_eq!(names(&face), vec!["en-us:Regular"]);
+        // legacy では stretch, weight, style の順に並べ直す．
+        let (family, face) = from("Test Sans Bold", "Condensed", true);
+        assert_eq!(names(&family), vec!["en-us:Test Sans"]);
+        assert_eq!(names(&face), vec!["en-us:Condensed Bold"]);
+        let names = WssNames::from_family_and_faces(vec![], vec![], true);
+        assert_eq!(names.face_names[0].string, "Regular");
+    }
+
+    // 名前から読み取った値は，OS/2 の値が既定値のときだけ使う．
+    #[test]
+    fn apply() {
+        let names = wss("Foo Extra Bold Condensed", "Italic");
+        assert_eq!(
+            names.apply(Weight(400), Style::Normal, Stretch::Normal),
+            (Weight(800), Style::Italic, Stretch::Condensed)
+        );
+        assert_eq!(
+            names.apply(Weight(700), Style::Oblique, Stretch::SemiCondensed),
+            (Weight(700), Style::Oblique, Stretch::SemiCondensed)
+        );
+        let names = wss("Foo", "Regular");
+        assert_eq!(
+            names.apply(Weight(300), Style::Normal, Stretch::Expanded),
+            (Weight(300), Style::Normal, Stretch::Expanded)
+        );
+    }
+
+    // typo.txt の Sitka のように，名前 ID 16, 17 があればそれを使う．
+    #[test]
+    fn typographic_names() {
+        let names = TypographicNames::from_name_table(&name_table(&[
+            (FAMILY_NAME, "en-us", "Sitka Small"),
+            (SUBFAMILY_NAME, "en-us", "Bold Italic"),
+            (TYPOGRAPHIC_FAMILY_NAME, "en-us", "Sitka"),
+            (TYPOGRAPHIC_SUBFAMILY_NAME, "en-us", "Small Bold Italic"),
+        ]));
+        assert_eq!(names.family_names[0].string, "Sitka");
+        assert_eq!(names.face_names[0].string, "Small Bold Italic");
+        // 名前 ID 17 が無ければ 2 を使う．
+        let names = TypographicNames::from_name_table(&name_table(&[
+            (FAMILY_NAME, "en-us", "Sitka Banner"),
+            (SUBFAMILY_NAME, "en-us", "Regular"),
+            (TYPOGRAPHIC_FAMILY_NAME, "en-us", "Sitka"),
+        ]));
+        assert_eq!(names.family_names[0].string, "Sitka");
+        assert_eq!(names.face_names[0].string, "Regular");
+        // 名前 ID 16 が無ければ 1, 2 を使う．
+        let names = TypographicNames::from_name_table(&name_table(&[
+            (FAMILY_NAME, "en-us", "Arial Black"),
+            (SUBFAMILY_NAME, "en-us", "Regular"),
+            (TYPOGRAPHIC_SUBFAMILY_NAME, "en-us", "Black"),
+        ]));
+        assert_eq!(names.family_names[0].string, "Arial Black");
+        assert_eq!(names.face_names[0].string, "Regular");
+    }
+
+    // typo.txt の Segoe UI Variable と Bahnschrift のように，名前付きインスタンスのフェイス名は
+    // STAT から組み立てる．fvar のサブファミリー名 (名前 ID 270) はわざと違う名前にしておく．
+    #[test]
+    fn named_instance_names() {
+        const OPSZ: u32 = make_tag(b"opsz");
+        let names = name_table(&[
+            (FAMILY_NAME, "en-us", "Segoe UI Variable"),
+            (SUBFAMILY_NAME, "en-us", "Regular"),
+            (256, "en-us", "Weight"),
+            (257, "en-us", "Optical size"),
+            (258, "en-us", "Light"),
+            (259, "en-us", "Regular"),
+            (260, "en-us", "Bold"),
+            (261, "en-us", "Small"),
+            (262, "en-us", "Text"),
+            (263, "en-us", "Display"),
+            (264, "en-us", "SemiLight"),
+            (265, "en-us", "Condensed"),
+            (266, "en-us", "Width"),
+            (267, "en-us", "Normal"),
+            (270, "en-us", "Instance"),
+        ]);
+        let segoe_fvar = Fvar::parse(&fvar(
+            &[
+                (WGHT, 300.0, 400.0, 700.0, 256),
+                (OPSZ, 8.0, 10.5, 36.0, 257),
+            ],
+            &[
+                (270, &[300.0, 8.0]),
+                (270, &[300.0, 12.0]),
+                (270, &[300.0, 36.0]),
+                (270, &[400.0, 12.0]),
+                (270, &[700.0, 36.0]),
+            ],
+        ))
+        .unwrap();
+        let segoe_stat = Stat::parse(&stat(
+            &[(WGHT, 256, 1), (OPSZ, 257, 0)],
+            &[
+                axis_value(1, 0, 0, 258, &[300.0]),
+                axis_value(1, 0, ELIDABLE_AXIS_VALUE_NAME, 259, &[400.0]),
+                axis_value(1, 0, 0, 260, &[700.0]),
+                axis_value(2, 1, 0, 261, &[8.0, 8.0, 11.0]),
+                axis_value(2, 1, 0, 262, &[12.0, 11.0, 20.0]),
+                axis_value(2, 1, 0, 263, &[36.0, 20.0, 36.0]),
+            ],
+            2,
+        ))
+        .unwrap();
+        let face_names = |fvar: &Fvar, stat: Option<&Stat>| {
+            fvar.instances
+                .iter()
+                .map(|instance| {
+                    let names = TypographicNames::from_named_instance(&names, fvar, instance, stat);
+                    assert_eq!(names.family_names[0].string, "Segoe UI Variable");
+                    names.face_names[0].string.clone()
+                })
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            face_names(&segoe_fvar, Some(&segoe_stat)),
+            vec![
+                "Small Light",
+                "Text Light",
+                "Display Light",
+                "Text",
+                "Display Bold"
+            ]
+        );
+        // STAT が無ければ fvar のサブファミリー名を使う．
+        assert_eq!(face_names(&segoe_fvar, None), vec!["Instance"; 5]);
+
+        let bahnschrift_fvar = Fvar::parse(&fvar(
+            &[
+                (WGHT, 300.0, 400.0, 700.0, 256),
+                (WDTH, 75.0, 100.0, 100.0, 266),
+            ],
+            &[
+                (270, &[300.0, 100.0]),
+                (270, &[350.0, 75.0]),
+                (270, &[400.0, 100.0]),
+                (270, &[400.0, 75.0]),
+            ],
+        ))
+        .unwrap();
+        let bahnschrift_stat = Stat::parse(&stat(
+            &[(WGHT, 256, 0), (WDTH, 266, 1)],
+            &[
+                axis_value(1, 0, 0, 258, &[300.0]),
+                axis_value(1, 0, 0, 264, &[350.0]),
+                axis_value(1, 0, ELIDABLE_AXIS_VALUE_NAME, 259, &[400.0]),
+                axis_value(1, 1, 0, 265, &[75.0]),
+                axis_value(1, 1, ELIDABLE_AXIS_VALUE_NAME, 267, &[100.0]),
+            ],
+            2,
+        ))
+        .unwrap();
+        assert_eq!(
+            face_names(&bahnschrift_fvar, Some(&bahnschrift_stat)),
+            vec!["Light", "SemiLight Condensed", "Regular", "Condensed"]
+        );
+    }
+}
@@ -1,14 +1,16 @@
 use core::fmt;
+#[cfg(windows)]
+use std::any::Any;
+use std::str::FromStr;
 
 use bitflags::bitflags;
+#[cfg(windows)]
 use windows::Win32::{
     Foundation::BOOL,
     Graphics::DirectWrite::{
         IDWriteFont, IDWriteFontFace, IDWriteFontFamily, IDWriteLocalizedStrings,
-        DWRITE_FONT_SIMULATIONS, DWRITE_FONT_SIMULATIONS_BOLD, DWRITE_FONT_SIMULATIONS_NONE,
-        DWRITE_FONT_SIMULATIONS_OBLIQUE, DWRITE_FONT_STRETCH, DWRITE_FONT_STYLE,
-        DWRITE_FONT_STYLE_ITALIC, DWRITE_FONT_STYLE_NORMAL, DWRITE_FONT_STYLE_OBLIQUE,
-        DWRITE_FONT_WEIGHT, DWRITE_INFORMATIONAL_STRING_COPYRIGHT_NOTICE,
+        DWRITE_FONT_SIMULATIONS, DWRITE_FONT_SIMULATIONS_NONE, DWRITE_FONT_STRETCH,
+        DWRITE_FONT_STYLE, DWRITE_FONT_WEIGHT, DWRITE_INFORMATIONAL_STRING_COPYRIGHT_NOTICE,
         DWRITE_INFORMATIONAL_STRING_DESCRIPTION, DWRITE_INFORMATIONAL_STRING_DESIGNER,
         DWRITE_INFORMATIONAL_STRING_DESIGNER_URL,
         DWRITE_INFORMATIONAL_STRING_DESIGN_SCRIPT_LANGUAGE_TAG,
@@ -32,24 +34,41 @@ use windows::Win32::{
     },
 };
 
-use crate::{font_face, font_family::FontFamily, localized_strings::LocalizedStrings};
+#[cfg(windows)]
+use crate::font_face;
+use crate::{
+    backend::FontBackend,
+    cmap::CoverageSet,
+    error::{Error, Result},
+    font_description::parse_angle,
+    font_family::FontFamily,
+    font_file::FontFileReference,
+    fvar::{Fvar, NamedInstance, VariationAxis},
+    localized_strings::LocalizedStrings,
+    sfnt::{is_collection, Sfnt},
+    woff,
+};
 
+#[cfg(windows)]
 pub fn get_simulations(font: &IDWriteFont) -> DWRITE_FONT_SIMULATIONS {
     unsafe { font.GetSimulations() }
 }
 
+#[cfg(windows)]
 pub fn is_simulation(font: &IDWriteFont) -> bool {
     get_simulations(font) != DWRITE_FONT_SIMULATIONS_NONE
 }
 
-pub fn get_face_names(font: &IDWriteFont) -> anyhow::Result<IDWriteLocalizedStrings> {
+#[cfg(windows)]
+pub fn get_face_names(font: &IDWriteFont) -> Result<IDWriteLocalizedStrings> {
     unsafe { Ok(font.GetFaceNames()?) }
 }
 
+#[cfg(windows)]
 pub fn get_informational_strings(
     font: &IDWriteFont,
     informational_string_id: DWRITE_INFORMATIONAL_STRING_ID,
-) -> anyhow::Result<Option<IDWriteLocalizedStrings>> {
+) -> Result<Option<IDWriteLocalizedStrings>> {
     let mut exists = BOOL::from(false);
     let mut strings = None;
     unsafe {
@@ -62,14 +81,16 @@ pub fn get_informational_strings(
     }
 }
 
-pub fn get_font_family(font: &IDWriteFont) -> anyhow::Result<IDWriteFontFamily> {
+#[cfg(windows)]
+pub fn get_font_family(font: &IDWriteFont) -> Result<IDWriteFontFamily> {
     unsafe {
         let family = font.GetFontFamily()?;
         Ok(family)
     }
 }
 
-#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Weight(pub i32);
 
 impl fmt::Display for Weight {
@@ -92,21 +113,135 @@ impl fmt::Display for Weight {
     }
 }
 
+// CSS の font-weight と DWRITE_FONT_WEIGHT の名前．
+const WEIGHT_NAMES: [(&str, i32); 17] = [
+    ("thin", 100),
+    ("extralight", 200),
+    ("ultralight", 200),
+    ("light", 300),
+    ("semilight", 350),
+    ("normal", 400),
+    ("regular", 400),
+    ("medium", 500),
+    ("semibold", 600),
+    ("demibold", 600),
+    ("bold", 700),
+    ("extrabold", 800),
+    ("ultrabold", 800),
+    ("black", 900),
+    ("heavy", 900),
+    ("extrablack", 950),
+    ("ultrablack", 950),
+];
+
+// 名前を比べるときの形．大文字と小文字，ハイフンや空白を区別しない．
+fn keyword(s: &str) -> String {
+    s.chars()
+        .filter(|c| !matches!(c, '-' | '_' | ' '))
+        .map(|c| c.to_ascii_lowercase())
+        .collect()
+}
+
+impl Weight {
+    // CSS の表記．400 と 700 はキーワード，それ以外は数値．
+    pub fn to_css(self) -> String {
+        match self.0 {
+            400 => "normal".to_string(),
+            700 => "bold".to_string(),
+            value => value.to_string(),
+        }
+    }
+
+    // OS/2 の usWeightClass から変換する．
+    // 1..=9 は古いフォントで使われていた値なので 100 倍する．範囲外は Regular とする．
+    pub fn from_weight_class(value: u16) -> Self {
+        match value {
+            1..=9 => Self(value as i32 * 100),
+            10..=1000 => Self(value as i32),
+            _ => Self(400),
+        }
+    }
+
+    pub fn to_weight_class(self) -> u16 {
+        self.0.clamp(1, 1000) as u16
+    }
+}
+
+// bold や SemiBold のような名前か，1 以上 1000 以下の数値．
+// bolder と lighter は親要素に対する相対的な値なので受け付けない．
+impl FromStr for Weight {
+    type Err = Error;
+    fn from_str(s: &str) -> Result<Self> {
+        let name = keyword(s);
+        if let Some((_, value)) = WEIGHT_NAMES.iter().find(|(other, _)| *other == name) {
+            return Ok(Self(*value));
+        }
+        match s.trim().parse::<f32>() {
+            Ok(value) if (1.0..=1000.0).contains(&value) => Ok(Self(value.round() as i32)),
+            _ => Err(Error::Parse(format!("invalid font weight: {}", s))),
+        }
+    }
+}
+
+#[cfg(windows)]
 impl From<DWRITE_FONT_WEIGHT> for Weight {
     fn from(value: DWRITE_FONT_WEIGHT) -> Self {
         Self(value.0)
     }
 }
 
+#[cfg(windows)]
+impl From<Weight> for DWRITE_FONT_WEIGHT {
+    fn from(value: Weight) -> Self {
+        Self(value.0)
+    }
+}
+
+#[cfg(windows)]
 pub fn get_weight(font: &IDWriteFont) -> DWRITE_FONT_WEIGHT {
     unsafe { font.GetWeight() }
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Style {
     Normal,
     Oblique,
     Italic,
+    // DWRITE_FONT_STYLE にない値．
+    Unknown(i32),
+}
+
+impl Style {
+    // DWRITE_FONT_STYLE と同じ値から変換する．未知の値は Unknown になる．
+    pub fn from_i32(value: i32) -> Self {
+        match value {
+            0 => Self::Normal,
+            1 => Self::Oblique,
+            2 => Self::Italic,
+            _ => Self::Unknown(value),
+        }
+    }
+
+    pub fn to_i32(self) -> i32 {
+        match self {
+            Self::Normal => 0,
+            Self::Oblique => 1,
+            Self::Italic => 2,
+            Self::Unknown(value) => value,
+        }
+    }
+}
+
+// 未知の値を受け付けない変換．エラーには元の値が入る．
+impl TryFrom<i32> for Style {
+    type Error = i32;
+    fn try_from(value: i32) -> std::result::Result<Self, Self::Error> {
+        match Self::from_i32(value) {
+            Self::Unknown(value) => Err(value),
+            style => Ok(style),
+        }
+    }
 }
 
 impl fmt::Display for Style {
@@ -115,38 +250,124 @@ impl fmt::Display for Style {
             Self::Italic => "Italic",
             Self::Normal => "Normal",
             Self::Oblique => "Oblique",
+            Self::Unknown(value) => return write!(f, "Unknown({})", value),
         };
         write!(f, "{}", s)
     }
 }
 
+impl Style {
+    // CSS の表記．Unknown は normal とする．
+    pub fn to_css(self) -> &'static str {
+        match self {
+            Self::Italic => "italic",
+            Self::Oblique => "oblique",
+            Self::Normal | Self::Unknown(_) => "normal",
+        }
+    }
+}
+
+// normal，italic，oblique．CSS と同じく oblique の後の角度 (oblique 12deg など) も受け付けるが，
+// Style には残らない．角度が必要なら FontDescription を使う．
+impl FromStr for Style {
+    type Err = Error;
+    fn from_str(s: &str) -> Result<Self> {
+        let error = || Error::Parse(format!("invalid font style: {}", s));
+        let mut words = s.split_whitespace();
+        let style = match words.next().map(keyword).as_deref() {
+            Some("normal") => Self::Normal,
+            Some("italic") => Self::Italic,
+            Some("oblique") => Self::Oblique,
+            _ => return Err(error()),
+        };
+        match (style, words.next(), words.next()) {
+            (_, None, _) => Ok(style),
+            (Self::Oblique, Some(angle), None) if parse_angle(angle).is_some() => Ok(style),
+            _ => Err(error()),
+        }
+    }
+}
+
+#[cfg(windows)]
 impl From<DWRITE_FONT_STYLE> for Style {
     fn from(value: DWRITE_FONT_STYLE) -> Self {
-        match value {
-            DWRITE_FONT_STYLE_ITALIC => Self::Italic,
-            DWRITE_FONT_STYLE_NORMAL => Self::Normal,
-            DWRITE_FONT_STYLE_OBLIQUE => Self::Oblique,
-            _ => panic!("invalid style"),
-        }
+        Self::from_i32(value.0)
+    }
+}
+
+#[cfg(windows)]
+impl From<Style> for DWRITE_FONT_STYLE {
+    fn from(value: Style) -> Self {
+        Self(value.to_i32())
     }
 }
 
+#[cfg(windows)]
 pub fn get_style(font: &IDWriteFont) -> DWRITE_FONT_STYLE {
     unsafe { font.GetStyle() }
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Stretch {
-    Undefined = 0,
-    UltraCondensed = 1,
-    ExtraCondensed = 2,
-    Condensed = 3,
-    SemiCondensed = 4,
-    Normal = 5,
-    SemiExpanded = 6,
-    Expanded = 7,
-    ExtraExpanded = 8,
-    UltraExpanded = 9,
+    Undefined,
+    UltraCondensed,
+    ExtraCondensed,
+    Condensed,
+    SemiCondensed,
+    Normal,
+    SemiExpanded,
+    Expanded,
+    ExtraExpanded,
+    UltraExpanded,
+    // DWRITE_FONT_STRETCH にない値．
+    Unknown(i32),
+}
+
+impl Stretch {
+    // DWRITE_FONT_STRETCH と同じ値から変換する．未知の値は Unknown になる．
+    pub fn from_i32(value: i32) -> Self {
+        match value {
+            0 => Self::Undefined,
+            1 => Self::UltraCondensed,
+            2 => Self::ExtraCondensed,
+            3 => Self::Condensed,
+            4 => Self::SemiCondensed,
+            5 => Self::Normal,
+            6 => Self::SemiExpanded,
+            7 => Self::Expanded,
+            8 => Self::ExtraExpanded,
+            9 => Self::UltraExpanded,
+            _ => Self::Unknown(value),
+        }
+    }
+
+    pub fn to_i32(self) -> i32 {
+        match self {
+            Self::Undefined => 0,
+            Self::UltraCondensed => 1,
+            Self::ExtraCondensed => 2,
+            Self::Condensed => 3,
+            Self::SemiCondensed => 4,
+            Self::Normal => 5,
+            Self::SemiExpanded => 6,
+            Self::Expanded => 7,
+            Self::ExtraExpanded => 8,
+            Self::UltraExpanded => 9,
+            Self::Unknown(value) => value,
+        }
+    }
+}
+
+// 未知の値を受け付けない変換．エラーには元の値が入る．
+impl TryFrom<i32> for Stretch {
+    type Error = i32;
+    fn try_from(value: i32) -> std::result::Result<Self, Self::Error> {
+        match Self::from_i32(value) {
+            Self::Unknown(value) => Err(value),
+            stretch => Ok(stretch),
+        }
+    }
 }
 
 impl fmt::Display for Stretch {
@@ -162,29 +383,109 @@ impl fmt::Display for Stretch {
             Stretch::Expanded => "Expanded",
             Stretch::ExtraExpanded => "ExtraExpanded",
             Stretch::UltraExpanded => "UltraExpanded",
+            Stretch::Unknown(value) => return write!(f, "Unknown({})", value),
         };
         write!(f, "{}", s)
     }
 }
 
+// CSS の font-stretch の百分率．
+const STRETCH_PERCENTAGES: [(Stretch, f32); 9] = [
+    (Stretch::UltraCondensed, 50.0),
+    (Stretch::ExtraCondensed, 62.5),
+    (Stretch::Condensed, 75.0),
+    (Stretch::SemiCondensed, 87.5),
+    (Stretch::Normal, 100.0),
+    (Stretch::SemiExpanded, 112.5),
+    (Stretch::Expanded, 125.0),
+    (Stretch::ExtraExpanded, 150.0),
+    (Stretch::UltraExpanded, 200.0),
+];
+
+impl Stretch {
+    // Undefined と Unknown には対応する百分率がない．
+    pub fn to_percentage(self) -> Option<f32> {
+        STRETCH_PERCENTAGES
+            .iter()
+            .find(|(stretch, _)| *stretch == self)
+            .map(|(_, percentage)| *percentage)
+    }
+
+    // 一番近い百分率の幅にする．
+    pub fn from_percentage(value: f32) -> Self {
+        STRETCH_PERCENTAGES
+            .iter()
+            .min_by(|(_, a), (_, b)| (a - value).abs().total_cmp(&(b - value).abs()))
+            .map(|(stretch, _)| *stretch)
+            .unwrap_or(Self::Normal)
+    }
+
+    // CSS のキーワード．Undefined と Unknown は normal とする．
+    pub fn to_css(self) -> &'static str {
+        match self {
+            Self::UltraCondensed => "ultra-condensed",
+            Self::ExtraCondensed => "extra-condensed",
+            Self::Condensed => "condensed",
+            Self::SemiCondensed => "semi-condensed",
+            Self::SemiExpanded => "semi-expanded",
+            Self::Expanded => "expanded",
+            Self::ExtraExpanded => "extra-expanded",
+            Self::UltraExpanded => "ultra-expanded",
+            Self::Undefined | Self::Normal | Self::Unknown(_) => "normal",
+        }
+    }
+
+    // OS/2 の usWidthClass から変換する．範囲外は Normal とする．
+    pub fn from_width_class(value: u16) -> Self {
+        match value {
+            1..=9 => Self::from_i32(value as i32),
+            _ => Self::Normal,
+        }
+    }
+
+    pub fn to_width_class(self) -> u16 {
+        match self.to_i32() {
+            value @ 1..=9 => value as u16,
+            _ => 5,
+        }
+    }
+}
+
+// semi-condensed や SemiCondensed のようなキーワードか，87.5% のような百分率．
+impl FromStr for Stretch {
+    type Err = Error;
+    fn from_str(s: &str) -> Result<Self> {
+        let name = keyword(s);
+        if let Some((stretch, _)) = STRETCH_PERCENTAGES
+            .iter()
+            .find(|(stretch, _)| keyword(&stretch.to_string()) == name)
+        {
+            return Ok(*stretch);
+        }
+        match s.trim().strip_suffix('%').map(|value| value.parse::<f32>()) {
+            Some(Ok(value)) if value.is_finite() && value >= 0.0 => {
+                Ok(Self::from_percentage(value))
+            }
+            _ => Err(Error::Parse(format!("invalid font stretch: {}", s))),
+        }
+    }
+}
+
+#[cfg(windows)]
 impl From<DWRITE_FONT_STRETCH> for Stretch {
     fn from(value: DWRITE_FONT_STRETCH) -> Self {
-        match value.0 {
-            0 => Stretch::Undefined,
-            1 => Stretch::UltraCondensed,
-            2 => Stretch::ExtraCondensed,
-            3 => Stretch::Condensed,
-            4 => Stretch::SemiCondensed,
-            5 => Stretch::Normal,
-            6 => Stretch::SemiExpanded,
-            7 => Stretch::Expanded,
-            8 => Stretch::ExtraExpanded,
-            9 => Stretch::UltraExpanded,
-            _ => panic!("invalid stretch"),
-        }
+        Self::from_i32(value.0)
+    }
+}
+
+#[cfg(windows)]
+impl From<Stretch> for DWRITE_FONT_STRETCH {
+    fn from(value: Stretch) -> Self {
+        Self(value.to_i32())
     }
 }
 
+#[cfg(windows)]
 pub fn get_stretch(font: &IDWriteFont) -> DWRITE_FONT_STRETCH {
     unsafe { font.GetStretch() }
 }
@@ -192,14 +493,17 @@ pub fn get_stretch(font: &IDWriteFont) -> DWRITE_FONT_STRETCH {
 bitflags! {
     #[repr(transparent)]
     #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+    #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
     pub struct Simulations: i32 {
-        const None = DWRITE_FONT_SIMULATIONS_NONE.0;
-        const Bold = DWRITE_FONT_SIMULATIONS_BOLD.0;
-        const Oblique = DWRITE_FONT_SIMULATIONS_OBLIQUE.0;
+        // DWRITE_FONT_SIMULATIONS と同じ値．
+        const None = 0x0000;
+        const Bold = 0x0001;
+        const Oblique = 0x0002;
     }
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum InformationalStringId {
     None,
     CopyrightNotice,
@@ -228,53 +532,113 @@ pub enum InformationalStringId {
     WwsFamilyName,
 }
 
-pub fn create_font_face(font: &IDWriteFont) -> anyhow::Result<IDWriteFontFace> {
+impl InformationalStringId {
+    // None と別名を除いたすべての ID．
+    pub const ALL: [Self; 21] = [
+        Self::CopyrightNotice,
+        Self::VersionStrings,
+        Self::Trademark,
+        Self::Manufacturer,
+        Self::Designer,
+        Self::DesignerUrl,
+        Self::Description,
+        Self::FontVendorUrl,
+        Self::LicenseDescription,
+        Self::LicenseInfoUrl,
+        Self::Win32FamilyNames,
+        Self::Win32SubfamilyNames,
+        Self::TypographicFamilyNames,
+        Self::TypographicSubfamilyNames,
+        Self::SampleText,
+        Self::FullName,
+        Self::PostscriptName,
+        Self::PostscriptCidName,
+        Self::WeightStretchStyleFamilyName,
+        Self::DesignScriptLanguageTag,
+        Self::SupportedScriptLanguageTag,
+    ];
+
+    // DirectWrite では Preferred* は Typographic* と，WwsFamilyName は WeightStretchStyleFamilyName と同じ値．
+    pub fn canonical(self) -> Self {
+        match self {
+            Self::PreferredFamilyNames => Self::TypographicFamilyNames,
+            Self::PreferredSubfamilyNames => Self::TypographicSubfamilyNames,
+            Self::WwsFamilyName => Self::WeightStretchStyleFamilyName,
+            _ => self,
+        }
+    }
+
+    // JSON や表に書く名前．Debug の表記に頼らず，列挙子を変えても出力が変わらないようにする．
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::None => "None",
+            Self::CopyrightNotice => "CopyrightNotice",
+            Self::VersionStrings => "VersionStrings",
+            Self::Trademark => "Trademark",
+            Self::Manufacturer => "Manufacturer",
+            Self::Designer => "Designer",
+            Self::DesignerUrl => "DesignerUrl",
+            Self::Description => "Description",
+            Self::FontVendorUrl => "FontVendorUrl",
+            Self::LicenseDescription => "LicenseDescription",
+            Self::LicenseInfoUrl => "LicenseInfoUrl",
+            Self::Win32FamilyNames => "Win32FamilyNames",
+            Self::Win32SubfamilyNames => "Win32SubfamilyNames",
+            Self::TypographicFamilyNames => "TypographicFamilyNames",
+            Self::TypographicSubfamilyNames => "TypographicSubfamilyNames",
+            Self::SampleText => "SampleText",
+            Self::FullName => "FullName",
+            Self::PostscriptName => "PostscriptName",
+            Self::PostscriptCidName => "PostscriptCidName",
+            Self::WeightStretchStyleFamilyName => "WeightStretchStyleFamilyName",
+            Self::DesignScriptLanguageTag => "DesignScriptLanguageTag",
+            Self::SupportedScriptLanguageTag => "SupportedScriptLanguageTag",
+            Self::PreferredFamilyNames => "PreferredFamilyNames",
+            Self::PreferredSubfamilyNames => "PreferredSubfamilyNames",
+            Self::WwsFamilyName => "WwsFamilyName",
+        }
+    }
+}
+
+#[cfg(windows)]
+pub fn create_font_face(font: &IDWriteFont) -> Result<IDWriteFontFace> {
     let face = unsafe { font.CreateFontFace() }?;
     Ok(face)
 }
 
-pub fn get_filepath(font: &IDWriteFont) -> anyhow::Result<String> {
+#[cfg(windows)]
+pub fn get_filepath(font: &IDWriteFont) -> Result<String> {
     let face = create_font_face(font)?;
     font_face::get_filepath(&face)
 }
 
-pub fn is_variable_font(font: &IDWriteFont) -> anyhow::Result<bool> {
+#[cfg(windows)]
+pub fn get_file_reference(font: &IDWriteFont) -> Result<FontFileReference> {
     let face = create_font_face(font)?;
-    font_face::is_variable_font(&face)
+    font_face::get_file_reference(&face)
 }
 
-pub struct Font(pub IDWriteFont);
-
-impl Font {
-    pub fn get_font_family(&self) -> anyhow::Result<FontFamily> {
-        get_font_family(&self.0).map(FontFamily)
-    }
-
-    pub fn get_simulations(&self) -> Simulations {
-        Simulations::from_bits_retain(get_simulations(&self.0).0)
-    }
-
-    pub fn get_weight(&self) -> Weight {
-        get_weight(&self.0).into()
-    }
-
-    pub fn get_style(&self) -> Style {
-        get_style(&self.0).into()
-    }
+#[cfg(windows)]
+pub fn get_face_index(font: &IDWriteFont) -> Result<u32> {
+    let face = create_font_face(font)?;
+    Ok(font_face::get_index(&face))
+}
 
-    pub fn get_stretch(&self) -> Stretch {
-        get_stretch(&self.0).into()
-    }
+#[cfg(windows)]
+pub fn has_character(font: &IDWriteFont, c: u32) -> Result<bool> {
+    Ok(unsafe { font.HasCharacter(c) }?.as_bool())
+}
 
-    pub fn get_face_names(&self) -> anyhow::Result<LocalizedStrings> {
-        get_face_names(&self.0).map(LocalizedStrings)
-    }
+#[cfg(windows)]
+pub fn is_variable_font(font: &IDWriteFont) -> Result<bool> {
+    let face = create_font_face(font)?;
+    font_face::is_variable_font(&face)
+}
 
-    pub fn get_informational_strings(
-        &self,
-        informational_string_id: InformationalStringId,
-    ) -> anyhow::Result<Option<LocalizedStrings>> {
-        let informational_string_id = match informational_string_id {
+#[cfg(windows)]
+impl From<InformationalStringId> for DWRITE_INFORMATIONAL_STRING_ID {
+    fn from(value: InformationalStringId) -> Self {
+        match value {
             InformationalStringId::None => DWRITE_INFORMATIONAL_STRING_NONE,
             InformationalStringId::CopyrightNotice => DWRITE_INFORMATIONAL_STRING_COPYRIGHT_NOTICE,
             InformationalStringId::VersionStrings => DWRITE_INFORMATIONAL_STRING_VERSION_STRINGS,
@@ -322,19 +686,182 @@ impl Font {
                 DWRITE_INFORMATIONAL_STRING_PREFERRED_SUBFAMILY_NAMES
             }
             InformationalStringId::WwsFamilyName => DWRITE_INFORMATIONAL_STRING_WWS_FAMILY_NAME,
-        };
-        Ok(get_informational_strings(&self.0, informational_string_id)?.map(LocalizedStrings))
+        }
+    }
+}
+
+#[cfg(windows)]
+impl FontBackend for IDWriteFont {
+    fn get_font_family(&self) -> Result<FontFamily> {
+        get_font_family(self).map(FontFamily::new)
+    }
+
+    fn get_simulations(&self) -> Simulations {
+        Simulations::from_bits_retain(get_simulations(self).0)
+    }
+
+    fn get_weight(&self) -> Weight {
+        get_weight(self).into()
+    }
+
+    fn get_style(&self) -> Style {
+        get_style(self).into()
+    }
+
+    fn get_stretch(&self) -> Stretch {
+        get_stretch(self).into()
+    }
+
+    fn get_face_names(&self) -> Result<LocalizedStrings> {
+        get_face_names(self).map(LocalizedStrings::new)
+    }
+
+    fn get_informational_strings(
+        &self,
+        informational_string_id: InformationalStringId,
+    ) -> Result<Option<LocalizedStrings>> {
+        Ok(
+            get_informational_strings(self, informational_string_id.into())?
+                .map(LocalizedStrings::new),
+        )
+    }
+
+    fn get_filepath(&self) -> Result<String> {
+        get_filepath(self)
+    }
+
+    fn get_file_reference(&self) -> Result<FontFileReference> {
+        get_file_reference(self)
+    }
+
+    fn get_face_index(&self) -> Result<u32> {
+        get_face_index(self)
+    }
+
+    fn is_variable_font(&self) -> Result<bool> {
+        is_variable_font(self)
+    }
+
+    fn has_character(&self, c: u32) -> Result<bool> {
+        has_character(self, c)
+    }
+
+    fn get_font_table(&self, tag: u32) -> Result<Option<Vec<u8>>> {
+        let face = create_font_face(self)?;
+        let mut table = None;
+        font_face::map_font_table(&face, tag, |data| {
+            table = Some(data.to_vec());
+        })?;
+        Ok(table)
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+pub struct Font(Box<dyn FontBackend>);
+
+impl Font {
+    pub fn new<T: FontBackend + 'static>(backend: T) -> Self {
+        Self(Box::new(backend))
+    }
+
+    pub fn backend(&self) -> &dyn FontBackend {
+        self.0.as_ref()
+    }
+
+    #[cfg(windows)]
+    pub fn as_dwrite(&self) -> Option<&IDWriteFont> {
+        self.0.as_any().downcast_ref()
+    }
+
+    pub fn get_font_family(&self) -> Result<FontFamily> {
+        self.0.get_font_family()
+    }
+
+    pub fn get_simulations(&self) -> Simulations {
+        self.0.get_simulations()
+    }
+
+    pub fn get_weight(&self) -> Weight {
+        self.0.get_weight()
+    }
+
+    pub fn get_style(&self) -> Style {
+        self.0.get_style()
+    }
+
+    pub fn get_stretch(&self) -> Stretch {
+        self.0.get_stretch()
     }
 
-    pub fn get_filepath(&self) -> anyhow::Result<String> {
-        get_filepath(&self.0)
+    pub fn get_face_names(&self) -> Result<LocalizedStrings> {
+        self.0.get_face_names()
+    }
+
+    pub fn get_informational_strings(
+        &self,
+        informational_string_id: InformationalStringId,
+    ) -> Result<Option<LocalizedStrings>> {
+        self.0.get_informational_strings(informational_string_id)
+    }
+
+    pub fn get_filepath(&self) -> Result<String> {
+        self.0.get_filepath()
+    }
+
+    pub fn get_file_reference(&self) -> Result<FontFileReference> {
+        self.0.get_file_reference()
+    }
+
+    pub fn get_face_index(&self) -> Result<u32> {
+        self.0.get_face_index()
+    }
+
+    // get_file_reference のファイルを読み，このフェイスだけの sfnt を返す．
+    // WOFF, WOFF2 は sfnt に変換し，コレクションからはこのフェイスのテーブルを取り出す．
+    pub fn read_face_data(&self) -> Result<Vec<u8>> {
+        let file = self.get_file_reference()?;
+        let data = file.read()?;
+        let data = woff::decode_font_data(&data)?;
+        if is_collection(&data) {
+            Ok(Sfnt::parse_face(&data, self.get_face_index()?)?.to_bytes()?)
+        } else {
+            Ok(data.into_owned())
+        }
     }
 
     pub fn is_simulation(&self) -> bool {
-        is_simulation(&self.0)
+        self.get_simulations() != Simulations::None
+    }
+
+    pub fn is_variable_font(&self) -> Result<bool> {
+        self.0.is_variable_font()
+    }
+
+    pub fn get_font_table(&self, tag: u32) -> Result<Option<Vec<u8>>> {
+        self.0.get_font_table(tag)
+    }
+
+    pub fn has_character(&self, c: char) -> Result<bool> {
+        self.0.has_character(c as u32)
+    }
+
+    pub fn coverage(&self) -> Result<CoverageSet> {
+        self.0.coverage()
+    }
+
+    // バリアブルフォントでない場合は空．
+    pub fn get_variation_axes(&self) -> Result<Vec<VariationAxis>> {
+        Ok(Fvar::from_font(self)?
+            .map(|fvar| fvar.axes)
+            .unwrap_or_default())
     }
 
-    pub fn is_variable_font(&self) -> anyhow::Result<bool> {
-        is_variable_font(&self.0)
+    pub fn get_named_instances(&self) -> Result<Vec<NamedInstance>> {
+        Ok(Fvar::from_font(self)?
+            .map(|fvar| fvar.instances)
+            .unwrap_or_default())
     }
 }
@@ -1,3 +1,7 @@
+#[cfg(windows)]
+use std::any::Any;
+
+#[cfg(windows)]
 use windows::{
     core::PCWSTR,
     Win32::{
@@ -6,23 +10,33 @@ use windows::{
     },
 };
 
-use crate::font_family::FontFamily;
+use core::fmt;
+
+use crate::{
+    backend::FontCollectionBackend,
+    error::{Error, Result},
+    font::Font,
+    font_family::FontFamily,
+};
 
+#[cfg(windows)]
 pub fn get_font_family_count(collection: &IDWriteFontCollection) -> u32 {
     unsafe { collection.GetFontFamilyCount() }
 }
 
+#[cfg(windows)]
 pub fn get_font_family(
     collection: &IDWriteFontCollection,
     index: u32,
-) -> anyhow::Result<IDWriteFontFamily> {
+) -> Result<IDWriteFontFamily> {
     Ok(unsafe { collection.GetFontFamily(index) }?)
 }
 
+#[cfg(windows)]
 pub fn find_family_name(
     collection: &IDWriteFontCollection,
     family_name: &str,
-) -> anyhow::Result<(u32, bool)> {
+) -> Result<(u32, bool)> {
     unsafe {
         let mut index = 0_u32;
         let mut exists = BOOL::from(false);
@@ -34,6 +48,7 @@ pub fn find_family_name(
     }
 }
 
+#[cfg(windows)]
 pub fn find_family(
     collection: &IDWriteFontCollection,
     family_name: &str,
@@ -46,18 +61,174 @@ pub fn find_family(
     }
 }
 
-pub struct FontCollection(pub IDWriteFontCollection);
+#[cfg(windows)]
+impl FontCollectionBackend for IDWriteFontCollection {
+    fn get_font_family_count(&self) -> u32 {
+        get_font_family_count(self)
+    }
+
+    fn get_font_family(&self, index: u32) -> Result<FontFamily> {
+        get_font_family(self, index).map(FontFamily::new)
+    }
+
+    fn find_family_name(&self, family_name: &str) -> Result<(u32, bool)> {
+        find_family_name(self, family_name)
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+pub struct FontCollection(Box<dyn FontCollectionBackend>);
 impl FontCollection {
+    pub fn new<T: FontCollectionBackend + 'static>(backend: T) -> Self {
+        Self(Box::new(backend))
+    }
+
+    pub fn backend(&self) -> &dyn FontCollectionBackend {
+        self.0.as_ref()
+    }
+
+    #[cfg(windows)]
+    pub fn as_dwrite(&self) -> Option<&IDWriteFontCollection> {
+        self.0.as_any().downcast_ref()
+    }
+
     pub fn get_font_family_count(&self) -> u32 {
-        get_font_family_count(&self.0)
+        self.0.get_font_family_count()
     }
 
-    pub fn get_font_family(&self, index: u32) -> anyhow::Result<FontFamily> {
-        get_font_family(&self.0, index).map(FontFamily)
+    pub fn get_font_family(&self, index: u32) -> Result<FontFamily> {
+        self.0.get_font_family(index)
+    }
+
+    pub fn find_family_name(&self, family_name: &str) -> Result<(u32, bool)> {
+        self.0.find_family_name(family_name)
     }
 
     pub fn find_family(&self, family_name: &str) -> Option<FontFamily> {
-        find_family(&self.0, family_name).map(FontFamily)
+        let (index, exists) = self.find_family_name(family_name).ok()?;
+        if exists {
+            self.get_font_family(index).ok()
+        } else {
+            None
+        }
+    }
+
+    // 取得できないファミリーやフォントを飛ばして，すべてのフォントに f を適用する．
+    // f が返したエラーも失敗として報告に記録される．
+    pub fn for_each_font<F>(&self, mut f: F) -> EnumerationReport
+    where
+        F: FnMut(&FontFamily, Font) -> Result<()>,
+    {
+        let mut report = EnumerationReport::default();
+        for (family_index, family) in self.into_iter().enumerate() {
+            let family_index = family_index as u32;
+            let family = match family {
+                Ok(family) => family,
+                Err(error) => {
+                    report.failures.push(EnumerationFailure {
+                        family_index,
+                        font_index: None,
+                        family_name: None,
+                        error,
+                    });
+                    continue;
+                }
+            };
+            report.family_count += 1;
+            for (font_index, font) in family.into_iter().enumerate() {
+                match font.and_then(|font| f(&family, font)) {
+                    Ok(()) => report.font_count += 1,
+                    Err(error) => report.failures.push(EnumerationFailure {
+                        family_index,
+                        font_index: Some(font_index as u32),
+                        family_name: get_family_name(&family),
+                        error,
+                    }),
+                }
+            }
+        }
+        report
+    }
+
+    pub fn get_fonts(&self) -> (Vec<Font>, EnumerationReport) {
+        let mut fonts = vec![];
+        let report = self.for_each_font(|_, font| {
+            fonts.push(font);
+            Ok(())
+        });
+        (fonts, report)
+    }
+}
+
+// 報告用のファミリー名．en-us がなければ先頭の名前を使う．
+fn get_family_name(family: &FontFamily) -> Option<String> {
+    let names = family.get_family_names().ok()?;
+    match names.get("en-us").ok()? {
+        Some(name) => Some(name),
+        None => names.get_string(0).ok(),
+    }
+}
+
+// 列挙に失敗したファミリーまたはフォント．
+#[derive(Debug)]
+pub struct EnumerationFailure {
+    pub family_index: u32,
+    // ファミリー自体を取得できなかった場合は None．
+    pub font_index: Option<u32>,
+    pub family_name: Option<String>,
+    pub error: Error,
+}
+
+impl fmt::Display for EnumerationFailure {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "family {}", self.family_index)?;
+        if let Some(family_name) = &self.family_name {
+            write!(f, " ({})", family_name)?;
+        }
+        if let Some(font_index) = self.font_index {
+            write!(f, " font {}", font_index)?;
+        }
+        write!(f, ": {}", self.error)
+    }
+}
+
+// for_each_font の結果．family_count と font_count は成功した数．
+#[derive(Debug, Default)]
+pub struct EnumerationReport {
+    pub family_count: u32,
+    pub font_count: u32,
+    pub failures: Vec<EnumerationFailure>,
+}
+
+impl EnumerationReport {
+    pub fn is_complete(&self) -> bool {
+        self.failures.is_empty()
+    }
+
+    pub fn remote_font_count(&self) -> usize {
+        self.failures
+            .iter()
+            .filter(|failure| failure.error.is_remote_font())
+            .count()
+    }
+}
+
+impl fmt::Display for EnumerationReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "{} families, {} fonts, {} failures",
+            self.family_count,
+            self.font_count,
+            self.failures.len()
+        )?;
+        for failure in &self.failures {
+            writeln!(f, "  {}", failure)?;
+        }
+        Ok(())
     }
 }
 
@@ -66,35 +237,34 @@ pub struct FontCollectionIter<'a> {
     index: usize,
 }
 
+// 要素の取得に失敗しても止まらず，その要素を Err として返す．
 impl<'a> Iterator for FontCollectionIter<'a> {
-    type Item = FontFamily;
+    type Item = Result<FontFamily>;
     fn next(&mut self) -> Option<Self::Item> {
-        if self.index < self.len() {
-            let family = self.collection.get_font_family(self.index as u32);
+        if self.index < self.collection.get_font_family_count() as usize {
+            let item = self.collection.get_font_family(self.index as u32);
             self.index += 1;
-            match family {
-                Ok(family) => Some(family),
-                Err(e) => {
-                    eprintln!("{}", e);
-                    assert!(false);
-                    None
-                }
-            }
+            Some(item)
         } else {
             None
         }
     }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = self.len();
+        (len, Some(len))
+    }
 }
 
 impl<'a> ExactSizeIterator for FontCollectionIter<'a> {
     #[inline]
     fn len(&self) -> usize {
-        self.collection.get_font_family_count() as usize
+        (self.collection.get_font_family_count() as usize).saturating_sub(self.index)
     }
 }
 
 impl<'a> IntoIterator for &'a FontCollection {
-    type Item = FontFamily;
+    type Item = Result<FontFamily>;
     type IntoIter = FontCollectionIter<'a>;
     fn into_iter(self) -> Self::IntoIter {
         Self::IntoIter {
@@ -0,0 +1,206 @@
+use std::path::{Path, PathBuf};
+
+use crate::{
+    error::{Error, Result},
+    factory::Factory,
+    family_model::FontFamilyModel,
+    font_collection::FontCollection,
+    font_file::FontFileReference,
+    portable::{collect_font_files, FONT_EXTENSIONS},
+};
+
+// ディレクトリやファイルからフォントコレクションを作る．
+// ファミリーへのまとめ方は family_model で選ぶ．
+//
+// let collection = FontCollectionBuilder::new()
+//     .add_directory("assets/fonts")
+//     .extensions(&["ttf", "otf"])
+//     .build(&factory)?;
+#[derive(Debug, Clone)]
+pub struct FontCollectionBuilder {
+    directories: Vec<PathBuf>,
+    files: Vec<FontFileReference>,
+    extensions: Vec<String>,
+    recursive: bool,
+    family_model: FontFamilyModel,
+}
+
+impl FontCollectionBuilder {
+    // 既定ではサブディレクトリも走査し，portable バックエンドと同じ拡張子のファイルを読む．
+    pub fn new() -> Self {
+        Self {
+            directories: vec![],
+            files: vec![],
+            extensions: FONT_EXTENSIONS.iter().map(|e| e.to_string()).collect(),
+            recursive: true,
+            family_model: FontFamilyModel::WeightStretchStyle,
+        }
+    }
+
+    pub fn add_directory<P: Into<PathBuf>>(mut self, directory: P) -> Self {
+        self.directories.push(directory.into());
+        self
+    }
+
+    pub fn add_file<F: Into<FontFileReference>>(mut self, file: F) -> Self {
+        self.files.push(file.into());
+        self
+    }
+
+    pub fn extensions<S: AsRef<str>>(mut self, extensions: &[S]) -> Self {
+        self.extensions = extensions.iter().map(|e| e.as_ref().to_string()).collect();
+        self
+    }
+
+    pub fn recursive(mut self, recursive: bool) -> Self {
+        self.recursive = recursive;
+        self
+    }
+
+    pub fn family_model(mut self, family_model: FontFamilyModel) -> Self {
+        self.family_model = family_model;
+        self
+    }
+
+    // ディレクトリを走査して，コレクションに入るファイルを返す．
+    // ディレクトリのファイルはパスの順に並べ，add_file のファイルはその後に続ける．
+    // add_file のファイルが無いか開けない場合はエラーにする．
+    pub fn collect_files(&self) -> Result<Vec<FontFileReference>> {
+        let mut files = vec![];
+        for directory in &self.directories {
+            check_directory(directory)?;
+            let mut paths = vec![];
+            collect_font_files(directory, &self.extensions, self.recursive, &mut paths);
+            paths.sort();
+            files.extend(paths.into_iter().map(FontFileReference::Local));
+        }
+        for file in &self.files {
+            if let FontFileReference::Local(path) = file {
+                check_file(path)?;
+            }
+            files.push(file.clone());
+        }
+        Ok(files)
+    }
+
+    // フォントとして読めないファイルは無視する．
+    pub fn build(&self, factory: &Factory) -> Result<FontCollection> {
+        factory.create_font_collection_with_model(&self.collect_files()?, self.family_model)
+    }
+}
+
+impl Default for FontCollectionBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// 指定されたディレクトリ自体が読めない場合はエラーにする．
+fn check_directory(directory: &Path) -> Result<()> {
+    let metadata = std::fs::metadata(directory).map_err(|e| Error::file_access(directory, e))?;
+    if metadata.is_dir() {
+        Ok(())
+    } else {
+        Err(Error::file_access(
+            directory,
+            std::io::Error::new(std::io::ErrorKind::InvalidInput, "not a directory"),
+        ))
+    }
+}
+
+fn check_file(path: &Path) -> Result<()> {
+    let metadata = std::fs::metadata(path).map_err(|e| Error::file_access(path, e))?;
+    if !metadata.is_file() {
+        return Err(Error::file_access(
+            path,
+            std::io::Error::new(std::io::ErrorKind::InvalidInput, "not a file"),
+        ));
+    }
+    std::fs::File::open(path).map_err(|e| Error::file_access(path, e))?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_fonts::{build_font, FS_SELECTION_BOLD, FS_SELECTION_REGULAR};
+
+    #[test]
+    fn collect_files() {
+        let directory =
+            std::env::temp_dir().join(format!("dwrite_rs_builder_{}", std::process::id()));
+        let sub_directory = directory.join("sub");
+        std::fs::create_dir_all(&sub_directory).unwrap();
+        let regular = build_font("Test Sans", "Regular", 400, FS_SELECTION_REGULAR);
+        std::fs::write(directory.join("b.TTF"), &regular).unwrap();
+        std::fs::write(directory.join("a.otf"), &regular).unwrap();
+        std::fs::write(directory.join("readme.txt"), b"not a font").unwrap();
+        let bold = build_font("Test Sans", "Bold", 700, FS_SELECTION_BOLD);
+        std::fs::write(sub_directory.join("bold.ttf"), &bold).unwrap();
+
+        let get_paths = |builder: FontCollectionBuilder| {
+            builder
+                .collect_files()
+                .unwrap()
+                .into_iter()
+                .map(|file| match file {
+                    FontFileReference::Local(path) => path,
+                    _ => PathBuf::new(),
+                })
+                .collect::<Vec<_>>()
+        };
+        let builder = FontCollectionBuilder::new().add_directory(&directory);
+        assert_eq!(
+            get_paths(builder.clone()),
+            vec![
+                directory.join("a.otf"),
+                directory.join("b.TTF"),
+                sub_directory.join("bold.ttf"),
+            ]
+        );
+        assert_eq!(
+            get_paths(builder.clone().recursive(false).extensions(&[".ttf"])),
+            vec![directory.join("b.TTF")]
+        );
+        assert_eq!(
+            get_paths(builder.clone().add_file(directory.join("readme.txt"))).last(),
+            Some(&directory.join("readme.txt"))
+        );
+
+        let collection = builder.build(&Factory::portable()).unwrap();
+        assert_eq!(collection.get_font_family_count(), 1);
+        assert_eq!(collection.get_font_family(0).unwrap().get_font_count(), 2);
+        std::fs::remove_dir_all(&directory).unwrap();
+    }
+
+    // 指定されたディレクトリやファイルが無ければエラーにする．
+    #[test]
+    fn missing_paths() {
+        let missing = std::env::temp_dir().join("dwrite_rs_builder_missing");
+        let factory = Factory::portable();
+        let result = FontCollectionBuilder::new()
+            .add_directory(&missing)
+            .build(&factory);
+        assert!(result.err().unwrap().is_not_found());
+        let result = FontCollectionBuilder::new()
+            .add_file(missing.join("font.ttf"))
+            .build(&factory);
+        assert!(result.err().unwrap().is_not_found());
+        let directory = std::env::temp_dir();
+        assert!(FontCollectionBuilder::new()
+            .add_file(directory.clone())
+            .collect_files()
+            .is_err());
+        assert!(FontCollectionBuilder::new()
+            .add_directory(missing.join("font.ttf"))
+            .add_file(FontFileReference::from_memory(&b"not a font"[..]))
+            .collect_files()
+            .is_err());
+        // メモリ上のファイルはフォントとして読めなくても無視するだけ．
+        let collection = FontCollectionBuilder::new()
+            .add_file(FontFileReference::from_memory(&b"not a font"[..]))
+            .build(&factory)
+            .unwrap();
+        assert_eq!(collection.get_font_family_count(), 0);
+    }
+}
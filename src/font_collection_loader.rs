@@ -0,0 +1,220 @@
+use std::{
+    any::Any,
+    cell::Cell,
+    collections::HashMap,
+    fs::File,
+    io::Read,
+    os::raw::c_void,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex, OnceLock,
+    },
+};
+
+use windows::{
+    core::{implement, Interface, HRESULT},
+    Win32::{
+        Foundation::BOOL,
+        Graphics::DirectWrite::{
+            IDWriteFactory, IDWriteFontCollection, IDWriteFontCollectionLoader,
+            IDWriteFontCollectionLoader_Impl, IDWriteFontFile, IDWriteFontFileEnumerator,
+            IDWriteFontFileEnumerator_Impl,
+        },
+    },
+};
+
+use crate::{
+    backend::FontCollectionBackend,
+    error::{Error, Result, E_INVALIDARG},
+    factory::{get_factory6, register_font_collection_loader},
+    family_model::FontFamilyModel,
+    font_family::FontFamily,
+    font_file::{create_font_file, FontFileReference},
+    woff, woff2,
+};
+
+// FontFileReference の列から DirectWrite のカスタムフォントコレクションを作るローダー．
+// コレクションキーは登録番号 (u64, リトルエンディアン)．DirectWrite はローダーとキーの組で
+// コレクションをキャッシュすることがあるので，同じ番号は二度使わない．
+
+type Files = Arc<[FontFileReference]>;
+
+fn registry() -> &'static Mutex<HashMap<u64, Files>> {
+    static REGISTRY: OnceLock<Mutex<HashMap<u64, Files>>> = OnceLock::new();
+    REGISTRY.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+fn register_files(files: &[FontFileReference]) -> u64 {
+    static NEXT_ID: AtomicU64 = AtomicU64::new(0);
+    let id = NEXT_ID.fetch_add(1, Ordering::Relaxed);
+    registry().lock().unwrap().insert(id, files.into());
+    id
+}
+
+// DirectWrite は WOFF と WOFF2 を読めないので，sfnt に展開してメモリ上のファイルにする．
+// 読めないファイルが一つでもあると CreateCustomFontCollection 全体が失敗するので，先に取り除く．
+fn prepare_files(files: &[FontFileReference]) -> Vec<FontFileReference> {
+    files
+        .iter()
+        .filter_map(|file| prepare_file(file).ok())
+        .collect()
+}
+
+fn prepare_file(file: &FontFileReference) -> Result<FontFileReference> {
+    let is_woff = |data: &[u8]| woff::is_woff(data) || woff2::is_woff2(data);
+    match file {
+        FontFileReference::Local(path) => {
+            let mut signature = [0; 4];
+            File::open(path)
+                .and_then(|mut reader| reader.read_exact(&mut signature))
+                .map_err(|e| Error::file_access(path, e))?;
+            if is_woff(&signature) {
+                decode_woff(&file.read()?)
+            } else {
+                Ok(file.clone())
+            }
+        }
+        FontFileReference::Memory(data) if is_woff(data) => decode_woff(data),
+        FontFileReference::Memory(_) => Ok(file.clone()),
+        FontFileReference::Other(_) => Err(Error::NotSupported(
+            "cannot create a font file for an unknown loader".to_string(),
+        )),
+    }
+}
+
+fn decode_woff(data: &[u8]) -> Result<FontFileReference> {
+    Ok(FontFileReference::from_memory(
+        woff::decode_font_data(data)?.into_owned(),
+    ))
+}
+
+thread_local! {
+    static LOADER: IDWriteFontCollectionLoader = FontCollectionLoader.into();
+}
+
+pub fn create_font_collection(
+    factory: &IDWriteFactory,
+    files: &[FontFileReference],
+) -> Result<CustomFontCollection> {
+    let loader = LOADER.with(|loader| loader.clone());
+    register_font_collection_loader(factory, &loader)?;
+    let files = prepare_files(files);
+    let id = register_files(&files);
+    let key = id.to_le_bytes();
+    // 列挙はコレクションを作る間に終わるので，その後はキーを消してよい．
+    let collection = unsafe {
+        factory.CreateCustomFontCollection(&loader, key.as_ptr() as *const c_void, key.len() as u32)
+    };
+    registry().lock().unwrap().remove(&id);
+    Ok(CustomFontCollection {
+        collection: collection?,
+        _files: files,
+    })
+}
+
+// フォントセットから作る．ファミリーモデルを選べるが IDWriteFactory6 が必要．
+// portable バックエンドと同じく，フォントとして読めないファイルは無視する．
+pub fn create_font_collection_from_font_set(
+    factory: &IDWriteFactory,
+    files: &[FontFileReference],
+    model: FontFamilyModel,
+) -> Result<CustomFontCollection> {
+    let factory6 = get_factory6(factory)?;
+    let builder = unsafe { factory6.CreateFontSetBuilder2() }?;
+    let files = prepare_files(files);
+    for file in &files {
+        let file = create_font_file(factory, file)?;
+        let _ = unsafe { builder.AddFontFile(&file) };
+    }
+    let font_set = unsafe { builder.CreateFontSet() }?;
+    let collection = unsafe { factory6.CreateFontCollectionFromFontSet2(&font_set, model.into()) }?;
+    Ok(CustomFontCollection {
+        collection: collection.cast()?,
+        _files: files,
+    })
+}
+
+// メモリ上のファイルのデータは弱い参照で登録されるので，WOFF を展開したデータなどはコレクションが持っておく．
+pub struct CustomFontCollection {
+    collection: IDWriteFontCollection,
+    _files: Vec<FontFileReference>,
+}
+
+impl FontCollectionBackend for CustomFontCollection {
+    fn get_font_family_count(&self) -> u32 {
+        self.collection.get_font_family_count()
+    }
+
+    fn get_font_family(&self, index: u32) -> Result<FontFamily> {
+        self.collection.get_font_family(index)
+    }
+
+    fn find_family_name(&self, family_name: &str) -> Result<(u32, bool)> {
+        self.collection.find_family_name(family_name)
+    }
+
+    // FontCollection::as_dwrite で IDWriteFontCollection を取り出せるようにする．
+    fn as_any(&self) -> &dyn Any {
+        &self.collection
+    }
+}
+
+#[implement(IDWriteFontCollectionLoader)]
+pub struct FontCollectionLoader;
+
+impl IDWriteFontCollectionLoader_Impl for FontCollectionLoader {
+    fn CreateEnumeratorFromKey(
+        &self,
+        factory: Option<&IDWriteFactory>,
+        key: *const c_void,
+        size: u32,
+    ) -> windows::core::Result<IDWriteFontFileEnumerator> {
+        let (Some(factory), false) = (factory, key.is_null()) else {
+            return Err(HRESULT(E_INVALIDARG).into());
+        };
+        let key = unsafe { std::slice::from_raw_parts(key as *const u8, size as usize) };
+        let Ok(id) = key.try_into().map(u64::from_le_bytes) else {
+            return Err(HRESULT(E_INVALIDARG).into());
+        };
+        match registry().lock().unwrap().get(&id) {
+            Some(files) => Ok(FontFileEnumerator {
+                factory: factory.clone(),
+                files: files.clone(),
+                position: Cell::new(0),
+            }
+            .into()),
+            None => Err(HRESULT(E_INVALIDARG).into()),
+        }
+    }
+}
+
+#[implement(IDWriteFontFileEnumerator)]
+pub struct FontFileEnumerator {
+    factory: IDWriteFactory,
+    files: Files,
+    // MoveNext を呼んだ回数．現在のファイルは position - 1 番目．
+    position: Cell<usize>,
+}
+
+impl IDWriteFontFileEnumerator_Impl for FontFileEnumerator {
+    fn MoveNext(&self) -> windows::core::Result<BOOL> {
+        if self.position.get() < self.files.len() {
+            self.position.set(self.position.get() + 1);
+            Ok(BOOL::from(true))
+        } else {
+            Ok(BOOL::from(false))
+        }
+    }
+
+    fn GetCurrentFontFile(&self) -> windows::core::Result<IDWriteFontFile> {
+        let file = self
+            .position
+            .get()
+            .checked_sub(1)
+            .and_then(|index| self.files.get(index));
+        match file {
+            Some(file) => Ok(create_font_file(&self.factory, file)?),
+            None => Err(HRESULT(E_INVALIDARG).into()),
+        }
+    }
+}
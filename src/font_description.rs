@@ -0,0 +1,443 @@
+use core::fmt;
+use std::str::FromStr;
+
+use crate::{
+    error::{Error, Result},
+    font::{Stretch, Style, Weight},
+    font_collection::FontCollection,
+    font_matching::FontMatch,
+};
+
+// CSS の font 一括指定で表したフォント．
+//
+// let description: FontDescription = "italic 600 condensed 12pt 'Yu Gothic', sans-serif".parse()?;
+// assert_eq!(description.to_string(), "italic 600 condensed 12pt \"Yu Gothic\", sans-serif");
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct FontDescription {
+    pub style: Style,
+    // oblique の後に書かれた角度 (度)．
+    pub oblique_angle: Option<f32>,
+    pub small_caps: bool,
+    pub weight: Weight,
+    pub stretch: Stretch,
+    pub size: FontSize,
+    // 単位によって意味が変わるので，書かれたままの文字列で持つ．
+    pub line_height: Option<String>,
+    // 総称ファミリー (sans-serif など) もそのまま入る．
+    pub families: Vec<String>,
+}
+
+impl Default for FontDescription {
+    fn default() -> Self {
+        Self {
+            style: Style::Normal,
+            oblique_angle: None,
+            small_caps: false,
+            weight: Weight(400),
+            stretch: Stretch::Normal,
+            size: FontSize::default(),
+            line_height: None,
+            families: vec![],
+        }
+    }
+}
+
+impl FontDescription {
+    // families の先頭から，コレクションにある最初のファミリーで一番近いフォントを探す．
+    pub fn find_font(&self, collection: &FontCollection) -> Result<Option<FontMatch>> {
+        for family in &self.families {
+            if let Some(family) = collection.find_family(family) {
+                return family.get_first_matching_font(self.weight, self.stretch, self.style);
+            }
+        }
+        Ok(None)
+    }
+}
+
+// [style || small-caps || weight || stretch] size [/ line-height] family, ...
+impl FromStr for FontDescription {
+    type Err = Error;
+    fn from_str(s: &str) -> Result<Self> {
+        let error = |message: &str| Error::Parse(format!("{}: {}", message, s));
+        let mut description = Self::default();
+        let (mut style, mut small_caps, mut weight, mut stretch) = (false, false, false, false);
+        let mut count = 0;
+        let mut rest = s.trim_start();
+        // size の前の値は順不同で，それぞれ一度だけ書ける．normal はまだ書かれていない値の既定値を表す．
+        loop {
+            let (word, mut after) = split_word(rest, false);
+            let name = word.to_ascii_lowercase();
+            if name == "normal" {
+                // どの値も既定のまま．
+            } else if !style && (name == "italic" || name == "oblique") {
+                style = true;
+                if name == "italic" {
+                    description.style = Style::Italic;
+                } else {
+                    description.style = Style::Oblique;
+                    let (angle, after_angle) = split_word(after, false);
+                    if let Some(angle) = parse_angle(angle) {
+                        description.oblique_angle = Some(angle);
+                        after = after_angle;
+                    }
+                }
+            } else if !small_caps && name == "small-caps" {
+                small_caps = true;
+                description.small_caps = true;
+            } else if let (false, Some(value)) = (weight, parse_weight(&name)) {
+                weight = true;
+                description.weight = value;
+            } else if let (false, Some(value)) = (stretch, parse_stretch(&name)) {
+                stretch = true;
+                description.stretch = value;
+            } else {
+                break;
+            }
+            count += 1;
+            if count > 4 {
+                return Err(error("too many font properties"));
+            }
+            rest = after;
+        }
+        let (size, after) = split_word(rest, true);
+        if size.is_empty() {
+            return Err(error("missing font size"));
+        }
+        description.size = size.parse()?;
+        rest = after.trim_start();
+        if let Some(after) = rest.strip_prefix('/') {
+            let (line_height, after) = split_word(after.trim_start(), false);
+            if line_height.is_empty() {
+                return Err(error("missing line height"));
+            }
+            description.line_height = Some(line_height.to_string());
+            rest = after;
+        }
+        description.families = parse_families(rest).ok_or_else(|| error("invalid font family"))?;
+        if description.families.is_empty() {
+            return Err(error("missing font family"));
+        }
+        Ok(description)
+    }
+}
+
+impl fmt::Display for FontDescription {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match (self.style, self.oblique_angle) {
+            (Style::Oblique, Some(angle)) => write!(f, "oblique {}deg ", angle)?,
+            (Style::Italic | Style::Oblique, _) => write!(f, "{} ", self.style.to_css())?,
+            _ => {}
+        }
+        if self.small_caps {
+            write!(f, "small-caps ")?;
+        }
+        if self.weight != Weight(400) {
+            write!(f, "{} ", self.weight.to_css())?;
+        }
+        if self.stretch.to_css() != "normal" {
+            write!(f, "{} ", self.stretch.to_css())?;
+        }
+        write!(f, "{}", self.size)?;
+        if let Some(line_height) = &self.line_height {
+            write!(f, "/{}", line_height)?;
+        }
+        for (index, family) in self.families.iter().enumerate() {
+            let separator = if index == 0 { " " } else { ", " };
+            write!(f, "{}{}", separator, quote_family(family))?;
+        }
+        Ok(())
+    }
+}
+
+// 一括指定で書ける font-weight は normal，bold と 1 以上 1000 以下の数値だけ．
+// Weight::from_str と違い，medium や light のような DirectWrite の名前は受け付けない．
+fn parse_weight(name: &str) -> Option<Weight> {
+    if name == "bold" {
+        return Some(Weight(700));
+    }
+    let value = name.parse::<f32>().ok()?;
+    (1.0..=1000.0)
+        .contains(&value)
+        .then(|| Weight(value.round() as i32))
+}
+
+// 一括指定で書ける font-stretch は CSS のキーワードだけ．百分率や SemiCondensed のような名前は受け付けない．
+fn parse_stretch(name: &str) -> Option<Stretch> {
+    (1..=9)
+        .map(Stretch::from_i32)
+        .find(|stretch| *stretch != Stretch::Normal && stretch.to_css() == name)
+}
+
+// 先頭の空白で区切られた語と，その後ろを返す．size なら / でも区切る．
+fn split_word(s: &str, size: bool) -> (&str, &str) {
+    let end = s
+        .find(|c: char| c.is_whitespace() || (size && c == '/'))
+        .unwrap_or(s.len());
+    (&s[..end], s[end..].trim_start())
+}
+
+// 引用符で囲まれた名前か，空白で区切られた識別子の並び．
+fn parse_families(s: &str) -> Option<Vec<String>> {
+    let mut families = vec![];
+    let mut chars = s.trim().chars().peekable();
+    while chars.peek().is_some() {
+        while chars.next_if(|c| c.is_whitespace()).is_some() {}
+        let family = match chars.peek() {
+            Some(&quote) if quote == '"' || quote == '\'' => {
+                chars.next();
+                let mut family = String::new();
+                loop {
+                    match chars.next()? {
+                        '\\' => family.push(chars.next()?),
+                        c if c == quote => break,
+                        c => family.push(c),
+                    }
+                }
+                while chars.next_if(|c| c.is_whitespace()).is_some() {}
+                family
+            }
+            _ => {
+                let mut family = String::new();
+                while let Some(c) = chars.next_if(|c| *c != ',') {
+                    family.push(c);
+                }
+                // 引用符で囲まない名前は識別子の並びに限る．
+                let words = family.split_whitespace().collect::<Vec<_>>();
+                if !words.iter().all(|word| is_identifier(word)) {
+                    return None;
+                }
+                words.join(" ")
+            }
+        };
+        if family.is_empty() {
+            return None;
+        }
+        families.push(family);
+        match chars.next() {
+            Some(',') if chars.peek().is_some() => {}
+            None => {}
+            _ => return None,
+        }
+    }
+    Some(families)
+}
+
+// ブラウザと同じく，一つの識別子として書けない名前は二重引用符で囲む．
+fn quote_family(family: &str) -> String {
+    if is_identifier(family) {
+        family.to_string()
+    } else {
+        format!("\"{}\"", family.replace('\\', "\\\\").replace('"', "\\\""))
+    }
+}
+
+fn is_identifier(word: &str) -> bool {
+    let mut chars = word.strip_prefix('-').unwrap_or(word).chars();
+    chars
+        .next()
+        .is_some_and(|c| c.is_alphabetic() || c == '_' || !c.is_ascii())
+        && chars.all(|c| c.is_alphanumeric() || c == '_' || c == '-' || !c.is_ascii())
+}
+
+// deg，grad，rad，turn の角度を度にする．CSS と同じく -90deg 以上 90deg 以下に限る．
+pub fn parse_angle(s: &str) -> Option<f32> {
+    let s = s.to_ascii_lowercase();
+    let (value, scale) = if let Some(value) = s.strip_suffix("deg") {
+        (value, 1.0)
+    } else if let Some(value) = s.strip_suffix("grad") {
+        (value, 0.9)
+    } else if let Some(value) = s.strip_suffix("rad") {
+        (value, 180.0 / std::f32::consts::PI)
+    } else if let Some(value) = s.strip_suffix("turn") {
+        (value, 360.0)
+    } else {
+        return None;
+    };
+    let angle = value.parse::<f32>().ok()? * scale;
+    (-90.0..=90.0).contains(&angle).then_some(angle)
+}
+
+// font-size の値．キーワード (medium など) は CSS の既定の大きさの px にする．
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct FontSize {
+    pub value: f32,
+    // 小文字の単位 (px，pt，em，% など)．0 のように単位がなければ空．
+    pub unit: String,
+}
+
+const FONT_SIZE_KEYWORDS: [(&str, f32); 8] = [
+    ("xx-small", 9.0),
+    ("x-small", 10.0),
+    ("small", 13.0),
+    ("medium", 16.0),
+    ("large", 18.0),
+    ("x-large", 24.0),
+    ("xx-large", 32.0),
+    ("xxx-large", 48.0),
+];
+
+// 絶対的な単位の，1 単位あたりの DIP (1/96 インチ) の数．
+const ABSOLUTE_UNITS: [(&str, f32); 7] = [
+    ("px", 1.0),
+    ("pt", 96.0 / 72.0),
+    ("pc", 16.0),
+    ("in", 96.0),
+    ("cm", 96.0 / 2.54),
+    ("mm", 96.0 / 25.4),
+    ("q", 96.0 / 101.6),
+];
+
+impl FontSize {
+    // DirectWrite で使う DIP にする．em や % のような相対的な単位では None．
+    pub fn to_dips(&self) -> Option<f32> {
+        if self.unit.is_empty() && self.value == 0.0 {
+            return Some(0.0);
+        }
+        ABSOLUTE_UNITS
+            .iter()
+            .find(|(unit, _)| *unit == self.unit)
+            .map(|(_, scale)| self.value * scale)
+    }
+}
+
+impl Default for FontSize {
+    fn default() -> Self {
+        Self {
+            value: 16.0,
+            unit: "px".to_string(),
+        }
+    }
+}
+
+impl FromStr for FontSize {
+    type Err = Error;
+    fn from_str(s: &str) -> Result<Self> {
+        let s = s.trim().to_ascii_lowercase();
+        if let Some((_, value)) = FONT_SIZE_KEYWORDS.iter().find(|(name, _)| *name == s) {
+            return Ok(Self {
+                value: *value,
+                unit: "px".to_string(),
+            });
+        }
+        let end = s
+            .find(|c: char| !(c.is_ascii_digit() || c == '.' || c == '+' || c == '-'))
+            .unwrap_or(s.len());
+        let (value, unit) = s.split_at(end);
+        match value.parse::<f32>() {
+            Ok(value)
+                if value.is_finite()
+                    && value >= 0.0
+                    && (unit.chars().all(|c| c.is_ascii_alphabetic()) || unit == "%")
+                    && (!unit.is_empty() || value == 0.0) =>
+            {
+                Ok(Self {
+                    value,
+                    unit: unit.to_string(),
+                })
+            }
+            _ => Err(Error::Parse(format!("invalid font size: {}", s))),
+        }
+    }
+}
+
+impl fmt::Display for FontSize {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}{}", self.value, self.unit)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(s: &str) -> FontDescription {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn shorthand() {
+        let description =
+            parse("oblique 10deg small-caps 600 condensed 12pt/1.5 'Yu Gothic', sans-serif");
+        assert_eq!(description.style, Style::Oblique);
+        assert_eq!(description.oblique_angle, Some(10.0));
+        assert!(description.small_caps);
+        assert_eq!(description.weight, Weight(600));
+        assert_eq!(description.stretch, Stretch::Condensed);
+        assert_eq!(description.size.to_dips(), Some(16.0));
+        assert_eq!(description.line_height.as_deref(), Some("1.5"));
+        assert_eq!(description.families, vec!["Yu Gothic", "sans-serif"]);
+
+        // 順不同で，normal はまだ書かれていない値の既定値．
+        let description = parse("Bold normal ITALIC 16px/normal Arial Black,serif");
+        assert_eq!(description.style, Style::Italic);
+        assert_eq!(description.weight, Weight(700));
+        assert_eq!(description.line_height.as_deref(), Some("normal"));
+        assert_eq!(description.families, vec!["Arial Black", "serif"]);
+
+        let description = parse("SEMI-EXPANDED 1000 0 serif");
+        assert_eq!(description.stretch, Stretch::SemiExpanded);
+        assert_eq!(description.weight, Weight(1000));
+        assert_eq!(description.size.to_dips(), Some(0.0));
+    }
+
+    // medium や large は大きさのキーワード．
+    #[test]
+    fn size_keywords() {
+        let description = parse("medium serif");
+        assert_eq!(description.weight, Weight(400));
+        assert_eq!(description.size.to_dips(), Some(16.0));
+        assert_eq!(description.families, vec!["serif"]);
+
+        let description = parse("bold x-large/2 \"Segoe UI\"");
+        assert_eq!(description.weight, Weight(700));
+        assert_eq!(description.size.to_dips(), Some(24.0));
+        assert_eq!(description.line_height.as_deref(), Some("2"));
+    }
+
+    // 一括指定では CSS の値だけを受け付ける．
+    #[test]
+    fn invalid_shorthand() {
+        for s in [
+            "",
+            "serif",
+            "12px",
+            "12px serif,",
+            "12px 'serif",
+            "12px/ serif",
+            "light 12px serif",
+            "semibold 12px serif",
+            "1001 12px serif",
+            "bolder 12px serif",
+            "semicondensed 12px serif",
+            "87.5% 12px serif",
+            "bold bold 12px serif",
+            "italic oblique 12px serif",
+            "normal normal normal normal normal 12px serif",
+            "12 serif",
+            "-12px serif",
+        ] {
+            assert!(s.parse::<FontDescription>().is_err(), "{}", s);
+        }
+    }
+
+    #[test]
+    fn format() {
+        for (s, expected) in [
+            (
+                "italic 600 condensed 12pt 'Yu Gothic', sans-serif",
+                "italic 600 condensed 12pt \"Yu Gothic\", sans-serif",
+            ),
+            ("normal 16px/1.2 serif", "16px/1.2 serif"),
+            (
+                "oblique -5deg bold 10px 'a\"b\\\\c'",
+                "oblique -5deg bold 10px \"a\\\"b\\\\c\"",
+            ),
+        ] {
+            let description = parse(s);
+            assert_eq!(description.to_string(), expected);
+            assert_eq!(parse(expected), description);
+        }
+    }
+}
@@ -0,0 +1,166 @@
+use std::{collections::BTreeMap, path::PathBuf};
+
+use crate::{
+    error::{Error, Result},
+    font::{Font, InformationalStringId, Simulations, Stretch, Style, Weight},
+    json::{self, Json},
+    localized_strings::{LocalizedString, LocalizedStrings},
+    name::NameTable,
+    sfnt::tag_to_string,
+};
+
+// Font から分かることをまとめた，所有権を持つ構造体．どのバックエンドのフォントからも作れる．
+// バックエンドが対応していない情報 (メモリ上のフォントのパスなど) は None や空になる．
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct FontDescriptor {
+    pub family_names: Vec<LocalizedString>,
+    pub face_names: Vec<LocalizedString>,
+    // フォントが持つ情報文字列．キーは InformationalStringId::ALL のいずれか．
+    pub informational_strings: BTreeMap<InformationalStringId, Vec<LocalizedString>>,
+    pub weight: Weight,
+    pub style: Style,
+    pub stretch: Stretch,
+    pub simulations: Simulations,
+    pub file_path: Option<PathBuf>,
+    pub face_index: Option<u32>,
+    pub is_variable_font: bool,
+    pub axes: Vec<AxisDescriptor>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct AxisDescriptor {
+    pub tag: String,
+    pub names: Vec<LocalizedString>,
+    pub min_value: f32,
+    pub default_value: f32,
+    pub max_value: f32,
+    pub is_hidden: bool,
+}
+
+impl FontDescriptor {
+    pub fn from_font(font: &Font) -> Result<Self> {
+        let family_names = collect(font.get_font_family()?.get_family_names()?)?;
+        let face_names = collect(font.get_face_names()?)?;
+        let mut informational_strings = BTreeMap::new();
+        for id in InformationalStringId::ALL {
+            if let Some(strings) = font.get_informational_strings(id)? {
+                let strings = collect(strings)?;
+                if !strings.is_empty() {
+                    informational_strings.insert(id, strings);
+                }
+            }
+        }
+        let axes = match optional(font.get_variation_axes())? {
+            Some(axes) if !axes.is_empty() => {
+                let names = optional(NameTable::from_font(font))?.flatten();
+                axes.iter()
+                    .map(|axis| AxisDescriptor {
+                        tag: tag_to_string(axis.tag),
+                        names: names
+                            .as_ref()
+                            .map(|names| axis.get_names(names))
+                            .unwrap_or_default(),
+                        min_value: axis.min_value,
+                        default_value: axis.default_value,
+                        max_value: axis.max_value,
+                        is_hidden: axis.is_hidden(),
+                    })
+                    .collect()
+            }
+            _ => vec![],
+        };
+        Ok(Self {
+            family_names,
+            face_names,
+            informational_strings,
+            weight: font.get_weight(),
+            style: font.get_style(),
+            stretch: font.get_stretch(),
+            simulations: font.get_simulations(),
+            file_path: optional(font.get_filepath())?.map(PathBuf::from),
+            face_index: optional(font.get_face_index())?,
+            is_variable_font: optional(font.is_variable_font())?.unwrap_or(false),
+            axes,
+        })
+    }
+
+    // 別名の ID (PreferredFamilyNames など) も使える．ない場合は空．
+    pub fn get_strings(&self, id: InformationalStringId) -> &[LocalizedString] {
+        self.informational_strings
+            .get(&id.canonical())
+            .map(|strings| strings.as_slice())
+            .unwrap_or_default()
+    }
+
+    // ロケールは大文字と小文字を区別しない．
+    pub fn get_string(&self, id: InformationalStringId, locale: &str) -> Option<&str> {
+        self.get_strings(id)
+            .iter()
+            .find(|string| string.locale.eq_ignore_ascii_case(locale))
+            .map(|string| string.string.as_str())
+    }
+
+    // キーの順番とインデントが固定された JSON．属性は DirectWrite の列挙値と同じ数値で書く．
+    pub fn to_json(&self) -> String {
+        self.to_json_value().to_string_pretty(2)
+    }
+
+    pub fn to_json_value(&self) -> Json {
+        let informational_strings = Json::object(
+            self.informational_strings
+                .iter()
+                .map(|(id, strings)| (id.name(), json::names(strings))),
+        );
+        let axes = Json::array(self.axes.iter().map(|axis| {
+            Json::object([
+                ("tag", axis.tag.as_str().into()),
+                ("names", json::names(&axis.names)),
+                ("min_value", axis.min_value.into()),
+                ("default_value", axis.default_value.into()),
+                ("max_value", axis.max_value.into()),
+                ("is_hidden", axis.is_hidden.into()),
+            ])
+        }));
+        Json::object([
+            ("family_names", json::names(&self.family_names)),
+            ("face_names", json::names(&self.face_names)),
+            ("informational_strings", informational_strings),
+            ("weight", self.weight.0.into()),
+            ("style", self.style.to_i32().into()),
+            ("stretch", self.stretch.to_i32().into()),
+            ("simulations", self.simulations.bits().into()),
+            (
+                "file_path",
+                self.file_path
+                    .as_ref()
+                    .map(|path| path.to_string_lossy().into_owned())
+                    .into(),
+            ),
+            ("face_index", self.face_index.into()),
+            ("is_variable_font", self.is_variable_font.into()),
+            ("axes", axes),
+        ])
+    }
+}
+
+impl TryFrom<&Font> for FontDescriptor {
+    type Error = Error;
+    fn try_from(value: &Font) -> Result<Self> {
+        Self::from_font(value)
+    }
+}
+
+fn collect(strings: LocalizedStrings) -> Result<Vec<LocalizedString>> {
+    strings.into_iter().collect()
+}
+
+// バックエンドが対応していない情報は None にする．
+fn optional<T>(result: Result<T>) -> Result<Option<T>> {
+    match result {
+        Ok(value) => Ok(Some(value)),
+        Err(Error::NotSupported(_)) => Ok(None),
+        Err(error) => Err(error),
+    }
+}
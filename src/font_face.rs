@@ -3,55 +3,69 @@ use windows::Win32::{
     Graphics::DirectWrite::{IDWriteFontFace, IDWriteFontFile},
 };
 
-use crate::font_file;
+use crate::{
+    error::{Error, Result},
+    font_file::{self, FontFileReference},
+};
 
-pub fn get_number_of_files(face: &IDWriteFontFace) -> anyhow::Result<u32> {
+pub fn get_number_of_files(face: &IDWriteFontFace) -> Result<u32> {
     let mut number_of_files = 0;
     unsafe { face.GetFiles(&mut number_of_files, None) }?;
-    return Ok(number_of_files);
+    Ok(number_of_files)
 }
 
-pub fn get_files(face: &IDWriteFontFace) -> anyhow::Result<IDWriteFontFile> {
+// 複数のファイルから成るフェイスは最初のファイルを返す．
+pub fn get_files(face: &IDWriteFontFace) -> Result<IDWriteFontFile> {
     let mut number_of_files = get_number_of_files(face)?;
-    assert!(number_of_files == 1);
     let mut files: Vec<Option<IDWriteFontFile>> = vec![None; number_of_files as usize];
-    unsafe { face.GetFiles(&mut number_of_files, Some(files.as_mut_ptr())) }?;
-    let file = files[0].clone().unwrap();
-    Ok(file)
+    if !files.is_empty() {
+        unsafe { face.GetFiles(&mut number_of_files, Some(files.as_mut_ptr())) }?;
+    }
+    files
+        .into_iter()
+        .flatten()
+        .next()
+        .ok_or_else(|| Error::InvalidFont("font face has no files".to_string()))
 }
 
-pub fn get_filepath(face: &IDWriteFontFace) -> anyhow::Result<String> {
+pub fn get_index(face: &IDWriteFontFace) -> u32 {
+    unsafe { face.GetIndex() }
+}
+
+pub fn get_filepath(face: &IDWriteFontFace) -> Result<String> {
     let file = get_files(face)?;
     font_file::get_filepath(&file)
 }
 
-pub fn map_font_table<F: FnOnce(&[u8])>(
-    face: &IDWriteFontFace,
-    tag: u32,
-    f: F,
-) -> anyhow::Result<()> {
+pub fn get_file_reference(face: &IDWriteFontFace) -> Result<FontFileReference> {
+    let file = get_files(face)?;
+    font_file::get_reference(&file)
+}
+
+pub fn map_font_table<F: FnOnce(&[u8])>(face: &IDWriteFontFace, tag: u32, f: F) -> Result<()> {
     let mut exists = BOOL::from(false);
     let mut data = std::ptr::null_mut();
     let mut size = 0_u32;
     let mut context = std::ptr::null_mut();
     unsafe { face.TryGetFontTable(tag, &mut data, &mut size, &mut context, &mut exists) }?;
-    if exists.as_bool() {
-        if data.is_null() {
-            assert!(false);
-            return Err(anyhow::anyhow!("data exists but is null"));
-        }
-        if size == 0 {
-            assert!(false);
-            return Err(anyhow::anyhow!("data exists but size is zero"));
-        }
-        let data = unsafe { std::slice::from_raw_parts(data as *const u8, size as usize) };
-        f(data);
-        unsafe { face.ReleaseFontTable(context) };
+    if !exists.as_bool() {
+        return Ok(());
     }
-    Ok(())
+    // 長さ 0 のテーブルは空のテーブルとして扱う．どの場合も context は解放する．
+    let result = if size == 0 {
+        f(&[]);
+        Ok(())
+    } else if data.is_null() {
+        Err(Error::InvalidFont("data exists but is null".to_string()))
+    } else {
+        f(unsafe { std::slice::from_raw_parts(data as *const u8, size as usize) });
+        Ok(())
+    };
+    unsafe { face.ReleaseFontTable(context) };
+    result
 }
 
-pub fn get_font_table(face: &IDWriteFontFace, tag: u32) -> anyhow::Result<Vec<u8>> {
+pub fn get_font_table(face: &IDWriteFontFace, tag: u32) -> Result<Vec<u8>> {
     let mut table = Vec::new();
     map_font_table(face, tag, |data| {
         table = data.to_vec();
@@ -59,10 +73,10 @@ pub fn get_font_table(face: &IDWriteFontFace, tag: u32) -> anyhow::Result<Vec<u8
     Ok(table)
 }
 
-pub fn is_variable_font(face: &IDWriteFontFace) -> anyhow::Result<bool> {
+pub fn is_variable_font(face: &IDWriteFontFace) -> Result<bool> {
     let mut is_variable_font = false;
     map_font_table(face, u32::from_le_bytes(*b"fvar"), |data| {
-        if data.len() > 0 {
+        if !data.is_empty() {
             is_variable_font = true;
         }
     })?;
@@ -1,33 +1,98 @@
+#[cfg(windows)]
+use std::any::Any;
+
+#[cfg(windows)]
 use windows::Win32::Graphics::DirectWrite::{
     IDWriteFont, IDWriteFontFamily, IDWriteLocalizedStrings,
 };
 
-use crate::{font::Font, localized_strings::LocalizedStrings};
+use crate::{
+    backend::FontFamilyBackend,
+    error::Result,
+    font::{Font, Stretch, Style, Weight},
+    font_matching::{self, FontMatch},
+    localized_strings::LocalizedStrings,
+};
 
-pub fn get_family_names(family: &IDWriteFontFamily) -> anyhow::Result<IDWriteLocalizedStrings> {
+#[cfg(windows)]
+pub fn get_family_names(family: &IDWriteFontFamily) -> Result<IDWriteLocalizedStrings> {
     unsafe { Ok(family.GetFamilyNames()?) }
 }
 
+#[cfg(windows)]
 pub fn get_font_count(family: &IDWriteFontFamily) -> u32 {
     unsafe { family.GetFontCount() }
 }
 
-pub fn get_font(family: &IDWriteFontFamily, index: u32) -> anyhow::Result<IDWriteFont> {
+#[cfg(windows)]
+pub fn get_font(family: &IDWriteFontFamily, index: u32) -> Result<IDWriteFont> {
     unsafe { Ok(family.GetFont(index)?) }
 }
 
-pub struct FontFamily(pub IDWriteFontFamily);
+#[cfg(windows)]
+impl FontFamilyBackend for IDWriteFontFamily {
+    fn get_family_names(&self) -> Result<LocalizedStrings> {
+        get_family_names(self).map(LocalizedStrings::new)
+    }
+
+    fn get_font_count(&self) -> u32 {
+        get_font_count(self)
+    }
+
+    fn get_font(&self, index: u32) -> Result<Font> {
+        get_font(self, index).map(Font::new)
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+pub struct FontFamily(Box<dyn FontFamilyBackend>);
 impl FontFamily {
-    pub fn get_family_names(&self) -> anyhow::Result<LocalizedStrings> {
-        get_family_names(&self.0).map(LocalizedStrings)
+    pub fn new<T: FontFamilyBackend + 'static>(backend: T) -> Self {
+        Self(Box::new(backend))
+    }
+
+    pub fn backend(&self) -> &dyn FontFamilyBackend {
+        self.0.as_ref()
+    }
+
+    #[cfg(windows)]
+    pub fn as_dwrite(&self) -> Option<&IDWriteFontFamily> {
+        self.0.as_any().downcast_ref()
+    }
+
+    pub fn get_family_names(&self) -> Result<LocalizedStrings> {
+        self.0.get_family_names()
     }
 
     pub fn get_font_count(&self) -> u32 {
-        get_font_count(&self.0)
+        self.0.get_font_count()
     }
 
-    pub fn get_font(&self, index: u32) -> anyhow::Result<Font> {
-        get_font(&self.0, index).map(Font)
+    pub fn get_font(&self, index: u32) -> Result<Font> {
+        self.0.get_font(index)
+    }
+
+    // DirectWrite の GetFirstMatchingFont と同じ規則で最も近いフォントを返す．
+    pub fn get_first_matching_font(
+        &self,
+        weight: Weight,
+        stretch: Stretch,
+        style: Style,
+    ) -> Result<Option<FontMatch>> {
+        font_matching::get_first_matching_font(self, weight, stretch, style)
+    }
+
+    // 近い順に並べたすべてのフォント．
+    pub fn get_matching_fonts(
+        &self,
+        weight: Weight,
+        stretch: Stretch,
+        style: Style,
+    ) -> Result<Vec<FontMatch>> {
+        font_matching::get_matching_fonts(self, weight, stretch, style)
     }
 }
 
@@ -37,34 +102,32 @@ pub struct FontFamilyIter<'a> {
 }
 
 impl<'a> Iterator for FontFamilyIter<'a> {
-    type Item = Font;
+    type Item = Result<Font>;
     fn next(&mut self) -> Option<Self::Item> {
-        if self.index < self.len() {
-            let font = self.family.get_font(self.index as u32);
+        if self.index < self.family.get_font_count() as usize {
+            let item = self.family.get_font(self.index as u32);
             self.index += 1;
-            match font {
-                Ok(font) => Some(font),
-                Err(e) => {
-                    eprintln!("{}", e);
-                    assert!(false);
-                    None
-                }
-            }
+            Some(item)
         } else {
             None
         }
     }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = self.len();
+        (len, Some(len))
+    }
 }
 
 impl<'a> ExactSizeIterator for FontFamilyIter<'a> {
     #[inline]
     fn len(&self) -> usize {
-        self.family.get_font_count() as usize
+        (self.family.get_font_count() as usize).saturating_sub(self.index)
     }
 }
 
 impl<'a> IntoIterator for &'a FontFamily {
-    type Item = Font;
+    type Item = Result<Font>;
     type IntoIter = FontFamilyIter<'a>;
     fn into_iter(self) -> Self::IntoIter {
         Self::IntoIter {
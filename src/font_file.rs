@@ -1,27 +1,173 @@
-use std::os::raw::c_void;
+use std::{
+    borrow::Cow,
+    fmt,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
 
+#[cfg(windows)]
 use windows::{
-    core::Interface,
-    Win32::Graphics::DirectWrite::{IDWriteFontFile, IDWriteFontFileLoader},
+    core::{Interface, HSTRING},
+    Win32::Graphics::DirectWrite::{
+        IDWriteFactory, IDWriteFontFile, IDWriteFontFileLoader, IDWriteLocalFontFileLoader,
+    },
 };
 
-use crate::local_font_file_loader::get_file_path_from_key;
+use crate::error::{Error, Result};
+#[cfg(windows)]
+use crate::{local_font_file_loader::get_file_path_from_key, memory_font_file_loader};
+
+// フォントファイルの参照．DirectWrite の参照キーをローダーごとに解釈したもの．
+#[derive(Clone, PartialEq, Eq, Hash)]
+pub enum FontFileReference {
+    Local(PathBuf),
+    Memory(Arc<[u8]>),
+    // このクレートが知らないローダーの参照キー．
+    Other(Vec<u8>),
+}
+
+impl FontFileReference {
+    pub fn from_memory<T: Into<Arc<[u8]>>>(data: T) -> Self {
+        Self::Memory(data.into())
+    }
+
+    pub fn get_filepath(&self) -> Option<&Path> {
+        match self {
+            Self::Local(path) => Some(path),
+            _ => None,
+        }
+    }
+
+    // ファイル全体を返す．他のローダーのファイルは読めない．
+    pub fn read(&self) -> Result<Cow<'_, [u8]>> {
+        match self {
+            Self::Local(path) => std::fs::read(path)
+                .map(Cow::Owned)
+                .map_err(|e| Error::file_access(path, e)),
+            Self::Memory(data) => Ok(Cow::Borrowed(data)),
+            Self::Other(_) => Err(Error::NotSupported(
+                "the font file uses an unknown loader".to_string(),
+            )),
+        }
+    }
+}
+
+// メモリ上のデータは長さだけを表示する．
+impl fmt::Debug for FontFileReference {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Local(path) => f.debug_tuple("Local").field(path).finish(),
+            Self::Memory(data) => write!(f, "Memory({} bytes)", data.len()),
+            Self::Other(key) => write!(f, "Other({} bytes)", key.len()),
+        }
+    }
+}
+
+impl From<PathBuf> for FontFileReference {
+    fn from(value: PathBuf) -> Self {
+        Self::Local(value)
+    }
+}
+
+impl From<&Path> for FontFileReference {
+    fn from(value: &Path) -> Self {
+        Self::Local(value.to_path_buf())
+    }
+}
+
+impl From<Arc<[u8]>> for FontFileReference {
+    fn from(value: Arc<[u8]>) -> Self {
+        Self::Memory(value)
+    }
+}
 
-pub fn get_reference_key(file: &IDWriteFontFile) -> anyhow::Result<(*const c_void, u32)> {
-    // GetReferenceKey には初期化されていないポインタを渡すことができる．
-    let mut reference_key = std::mem::MaybeUninit::uninit();
+#[cfg(windows)]
+pub fn get_reference_key(file: &IDWriteFontFile) -> Result<Vec<u8>> {
+    let mut key = std::ptr::null_mut();
     let mut size = 0_u32;
-    unsafe { file.GetReferenceKey(reference_key.as_mut_ptr(), &mut size) }?;
-    Ok((unsafe { *reference_key.as_ptr() }, size))
+    unsafe { file.GetReferenceKey(&mut key, &mut size) }?;
+    if key.is_null() || size == 0 {
+        return Ok(vec![]);
+    }
+    // キーはファイルオブジェクトが持っているのでコピーして返す．
+    Ok(unsafe { std::slice::from_raw_parts(key as *const u8, size as usize) }.to_vec())
 }
 
-pub fn get_loader(file: &IDWriteFontFile) -> anyhow::Result<IDWriteFontFileLoader> {
+#[cfg(windows)]
+pub fn get_loader(file: &IDWriteFontFile) -> Result<IDWriteFontFileLoader> {
     let loader = unsafe { file.GetLoader() }?;
     Ok(loader)
 }
 
-pub fn get_filepath(file: &IDWriteFontFile) -> anyhow::Result<String> {
-    let (key, size) = get_reference_key(file)?;
+#[cfg(windows)]
+pub fn get_reference(file: &IDWriteFontFile) -> Result<FontFileReference> {
+    let key = get_reference_key(file)?;
     let loader = get_loader(file)?;
-    get_file_path_from_key(&loader.cast()?, key, size)
+    if let Ok(loader) = loader.cast::<IDWriteLocalFontFileLoader>() {
+        let path = get_file_path_from_key(&loader, &key)?;
+        return Ok(FontFileReference::Local(PathBuf::from(path)));
+    }
+    if loader != memory_font_file_loader::get_loader() {
+        return Ok(FontFileReference::Other(key));
+    }
+    match memory_font_file_loader::get_data(&key) {
+        Some(data) => Ok(FontFileReference::Memory(data)),
+        None => Err(Error::NotSupported(
+            "the data of the memory font file has been released".to_string(),
+        )),
+    }
+}
+
+#[cfg(windows)]
+pub fn get_filepath(file: &IDWriteFontFile) -> Result<String> {
+    match get_reference(file)? {
+        FontFileReference::Local(path) => Ok(path.to_string_lossy().into_owned()),
+        _ => Err(Error::NotSupported(
+            "the font file is not a local file".to_string(),
+        )),
+    }
+}
+
+#[cfg(windows)]
+pub fn create_font_file(
+    factory: &IDWriteFactory,
+    reference: &FontFileReference,
+) -> Result<IDWriteFontFile> {
+    match reference {
+        FontFileReference::Local(path) => {
+            let path = HSTRING::from(path.as_os_str());
+            Ok(unsafe { factory.CreateFontFileReference(&path, None) }?)
+        }
+        FontFileReference::Memory(data) => memory_font_file_loader::create_font_file(factory, data),
+        FontFileReference::Other(_) => Err(Error::NotSupported(
+            "cannot create a font file for an unknown loader".to_string(),
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // メモリ上のファイルはコピーせずに読み，他のローダーのファイルは読めない．
+    #[test]
+    fn read_references() {
+        let data: Arc<[u8]> = Arc::from(&b"font data"[..]);
+        let reference = FontFileReference::from(data.clone());
+        assert_eq!(reference, FontFileReference::from_memory(&b"font data"[..]));
+        assert!(
+            matches!(reference.read().unwrap(), Cow::Borrowed(read) if read.as_ptr() == data.as_ptr())
+        );
+        assert_eq!(reference.get_filepath(), None);
+        assert_eq!(format!("{:?}", reference), "Memory(9 bytes)");
+
+        let path = std::env::temp_dir().join("dwrite_rs_missing.ttf");
+        let reference = FontFileReference::from(path.as_path());
+        assert_eq!(reference.get_filepath(), Some(path.as_path()));
+        assert!(reference.read().unwrap_err().is_not_found());
+
+        let reference = FontFileReference::Other(vec![1, 2]);
+        assert!(matches!(reference.read(), Err(Error::NotSupported(_))));
+        assert_eq!(format!("{:?}", reference), "Other(2 bytes)");
+    }
 }
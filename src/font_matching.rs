@@ -0,0 +1,314 @@
+use crate::{
+    error::Result,
+    font::{Font, Simulations, Stretch, Style, Weight},
+    font_family::FontFamily,
+    os2::get_simulations,
+};
+
+// CSS Fonts Level 4 のフォントマッチング．DirectWrite の GetMatchingFonts と同じく，
+// 幅，スタイル，太さの順に絞り込む．どのバックエンドでも同じ結果になるように Rust で実装する．
+
+// 一致したフォントと，要求された太さとスタイルで表示するのに必要なシミュレーション．
+pub struct FontMatch {
+    pub font: Font,
+    pub index: u32,
+    pub simulations: Simulations,
+}
+
+// 要求との近さ．小さいほど良い．(範囲, 距離) の組を幅，スタイル，太さの順に比べる．
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct MatchKey {
+    stretch: (u8, i32),
+    style: u8,
+    weight: (u8, i32),
+}
+
+impl MatchKey {
+    pub fn new(font: (Weight, Stretch, Style), request: (Weight, Stretch, Style)) -> Self {
+        let (font_weight, font_stretch, font_style) = font;
+        let (weight, stretch, style) = request;
+        Self {
+            stretch: stretch_key(stretch_value(font_stretch), stretch_value(stretch)),
+            style: style_key(normalize_style(font_style), normalize_style(style)),
+            weight: weight_key(font_weight.0, weight.0),
+        }
+    }
+}
+
+// 未定義や未知の値は Normal とみなす．
+fn stretch_value(stretch: Stretch) -> i32 {
+    match stretch {
+        Stretch::Undefined | Stretch::Unknown(_) => Stretch::Normal.to_i32(),
+        _ => stretch.to_i32(),
+    }
+}
+
+fn normalize_style(style: Style) -> Style {
+    match style {
+        Style::Unknown(_) => Style::Normal,
+        _ => style,
+    }
+}
+
+// Normal 以下を求められたら狭い方から，それより広い幅なら広い方から探す．
+fn stretch_key(font: i32, request: i32) -> (u8, i32) {
+    let normal = Stretch::Normal.to_i32();
+    if request <= normal {
+        if font <= request {
+            (0, request - font)
+        } else {
+            (1, font - request)
+        }
+    } else if font >= request {
+        (0, font - request)
+    } else {
+        (1, request - font)
+    }
+}
+
+// Italic なら Italic, Oblique, Normal，Oblique なら Oblique, Italic, Normal，
+// Normal なら Normal, Oblique, Italic の順．
+fn style_key(font: Style, request: Style) -> u8 {
+    let order = match request {
+        Style::Italic => [Style::Italic, Style::Oblique, Style::Normal],
+        Style::Oblique => [Style::Oblique, Style::Italic, Style::Normal],
+        _ => [Style::Normal, Style::Oblique, Style::Italic],
+    };
+    order
+        .iter()
+        .position(|style| *style == font)
+        .unwrap_or(order.len()) as u8
+}
+
+// 400 以上 500 以下なら要求から 500 まで，要求より細いもの，500 より太いものの順．
+// 400 未満なら細い方から，500 より大きければ太い方から探す．
+fn weight_key(font: i32, request: i32) -> (u8, i32) {
+    if (400..=500).contains(&request) {
+        if font >= request && font <= 500 {
+            (0, font - request)
+        } else if font < request {
+            (1, request - font)
+        } else {
+            (2, font - request)
+        }
+    } else if request < 400 {
+        if font <= request {
+            (0, request - font)
+        } else {
+            (1, font - request)
+        }
+    } else if font >= request {
+        (0, font - request)
+    } else {
+        (1, request - font)
+    }
+}
+
+// fonts を要求に近い順に並べた添字を返す．同じ近さなら元の順番を保つ．
+pub fn rank_fonts(
+    fonts: &[(Weight, Stretch, Style)],
+    request: (Weight, Stretch, Style),
+) -> Vec<usize> {
+    let mut indices: Vec<usize> = (0..fonts.len()).collect();
+    indices.sort_by_key(|&index| MatchKey::new(fonts[index], request));
+    indices
+}
+
+// シミュレーションで作られたフォントは候補にせず，必要なシミュレーションを FontMatch に入れる．
+// DirectWrite と同じく，ダウンロードされていないリモートフォントは候補にしない．
+pub fn get_matching_fonts(
+    family: &FontFamily,
+    weight: Weight,
+    stretch: Stretch,
+    style: Style,
+) -> Result<Vec<FontMatch>> {
+    let request = (weight, stretch, style);
+    let mut candidates = vec![];
+    for (index, font) in family.into_iter().enumerate() {
+        let font = match font {
+            Ok(font) => font,
+            Err(error) if error.is_remote_font() => continue,
+            Err(error) => return Err(error),
+        };
+        if font.is_simulation() {
+            continue;
+        }
+        let attributes = (font.get_weight(), font.get_stretch(), font.get_style());
+        candidates.push((MatchKey::new(attributes, request), index as u32, font));
+    }
+    candidates.sort_by_key(|(key, _, _)| *key);
+    Ok(candidates
+        .into_iter()
+        .map(|(_, index, font)| {
+            let simulations = get_simulations(font.get_weight(), font.get_style(), weight, style);
+            FontMatch {
+                font,
+                index,
+                simulations,
+            }
+        })
+        .collect())
+}
+
+pub fn get_first_matching_font(
+    family: &FontFamily,
+    weight: Weight,
+    stretch: Stretch,
+    style: Style,
+) -> Result<Option<FontMatch>> {
+    Ok(get_matching_fonts(family, weight, stretch, style)?
+        .into_iter()
+        .next())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        font_collection::FontCollection,
+        mock::{names, MockFont, MockFontCollection, MockFontFamily},
+    };
+
+    // 各要求に対して，候補を近い順に並べたもの．
+    #[test]
+    fn weight_order() {
+        let fonts = [100, 300, 400, 450, 500, 600, 900];
+        for (request, expected) in [
+            (400, [400, 450, 500, 300, 100, 600, 900]),
+            (450, [450, 500, 400, 300, 100, 600, 900]),
+            (500, [500, 450, 400, 300, 100, 600, 900]),
+            (300, [300, 100, 400, 450, 500, 600, 900]),
+            (200, [100, 300, 400, 450, 500, 600, 900]),
+            (550, [600, 900, 500, 450, 400, 300, 100]),
+            (700, [900, 600, 500, 450, 400, 300, 100]),
+            (1000, [900, 600, 500, 450, 400, 300, 100]),
+        ] {
+            let mut ranked = fonts;
+            ranked.sort_by_key(|&font| weight_key(font, request));
+            assert_eq!(ranked, expected, "{}", request);
+        }
+    }
+
+    #[test]
+    fn stretch_order() {
+        let fonts = [1, 3, 4, 5, 6, 7, 9];
+        for (request, expected) in [
+            (5, [5, 4, 3, 1, 6, 7, 9]),
+            (4, [4, 3, 1, 5, 6, 7, 9]),
+            (2, [1, 3, 4, 5, 6, 7, 9]),
+            (6, [6, 7, 9, 5, 4, 3, 1]),
+            (8, [9, 7, 6, 5, 4, 3, 1]),
+        ] {
+            let mut ranked = fonts;
+            ranked.sort_by_key(|&font| stretch_key(font, request));
+            assert_eq!(ranked, expected, "{}", request);
+        }
+    }
+
+    #[test]
+    fn style_order() {
+        let styles = [Style::Normal, Style::Oblique, Style::Italic];
+        for (request, expected) in [
+            (
+                Style::Normal,
+                [Style::Normal, Style::Oblique, Style::Italic],
+            ),
+            (
+                Style::Italic,
+                [Style::Italic, Style::Oblique, Style::Normal],
+            ),
+            (
+                Style::Oblique,
+                [Style::Oblique, Style::Italic, Style::Normal],
+            ),
+        ] {
+            let mut ranked = styles;
+            ranked.sort_by_key(|&font| style_key(font, request));
+            assert_eq!(ranked, expected, "{:?}", request);
+        }
+        // 未知の値は Normal として扱う．
+        let key = |font| MatchKey::new(font, (Weight(400), Stretch::Normal, Style::Normal));
+        assert_eq!(
+            key((Weight(400), Stretch::Unknown(12), Style::Unknown(7))),
+            key((Weight(400), Stretch::Normal, Style::Normal))
+        );
+    }
+
+    // 幅，スタイル，太さの順に比べる．
+    #[test]
+    fn rank() {
+        let fonts = [
+            (Weight(400), Stretch::Normal, Style::Normal),
+            (Weight(700), Stretch::Normal, Style::Italic),
+            (Weight(400), Stretch::Condensed, Style::Italic),
+            (Weight(700), Stretch::Normal, Style::Normal),
+        ];
+        let request = (Weight(700), Stretch::Normal, Style::Italic);
+        assert_eq!(rank_fonts(&fonts, request), vec![1, 3, 0, 2]);
+        let request = (Weight(400), Stretch::SemiCondensed, Style::Normal);
+        assert_eq!(rank_fonts(&fonts, request), vec![2, 0, 3, 1]);
+        let request = (Weight(500), Stretch::Normal, Style::Normal);
+        assert_eq!(rank_fonts(&fonts, request), vec![0, 3, 1, 2]);
+    }
+
+    #[test]
+    fn simulations() {
+        let collection: FontCollection = MockFontCollection {
+            families: vec![MockFontFamily {
+                names: names(&[("en-us", "Test Sans")]),
+                fonts: [
+                    (400, Stretch::Normal, Style::Normal),
+                    (400, Stretch::Condensed, Style::Italic),
+                    (700, Stretch::Normal, Style::Oblique),
+                ]
+                .into_iter()
+                .map(|(weight, stretch, style)| MockFont {
+                    weight: Weight(weight),
+                    stretch,
+                    style,
+                    ..Default::default()
+                })
+                .collect(),
+                ..Default::default()
+            }],
+        }
+        .into();
+        let family = collection.get_font_family(0).unwrap();
+        for (request, index, simulations) in [
+            ((700, Stretch::Normal, Style::Italic), 2, Simulations::None),
+            ((700, Stretch::Normal, Style::Normal), 0, Simulations::Bold),
+            (
+                (600, Stretch::SemiCondensed, Style::Normal),
+                1,
+                Simulations::Bold,
+            ),
+            (
+                (400, Stretch::Expanded, Style::Oblique),
+                2,
+                Simulations::None,
+            ),
+            ((500, Stretch::Normal, Style::Normal), 0, Simulations::None),
+        ] {
+            let (weight, stretch, style) = request;
+            let font_match = family
+                .get_first_matching_font(Weight(weight), stretch, style)
+                .unwrap()
+                .unwrap();
+            assert_eq!(
+                (font_match.index, font_match.simulations),
+                (index, simulations),
+                "{:?}",
+                request
+            );
+        }
+        let matches = family
+            .get_matching_fonts(Weight(700), Stretch::Normal, Style::Italic)
+            .unwrap();
+        let indices: Vec<_> = matches.iter().map(|m| m.index).collect();
+        assert_eq!(indices, vec![2, 0, 1]);
+        assert_eq!(
+            matches[1].simulations,
+            Simulations::Bold | Simulations::Oblique
+        );
+    }
+}
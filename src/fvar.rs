@@ -0,0 +1,434 @@
+use core::fmt;
+
+use crate::{
+    error::Result,
+    font::{Font, Stretch, Style, Weight},
+    localized_strings::LocalizedString,
+    name::NameTable,
+    sfnt::{make_tag, tag_to_string, Reader, Sfnt},
+    woff,
+};
+
+// バリアブルフォントの fvar (軸と名前付きインスタンス) と avar (座標の正規化) テーブル．
+
+pub const FVAR: u32 = make_tag(b"fvar");
+pub const AVAR: u32 = make_tag(b"avar");
+
+// 登録されている軸．
+pub const WGHT: u32 = make_tag(b"wght");
+pub const WDTH: u32 = make_tag(b"wdth");
+pub const ITAL: u32 = make_tag(b"ital");
+pub const SLNT: u32 = make_tag(b"slnt");
+
+// 軸の flags の HIDDEN_AXIS．
+pub const HIDDEN_AXIS: u16 = 0x0001;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct VariationAxis {
+    pub tag: u32,
+    pub min_value: f32,
+    pub default_value: f32,
+    pub max_value: f32,
+    pub flags: u16,
+    pub axis_name_id: u16,
+}
+
+impl VariationAxis {
+    pub fn is_hidden(&self) -> bool {
+        self.flags & HIDDEN_AXIS != 0
+    }
+
+    pub fn get_names(&self, names: &NameTable) -> Vec<LocalizedString> {
+        names.get_names(self.axis_name_id)
+    }
+
+    // avar を適用する前の，-1.0..=1.0 の正規化された座標．
+    pub fn normalize(&self, value: f32) -> f32 {
+        let value = value.clamp(self.min_value, self.max_value);
+        if value < self.default_value {
+            (value - self.default_value) / (self.default_value - self.min_value)
+        } else if value > self.default_value {
+            (value - self.default_value) / (self.max_value - self.default_value)
+        } else {
+            0.0
+        }
+    }
+}
+
+// "wght 100–900" の形式．
+impl fmt::Display for VariationAxis {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} {}–{}",
+            tag_to_string(self.tag),
+            self.min_value,
+            self.max_value
+        )
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct NamedInstance {
+    pub subfamily_name_id: u16,
+    pub flags: u16,
+    // 軸と同じ順番のユーザー座標．
+    pub coordinates: Vec<f32>,
+    pub postscript_name_id: Option<u16>,
+}
+
+impl NamedInstance {
+    pub fn get_subfamily_names(&self, names: &NameTable) -> Vec<LocalizedString> {
+        names.get_names(self.subfamily_name_id)
+    }
+
+    // 0xFFFF は PostScript 名が無いことを表す．
+    pub fn get_postscript_names(&self, names: &NameTable) -> Vec<LocalizedString> {
+        match self.postscript_name_id {
+            Some(0xFFFF) | None => vec![],
+            Some(name_id) => names.get_names(name_id),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Fvar {
+    pub major_version: u16,
+    pub minor_version: u16,
+    pub axes: Vec<VariationAxis>,
+    pub instances: Vec<NamedInstance>,
+}
+
+impl Fvar {
+    pub fn parse(data: &[u8]) -> Result<Self> {
+        let mut reader = Reader::table(FVAR, data);
+        let major_version = reader.read_u16()?;
+        let minor_version = reader.read_u16()?;
+        if major_version != 1 {
+            return Err(reader.error(format!("unknown version {}", major_version)));
+        }
+        let axes_array_offset = reader.read_u16()? as usize;
+        // reserved
+        reader.skip(2)?;
+        let axis_count = reader.read_u16()? as usize;
+        let axis_size = reader.read_u16()? as usize;
+        let instance_count = reader.read_u16()? as usize;
+        let instance_size = reader.read_u16()? as usize;
+        if axis_size < 20 || instance_size < axis_count * 4 + 4 {
+            return Err(reader.error(format!(
+                "invalid record size (axis {}, instance {})",
+                axis_size, instance_size
+            )));
+        }
+
+        let mut axes = Vec::with_capacity(axis_count);
+        for i in 0..axis_count {
+            let mut reader = reader.jump(axes_array_offset + i * axis_size)?;
+            axes.push(VariationAxis {
+                tag: reader.read_tag()?,
+                min_value: reader.read_fixed()?,
+                default_value: reader.read_fixed()?,
+                max_value: reader.read_fixed()?,
+                flags: reader.read_u16()?,
+                axis_name_id: reader.read_u16()?,
+            });
+        }
+
+        let instances_offset = axes_array_offset + axis_count * axis_size;
+        let has_postscript_name_id = instance_size >= axis_count * 4 + 6;
+        let mut instances = Vec::with_capacity(instance_count);
+        for i in 0..instance_count {
+            let mut reader = reader.jump(instances_offset + i * instance_size)?;
+            let subfamily_name_id = reader.read_u16()?;
+            let flags = reader.read_u16()?;
+            let coordinates = (0..axis_count)
+                .map(|_| reader.read_fixed())
+                .collect::<Result<Vec<_>>>()?;
+            let postscript_name_id = if has_postscript_name_id {
+                Some(reader.read_u16()?)
+            } else {
+                None
+            };
+            instances.push(NamedInstance {
+                subfamily_name_id,
+                flags,
+                coordinates,
+                postscript_name_id,
+            });
+        }
+
+        Ok(Self {
+            major_version,
+            minor_version,
+            axes,
+            instances,
+        })
+    }
+
+    pub fn from_font_data(data: &[u8]) -> Result<Option<Self>> {
+        let data = woff::decode_font_data(data)?;
+        match Sfnt::parse(&data)?.get_font_table(FVAR)? {
+            Some(table) => Ok(Some(Self::parse(table)?)),
+            None => Ok(None),
+        }
+    }
+
+    pub fn from_font(font: &Font) -> Result<Option<Self>> {
+        match font.get_font_table(FVAR)? {
+            Some(table) => Ok(Some(Self::parse(&table)?)),
+            None => Ok(None),
+        }
+    }
+
+    pub fn get_axis(&self, tag: u32) -> Option<&VariationAxis> {
+        self.axes.iter().find(|axis| axis.tag == tag)
+    }
+
+    // 名前付きインスタンスの wght, wdth, ital, slnt から weight, style, stretch を求める．
+    // 軸が無いものは default の値を使う．
+    pub fn get_instance_attributes(
+        &self,
+        instance: &NamedInstance,
+        default: (Weight, Style, Stretch),
+    ) -> (Weight, Style, Stretch) {
+        let (mut weight, mut style, mut stretch) = default;
+        for (axis, &value) in self.axes.iter().zip(&instance.coordinates) {
+            match axis.tag {
+                WGHT => weight = Weight(value.round().clamp(1.0, 1000.0) as i32),
+                WDTH => stretch = get_stretch_from_width(value),
+                ITAL if value >= 1.0 => style = Style::Italic,
+                SLNT if value != 0.0 && style == Style::Normal => style = Style::Oblique,
+                _ => {}
+            }
+        }
+        (weight, style, stretch)
+    }
+
+    // ユーザー座標を正規化し，avar があればそれも適用する．
+    // 足りない軸はデフォルト値として扱う．
+    pub fn normalize(&self, coordinates: &[f32], avar: Option<&Avar>) -> Vec<f32> {
+        self.axes
+            .iter()
+            .enumerate()
+            .map(|(i, axis)| {
+                let value = coordinates.get(i).copied().unwrap_or(axis.default_value);
+                let value = axis.normalize(value);
+                match avar.and_then(|avar| avar.segment_maps.get(i)) {
+                    Some(segment_map) => segment_map.map(value),
+                    None => value,
+                }
+            })
+            .collect()
+    }
+}
+
+// wdth 軸の値 (%) に最も近い usWidthClass の stretch を返す．
+pub fn get_stretch_from_width(width: f32) -> Stretch {
+    const WIDTHS: [f32; 9] = [50.0, 62.5, 75.0, 87.5, 100.0, 112.5, 125.0, 150.0, 200.0];
+    let index = WIDTHS
+        .iter()
+        .enumerate()
+        .min_by(|(_, a), (_, b)| (*a - width).abs().total_cmp(&(*b - width).abs()))
+        .map(|(index, _)| index)
+        .unwrap_or(4);
+    Stretch::from_i32(index as i32 + 1)
+}
+
+// 正規化された座標の区分線形な対応．(from, to) の順に並ぶ．
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SegmentMap(pub Vec<(f32, f32)>);
+
+impl SegmentMap {
+    pub fn map(&self, value: f32) -> f32 {
+        let maps = &self.0;
+        // 仕様では -1, 0, 1 の対応が必須だが，満たしていない場合はそのまま返す．
+        if maps.len() < 3 {
+            return value;
+        }
+        let Some(end) = maps.iter().position(|&(from, _)| value <= from) else {
+            return maps[maps.len() - 1].1;
+        };
+        let (from1, to1) = maps[end];
+        if end == 0 || value == from1 {
+            return to1;
+        }
+        let (from0, to0) = maps[end - 1];
+        to0 + (to1 - to0) * (value - from0) / (from1 - from0)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Avar {
+    pub major_version: u16,
+    pub minor_version: u16,
+    pub segment_maps: Vec<SegmentMap>,
+}
+
+impl Avar {
+    // バージョン 2 で追加されたデータは読まない．
+    pub fn parse(data: &[u8]) -> Result<Self> {
+        let mut reader = Reader::table(AVAR, data);
+        let major_version = reader.read_u16()?;
+        let minor_version = reader.read_u16()?;
+        if !matches!(major_version, 1 | 2) {
+            return Err(reader.error(format!("unknown version {}", major_version)));
+        }
+        // reserved
+        reader.skip(2)?;
+        let axis_count = reader.read_u16()?;
+        let mut segment_maps = Vec::with_capacity(axis_count as usize);
+        for _ in 0..axis_count {
+            let position_map_count = reader.read_u16()?;
+            let maps = (0..position_map_count)
+                .map(|_| Ok((reader.read_f2dot14()?, reader.read_f2dot14()?)))
+                .collect::<Result<Vec<_>>>()?;
+            segment_maps.push(SegmentMap(maps));
+        }
+        Ok(Self {
+            major_version,
+            minor_version,
+            segment_maps,
+        })
+    }
+
+    pub fn from_font_data(data: &[u8]) -> Result<Option<Self>> {
+        let data = woff::decode_font_data(data)?;
+        match Sfnt::parse(&data)?.get_font_table(AVAR)? {
+            Some(table) => Ok(Some(Self::parse(table)?)),
+            None => Ok(None),
+        }
+    }
+
+    pub fn from_font(font: &Font) -> Result<Option<Self>> {
+        match font.get_font_table(AVAR)? {
+            Some(table) => Ok(Some(Self::parse(&table)?)),
+            None => Ok(None),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_fonts::{avar, build_variable_font, fvar};
+
+    #[test]
+    fn parse_font() {
+        let data = build_variable_font("Test Var");
+        let fvar = Fvar::from_font_data(&data).unwrap().unwrap();
+        assert_eq!(fvar.axes.len(), 2);
+        assert_eq!(fvar.axes[0].to_string(), "wght 300–700");
+        assert_eq!(fvar.get_axis(WDTH).unwrap().default_value, 100.0);
+        assert!(fvar.get_axis(ITAL).is_none());
+        assert_eq!(fvar.instances.len(), 3);
+        assert_eq!(fvar.instances[2].coordinates, vec![700.0, 75.0]);
+        assert_eq!(fvar.instances[2].postscript_name_id, Some(0xFFFF));
+        let avar = Avar::from_font_data(&data).unwrap().unwrap();
+        assert_eq!(avar.segment_maps.len(), 2);
+    }
+
+    #[test]
+    fn instance_attributes() {
+        let fvar = Fvar::parse(&fvar(
+            &[
+                (WGHT, 100.0, 400.0, 900.0, 256),
+                (WDTH, 50.0, 100.0, 200.0, 257),
+                (SLNT, -12.0, 0.0, 0.0, 258),
+                (ITAL, 0.0, 0.0, 1.0, 259),
+            ],
+            &[
+                (260, &[400.0, 100.0, 0.0, 0.0]),
+                (261, &[650.4, 80.0, -12.0, 0.0]),
+                (262, &[900.0, 190.0, -12.0, 1.0]),
+            ],
+        ))
+        .unwrap();
+        let default = (Weight(500), Style::Normal, Stretch::SemiExpanded);
+        let attributes: Vec<_> = fvar
+            .instances
+            .iter()
+            .map(|instance| fvar.get_instance_attributes(instance, default))
+            .collect();
+        assert_eq!(
+            attributes,
+            vec![
+                (Weight(400), Style::Normal, Stretch::Normal),
+                (Weight(650), Style::Oblique, Stretch::Condensed),
+                (Weight(900), Style::Italic, Stretch::UltraExpanded),
+            ]
+        );
+        assert_eq!(get_stretch_from_width(87.5), Stretch::SemiCondensed);
+        assert_eq!(get_stretch_from_width(10.0), Stretch::UltraCondensed);
+    }
+
+    #[test]
+    fn normalize() {
+        let fvar = Fvar::parse(&fvar(
+            &[
+                (WGHT, 100.0, 400.0, 900.0, 256),
+                (WDTH, 75.0, 100.0, 100.0, 257),
+            ],
+            &[],
+        ))
+        .unwrap();
+        assert_eq!(fvar.normalize(&[100.0, 75.0], None), vec![-1.0, -1.0]);
+        assert_eq!(fvar.normalize(&[650.0, 87.5], None), vec![0.5, -0.5]);
+        // 範囲外の値は丸め，足りない軸はデフォルト値にする．
+        assert_eq!(fvar.normalize(&[1000.0, 120.0], None), vec![1.0, 0.0]);
+        assert_eq!(fvar.normalize(&[250.0], None), vec![-0.5, 0.0]);
+
+        let avar = Avar::parse(&avar(&[
+            &[
+                (-1.0, -1.0),
+                (-0.5, -0.75),
+                (0.0, 0.0),
+                (0.5, 0.25),
+                (1.0, 1.0),
+            ],
+            // -1, 0, 1 の対応が無いものはそのまま．
+            &[(-1.0, -0.5)],
+        ]))
+        .unwrap();
+        assert_eq!(
+            fvar.normalize(&[250.0, 87.5], Some(&avar)),
+            vec![-0.75, -0.5]
+        );
+        assert_eq!(fvar.normalize(&[525.0], Some(&avar)), vec![0.125, 0.0]);
+        assert_eq!(fvar.normalize(&[900.0], Some(&avar)), vec![1.0, 0.0]);
+    }
+
+    #[test]
+    fn malformed_tables() {
+        let data = fvar(&[(WGHT, 100.0, 400.0, 900.0, 256)], &[(257, &[700.0])]);
+        assert!(Fvar::parse(&data).is_ok());
+        // バージョンが違う．
+        let mut bad = data.clone();
+        bad[1] = 2;
+        assert!(Fvar::parse(&bad).is_err());
+        // 軸のレコードが小さすぎる．
+        let mut bad = data.clone();
+        bad[11] = 16;
+        assert!(Fvar::parse(&bad).is_err());
+        // インスタンスのレコードが軸の数に足りない．
+        let mut bad = data.clone();
+        bad[15] = 6;
+        assert!(Fvar::parse(&bad).is_err());
+        // PostScript 名 ID の無いインスタンス．
+        let mut short = data[..data.len() - 2].to_vec();
+        short[15] = 8;
+        let fvar = Fvar::parse(&short).unwrap();
+        assert_eq!(fvar.instances[0].postscript_name_id, None);
+        // 途中で切れている．
+        assert!(Fvar::parse(&data[..data.len() - 4]).is_err());
+        assert!(Fvar::parse(&data[..30]).is_err());
+
+        let data = avar(&[&[(-1.0, -1.0), (0.0, 0.0), (1.0, 1.0)]]);
+        let mut version_2 = data.clone();
+        version_2[1] = 2;
+        assert!(Avar::parse(&version_2).is_ok());
+        let mut bad = data.clone();
+        bad[1] = 3;
+        assert!(Avar::parse(&bad).is_err());
+        assert!(Avar::parse(&data[..data.len() - 2]).is_err());
+    }
+}
@@ -6,12 +6,15 @@ use windows::Win32::{
     },
 };
 
-use crate::font::Font;
+use crate::{
+    error::{Error, Result},
+    font::Font,
+};
 
 pub fn convert_font_to_logfont(
     interop: &IDWriteGdiInterop,
     font: &IDWriteFont,
-) -> anyhow::Result<(LOGFONTW, bool)> {
+) -> Result<(LOGFONTW, bool)> {
     let mut logfont = LOGFONTW::default();
     let mut is_system_font = BOOL::from(false);
     unsafe {
@@ -23,18 +26,21 @@ pub fn convert_font_to_logfont(
 pub fn create_font_from_logfont(
     interop: &IDWriteGdiInterop,
     logfont: &LOGFONTW,
-) -> anyhow::Result<IDWriteFont> {
+) -> Result<IDWriteFont> {
     unsafe { Ok(interop.CreateFontFromLOGFONT(logfont)?) }
 }
 
 pub struct GdiInterop(pub IDWriteGdiInterop);
 
 impl GdiInterop {
-    pub fn convert_font_to_logfont(&self, font: &Font) -> anyhow::Result<(LOGFONTW, bool)> {
-        convert_font_to_logfont(&self.0, &font.0)
+    pub fn convert_font_to_logfont(&self, font: &Font) -> Result<(LOGFONTW, bool)> {
+        let font = font.as_dwrite().ok_or_else(|| {
+            Error::NotSupported("GDI interop requires a DirectWrite font".to_string())
+        })?;
+        convert_font_to_logfont(&self.0, font)
     }
 
-    pub fn create_font_from_logfont(&self, logfont: &LOGFONTW) -> anyhow::Result<IDWriteFont> {
-        create_font_from_logfont(&self.0, logfont)
+    pub fn create_font_from_logfont(&self, logfont: &LOGFONTW) -> Result<Font> {
+        create_font_from_logfont(&self.0, logfont).map(Font::new)
     }
 }
@@ -0,0 +1,208 @@
+use crate::localized_strings::LocalizedString;
+
+// serde を使わずに JSON を書くための値．dwrite-rs コマンドでも使う．
+// キーは書いた順番のまま出力する．
+#[derive(Debug, Clone, PartialEq)]
+pub enum Json {
+    Null,
+    Bool(bool),
+    Int(i64),
+    Number(f32),
+    String(String),
+    Array(Vec<Json>),
+    Object(Vec<(String, Json)>),
+}
+
+impl Json {
+    pub fn object<K: Into<String>>(fields: impl IntoIterator<Item = (K, Json)>) -> Self {
+        Self::Object(
+            fields
+                .into_iter()
+                .map(|(key, value)| (key.into(), value))
+                .collect(),
+        )
+    }
+
+    pub fn array(items: impl IntoIterator<Item = Json>) -> Self {
+        Self::Array(items.into_iter().collect())
+    }
+
+    // 深さが depth 未満の配列とオブジェクトは要素を一行ずつ書き，それより深いものは一行に書く．
+    // インデントは 2 文字で，最後に改行を付ける．
+    pub fn to_string_pretty(&self, depth: usize) -> String {
+        let mut s = String::new();
+        self.write(&mut s, 0, depth);
+        s.push('\n');
+        s
+    }
+
+    fn write(&self, s: &mut String, level: usize, depth: usize) {
+        match self {
+            Self::Null => s.push_str("null"),
+            Self::Bool(value) => s.push_str(&value.to_string()),
+            Self::Int(value) => s.push_str(&value.to_string()),
+            // NaN と無限大は JSON で書けないので null にする．
+            Self::Number(value) if value.is_finite() => s.push_str(&value.to_string()),
+            Self::Number(_) => s.push_str("null"),
+            Self::String(value) => s.push_str(&string(value)),
+            Self::Array(items) => write_block(s, '[', ']', items, level, depth, |s, item| {
+                item.write(s, level + 1, depth)
+            }),
+            Self::Object(fields) => {
+                write_block(s, '{', '}', fields, level, depth, |s, (key, value)| {
+                    s.push_str(&string(key));
+                    s.push_str(": ");
+                    value.write(s, level + 1, depth);
+                })
+            }
+        }
+    }
+}
+
+fn write_block<T>(
+    s: &mut String,
+    open: char,
+    close: char,
+    items: &[T],
+    level: usize,
+    depth: usize,
+    mut f: impl FnMut(&mut String, &T),
+) {
+    s.push(open);
+    if items.is_empty() {
+        s.push(close);
+        return;
+    }
+    let expand = level < depth;
+    for (index, item) in items.iter().enumerate() {
+        if index > 0 {
+            s.push(',');
+            if !expand {
+                s.push(' ');
+            }
+        }
+        if expand {
+            s.push('\n');
+            s.push_str(&"  ".repeat(level + 1));
+        }
+        f(s, item);
+    }
+    if expand {
+        s.push('\n');
+        s.push_str(&"  ".repeat(level));
+    }
+    s.push(close);
+}
+
+impl From<bool> for Json {
+    fn from(value: bool) -> Self {
+        Self::Bool(value)
+    }
+}
+
+impl From<i32> for Json {
+    fn from(value: i32) -> Self {
+        Self::Int(value as i64)
+    }
+}
+
+impl From<i64> for Json {
+    fn from(value: i64) -> Self {
+        Self::Int(value)
+    }
+}
+
+impl From<u32> for Json {
+    fn from(value: u32) -> Self {
+        Self::Int(value as i64)
+    }
+}
+
+impl From<usize> for Json {
+    fn from(value: usize) -> Self {
+        Self::Int(value as i64)
+    }
+}
+
+impl From<f32> for Json {
+    fn from(value: f32) -> Self {
+        Self::Number(value)
+    }
+}
+
+impl From<&str> for Json {
+    fn from(value: &str) -> Self {
+        Self::String(value.to_string())
+    }
+}
+
+impl From<String> for Json {
+    fn from(value: String) -> Self {
+        Self::String(value)
+    }
+}
+
+impl<T: Into<Json>> From<Option<T>> for Json {
+    fn from(value: Option<T>) -> Self {
+        value.map_or(Self::Null, Into::into)
+    }
+}
+
+pub fn string(s: &str) -> String {
+    let mut json = String::with_capacity(s.len() + 2);
+    json.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => json.push_str("\\\""),
+            '\\' => json.push_str("\\\\"),
+            '\n' => json.push_str("\\n"),
+            '\r' => json.push_str("\\r"),
+            '\t' => json.push_str("\\t"),
+            c if (c as u32) < 0x20 => json.push_str(&format!("\\u{:04x}", c as u32)),
+            c => json.push(c),
+        }
+    }
+    json.push('"');
+    json
+}
+
+pub fn names(names: &[LocalizedString]) -> Json {
+    Json::array(names.iter().map(|name| {
+        Json::object([
+            ("string", name.string.as_str().into()),
+            ("locale", name.locale.as_str().into()),
+        ])
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // 深さより浅いものだけを展開する．
+    #[test]
+    fn pretty() {
+        let value = Json::object([
+            ("name", "a\"b\n".into()),
+            ("count", 2.into()),
+            ("items", Json::array([Json::object([("x", 1.5.into())])])),
+            ("empty", Json::array([])),
+            ("missing", Option::<i32>::None.into()),
+            ("nan", f32::NAN.into()),
+        ]);
+        assert_eq!(
+            value.to_string_pretty(0),
+            "{\"name\": \"a\\\"b\\n\", \"count\": 2, \"items\": [{\"x\": 1.5}], \"empty\": [], \"missing\": null, \"nan\": null}\n"
+        );
+        assert_eq!(
+            value.to_string_pretty(2),
+            "{\n  \"name\": \"a\\\"b\\n\",\n  \"count\": 2,\n  \"items\": [\n    {\"x\": 1.5}\n  ],\n  \"empty\": [],\n  \"missing\": null,\n  \"nan\": null\n}\n"
+        );
+    }
+
+    // 制御文字は \u でエスケープする．
+    #[test]
+    fn escape() {
+        assert_eq!(string("\u{1}\t\\"), "\"\\u0001\\t\\\\\"");
+    }
+}
@@ -1,9 +1,41 @@
+pub mod backend;
+pub mod cmap;
+pub mod error;
 pub mod factory;
+pub mod family_model;
 pub mod font;
 pub mod font_collection;
+pub mod font_collection_builder;
+#[cfg(windows)]
+pub mod font_collection_loader;
+pub mod font_description;
+pub mod font_descriptor;
+#[cfg(windows)]
 pub mod font_face;
 pub mod font_family;
 pub mod font_file;
+pub mod font_matching;
+pub mod fvar;
+#[cfg(windows)]
 pub mod gdi_interop;
+pub mod json;
+#[cfg(windows)]
 pub mod local_font_file_loader;
+pub mod locale;
 pub mod localized_strings;
+pub mod logfont;
+#[cfg(windows)]
+pub mod memory_font_file_loader;
+pub mod mock;
+pub mod name;
+pub mod os2;
+pub mod portable;
+pub mod sfnt;
+pub mod snapshot;
+pub mod stat;
+#[cfg(test)]
+mod test_fonts;
+pub mod woff;
+pub mod woff2;
+
+pub use error::{Error, Result};
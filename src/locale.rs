@@ -0,0 +1,283 @@
+use std::sync::{OnceLock, RwLock};
+
+#[cfg(windows)]
+use windows::{
+    core::PWSTR,
+    Win32::Globalization::{GetUserPreferredUILanguages, MUI_LANGUAGE_NAME},
+};
+
+// LocalizedStrings のロケール名を BCP 47 の言語タグとして照合する．
+// 大文字と小文字を区別せず，合わなければ zh-hant-tw，zh-hant，zh のように末尾から切り詰めて探す．
+
+// 古い言語コードや Windows 独自のロケール名と，その置き換え先．先頭の部分タグごと置き換える．
+const LEGACY_LOCALES: [(&str, &str); 6] = [
+    ("zh-chs", "zh-hans"),
+    ("zh-cht", "zh-hant"),
+    ("iw", "he"),
+    ("in", "id"),
+    ("ji", "yi"),
+    ("no", "nb"),
+];
+
+// スクリプトを省略した中国語のロケール名に，地域から分かるスクリプトを補う．
+const IMPLIED_SCRIPTS: [(&str, &str); 5] = [
+    ("zh-tw", "zh-hant-tw"),
+    ("zh-hk", "zh-hant-hk"),
+    ("zh-mo", "zh-hant-mo"),
+    ("zh-cn", "zh-hans-cn"),
+    ("zh-sg", "zh-hans-sg"),
+];
+
+// 小文字にして，POSIX のロケール名 (ja_JP.UTF-8 など) のエンコーディングを取り除き，古い言語コードを置き換える．
+// Windows の並べ替え順の接尾辞 (es-ES_tradnl など) は別のロケールなので残す．
+pub fn normalize(locale: &str) -> String {
+    let mut locale = locale.trim().to_ascii_lowercase();
+    if let Some(end) = locale.find(['.', '@']) {
+        locale.truncate(end);
+    }
+    // ハイフンより後ろの _ は並べ替え順の区切り．
+    let has_sort_suffix = matches!(
+        (locale.find('-'), locale.find('_')),
+        (Some(hyphen), Some(underscore)) if hyphen < underscore
+    );
+    if !has_sort_suffix {
+        locale = locale.replace('_', "-");
+    }
+    replace_prefix(locale, &LEGACY_LOCALES)
+}
+
+// es-es_tradnl なら es-es．並べ替え順の接尾辞がなければそのまま．
+fn strip_sort_suffix(locale: &str) -> &str {
+    locale.split_once('_').map_or(locale, |(locale, _)| locale)
+}
+
+fn replace_prefix(locale: String, table: &[(&str, &str)]) -> String {
+    for (from, to) in table {
+        if let Some(rest) = locale.strip_prefix(from) {
+            if rest.is_empty() || rest.starts_with(['-', '_']) {
+                return format!("{}{}", to, rest);
+            }
+        }
+    }
+    locale
+}
+
+// 照合に使う形．normalize したうえで，zh-tw を zh-hant-tw のようにスクリプト付きにする．
+pub fn canonicalize(locale: &str) -> String {
+    replace_prefix(normalize(locale), &IMPLIED_SCRIPTS)
+}
+
+// 並べ替え順の接尾辞があればそれを，なければ最後の部分タグを取り除く．
+// その結果一文字の部分タグ (x や u) で終わる場合はそれも取り除く．
+pub fn truncate(locale: &str) -> Option<&str> {
+    let stripped = strip_sort_suffix(locale);
+    if stripped.len() < locale.len() {
+        return Some(stripped);
+    }
+    let mut locale = &locale[..locale.rfind('-')?];
+    if let Some(index) = locale.rfind('-') {
+        if locale.len() - index == 2 {
+            locale = &locale[..index];
+        }
+    }
+    Some(locale)
+}
+
+// zh-Hant-TW なら zh-hant-tw，zh-hant，zh の順．zh-TW も同じになる．
+// es-ES_tradnl なら es-es_tradnl，es-es，es の順．
+pub fn fallback_chain(locale: &str) -> Vec<String> {
+    let locale = canonicalize(locale);
+    let mut chain = vec![];
+    let mut current = locale.as_str();
+    while !current.is_empty() {
+        chain.push(current.to_string());
+        match truncate(current) {
+            Some(truncated) => current = truncated,
+            None => break,
+        }
+    }
+    chain
+}
+
+// preferred の先頭から順に available のロケール名と照合し，最初に一致したものの添字を返す．
+// 各ロケールについて，切り詰めた言語タグと同じものを，なければ並べ替え順の接尾辞を除くと同じもの
+// (es-es なら es-es_tradnl) を，それもなければより詳しいもの (zh-hant なら zh-hk) を探してから，
+// さらに切り詰める．より詳しいものは接尾辞のないものを優先する．
+pub fn best_match<S: AsRef<str>, P: AsRef<str>>(available: &[S], preferred: &[P]) -> Option<usize> {
+    let available = available
+        .iter()
+        .map(|locale| canonicalize(locale.as_ref()))
+        .collect::<Vec<_>>();
+    let find = |f: &dyn Fn(&str) -> bool| available.iter().position(|locale| f(locale));
+    for locale in preferred {
+        for range in fallback_chain(locale.as_ref()) {
+            let prefix = format!("{}-", range);
+            let index = find(&|locale| locale == range)
+                .or_else(|| find(&|locale| strip_sort_suffix(locale) == range))
+                .or_else(|| find(&|locale| locale.starts_with(&prefix) && !locale.contains('_')))
+                .or_else(|| find(&|locale| locale.starts_with(&prefix)));
+            if index.is_some() {
+                return index;
+            }
+        }
+    }
+    None
+}
+
+// ユーザーの UI 言語を優先順に返す．名前は normalize したもの．
+#[cfg(windows)]
+pub fn user_ui_locales() -> Vec<String> {
+    let mut count = 0;
+    let mut len = 0;
+    unsafe {
+        if GetUserPreferredUILanguages(MUI_LANGUAGE_NAME, &mut count, PWSTR::null(), &mut len)
+            .is_err()
+        {
+            return vec![];
+        }
+        let mut buf = vec![0_u16; len as usize];
+        if GetUserPreferredUILanguages(
+            MUI_LANGUAGE_NAME,
+            &mut count,
+            PWSTR(buf.as_mut_ptr()),
+            &mut len,
+        )
+        .is_err()
+        {
+            return vec![];
+        }
+        dedup(
+            buf.split(|c| *c == 0)
+                .map(|locale| normalize(&String::from_utf16_lossy(locale))),
+        )
+    }
+}
+
+// gettext と同じく LANGUAGE，LC_ALL，LC_MESSAGES，LANG の順に見る．
+#[cfg(not(windows))]
+pub fn user_ui_locales() -> Vec<String> {
+    let locales = ["LANGUAGE", "LC_ALL", "LC_MESSAGES", "LANG"]
+        .iter()
+        .filter_map(|name| std::env::var(name).ok())
+        .flat_map(|value| {
+            value
+                .split(':')
+                .map(normalize)
+                .filter(|locale| locale != "c" && locale != "posix")
+                .collect::<Vec<_>>()
+        });
+    dedup(locales)
+}
+
+fn dedup(locales: impl Iterator<Item = String>) -> Vec<String> {
+    let mut result: Vec<String> = vec![];
+    for locale in locales {
+        if !locale.is_empty() && !result.contains(&locale) {
+            result.push(locale);
+        }
+    }
+    result
+}
+
+static DEFAULT_LOCALES: RwLock<Option<Vec<String>>> = RwLock::new(None);
+static USER_LOCALES: OnceLock<Vec<String>> = OnceLock::new();
+
+// LocalizedStrings::get_preferred が使うロケールの優先順位．
+// set_default_locales で変えていなければ，ユーザーの UI 言語の後に en-us を加えたもの．
+pub fn default_locales() -> Vec<String> {
+    let locales = DEFAULT_LOCALES
+        .read()
+        .unwrap_or_else(|error| error.into_inner());
+    match locales.as_ref() {
+        Some(locales) => locales.clone(),
+        None => USER_LOCALES
+            .get_or_init(|| {
+                let mut locales = user_ui_locales();
+                if !locales.iter().any(|locale| locale == "en-us") {
+                    locales.push("en-us".to_string());
+                }
+                locales
+            })
+            .clone(),
+    }
+}
+
+pub fn set_default_locales<S: AsRef<str>>(locales: &[S]) {
+    let locales = locales
+        .iter()
+        .map(|locale| normalize(locale.as_ref()))
+        .collect();
+    *DEFAULT_LOCALES
+        .write()
+        .unwrap_or_else(|error| error.into_inner()) = Some(locales);
+}
+
+// ユーザーの UI 言語から作る既定の優先順位に戻す．
+pub fn reset_default_locales() {
+    *DEFAULT_LOCALES
+        .write()
+        .unwrap_or_else(|error| error.into_inner()) = None;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn normalize_locales() {
+        assert_eq!(normalize("ja_JP.UTF-8"), "ja-jp");
+        assert_eq!(normalize("de_DE@euro"), "de-de");
+        assert_eq!(normalize("es-ES_tradnl"), "es-es_tradnl");
+        assert_eq!(normalize("zh-CHT"), "zh-hant");
+        assert_eq!(normalize("iw-IL"), "he-il");
+        assert_eq!(normalize("no"), "nb");
+        assert_eq!(normalize("nn-NO"), "nn-no");
+        assert_eq!(canonicalize("zh-TW"), "zh-hant-tw");
+        assert_eq!(canonicalize("zh-TW_radstr"), "zh-hant-tw_radstr");
+    }
+
+    #[test]
+    fn fallback() {
+        assert_eq!(
+            fallback_chain("zh-Hant-TW"),
+            ["zh-hant-tw", "zh-hant", "zh"]
+        );
+        assert_eq!(fallback_chain("zh-TW"), ["zh-hant-tw", "zh-hant", "zh"]);
+        assert_eq!(
+            fallback_chain("es-ES_tradnl"),
+            ["es-es_tradnl", "es-es", "es"]
+        );
+        assert_eq!(
+            fallback_chain("en-a-bbb-x-foo"),
+            ["en-a-bbb-x-foo", "en-a-bbb", "en"]
+        );
+        assert_eq!(truncate("en"), None);
+    }
+
+    #[test]
+    fn matching() {
+        let available = ["en-us", "zh-cn", "zh-hk", "ja-jp"];
+        assert_eq!(best_match(&available, &["zh-Hant-TW"]), Some(2));
+        assert_eq!(best_match(&available, &["zh-Hant"]), Some(2));
+        assert_eq!(best_match(&available, &["zh-SG"]), Some(1));
+        assert_eq!(best_match(&available, &["ZH"]), Some(1));
+        assert_eq!(best_match(&available, &["fr-fr", "JA-JP"]), Some(3));
+        assert_eq!(best_match(&available, &["en-GB"]), Some(0));
+        assert_eq!(best_match(&available, &["fr"]), None);
+        assert_eq!(best_match(&["zh-tw"], &["zh-hant-tw"]), Some(0));
+    }
+
+    // 並べ替え順の違うロケールは，完全に一致するものを優先する．
+    #[test]
+    fn sort_suffix() {
+        assert_eq!(best_match(&["es-es_tradnl", "es-es"], &["es-ES"]), Some(1));
+        assert_eq!(
+            best_match(&["es-es", "es-es_tradnl"], &["es-ES_tradnl"]),
+            Some(1)
+        );
+        assert_eq!(best_match(&["es-es_tradnl", "es-mx"], &["es-ES"]), Some(0));
+        assert_eq!(best_match(&["es-es"], &["es-ES_tradnl"]), Some(0));
+        assert_eq!(best_match(&["es-es_tradnl", "es-es"], &["es-AR"]), Some(1));
+        assert_eq!(best_match(&["es-es_tradnl"], &["es"]), Some(0));
+    }
+}
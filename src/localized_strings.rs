@@ -1,11 +1,20 @@
 use core::fmt;
+use std::any::Any;
 
+#[cfg(windows)]
 use windows::{
     core::{HSTRING, PCWSTR},
     Win32::{Foundation::BOOL, Graphics::DirectWrite::IDWriteLocalizedStrings},
 };
 
-pub fn get_string(strings: &IDWriteLocalizedStrings, index: u32) -> anyhow::Result<String> {
+use crate::{
+    backend::LocalizedStringsBackend,
+    error::{Error, Result},
+    locale::{self, default_locales},
+};
+
+#[cfg(windows)]
+pub fn get_string(strings: &IDWriteLocalizedStrings, index: u32) -> Result<String> {
     unsafe {
         let len = strings.GetStringLength(index)?;
         let mut buf = vec![0_u16; len as usize + 1];
@@ -14,7 +23,8 @@ pub fn get_string(strings: &IDWriteLocalizedStrings, index: u32) -> anyhow::Resu
     }
 }
 
-pub fn get_locale_name(strings: &IDWriteLocalizedStrings, index: u32) -> anyhow::Result<String> {
+#[cfg(windows)]
+pub fn get_locale_name(strings: &IDWriteLocalizedStrings, index: u32) -> Result<String> {
     unsafe {
         let len = strings.GetLocaleNameLength(index)?;
         let mut buf = vec![0_u16; len as usize + 1];
@@ -23,10 +33,8 @@ pub fn get_locale_name(strings: &IDWriteLocalizedStrings, index: u32) -> anyhow:
     }
 }
 
-pub fn find_locale_name(
-    strings: &IDWriteLocalizedStrings,
-    locale: &str,
-) -> anyhow::Result<Option<u32>> {
+#[cfg(windows)]
+pub fn find_locale_name(strings: &IDWriteLocalizedStrings, locale: &str) -> Result<Option<u32>> {
     let mut index: u32 = 0;
     let mut exists = BOOL::from(false);
     unsafe {
@@ -39,32 +47,144 @@ pub fn find_locale_name(
     }
 }
 
-pub struct LocalizedStrings(pub IDWriteLocalizedStrings);
+#[cfg(windows)]
+impl LocalizedStringsBackend for IDWriteLocalizedStrings {
+    fn get_count(&self) -> u32 {
+        unsafe { self.GetCount() }
+    }
+
+    fn get_string(&self, index: u32) -> Result<String> {
+        get_string(self, index)
+    }
+
+    fn get_locale_name(&self, index: u32) -> Result<String> {
+        get_locale_name(self, index)
+    }
+
+    fn find_locale_name(&self, locale: &str) -> Result<Option<u32>> {
+        find_locale_name(self, locale)
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+// DirectWrite を使わないバックエンドでは，文字列とロケールの組をそのまま保持する．
+impl LocalizedStringsBackend for Vec<LocalizedString> {
+    fn get_count(&self) -> u32 {
+        self.len() as u32
+    }
+
+    fn get_string(&self, index: u32) -> Result<String> {
+        match self.get(index as usize) {
+            Some(string) => Ok(string.string.clone()),
+            None => Err(Error::IndexOutOfRange {
+                index,
+                count: self.get_count(),
+            }),
+        }
+    }
+
+    fn get_locale_name(&self, index: u32) -> Result<String> {
+        match self.get(index as usize) {
+            Some(string) => Ok(string.locale.clone()),
+            None => Err(Error::IndexOutOfRange {
+                index,
+                count: self.get_count(),
+            }),
+        }
+    }
+
+    // DirectWrite の FindLocaleName と同じく大文字と小文字を区別しない．
+    fn find_locale_name(&self, locale: &str) -> Result<Option<u32>> {
+        Ok(self
+            .iter()
+            .position(|string| string.locale.eq_ignore_ascii_case(locale))
+            .map(|index| index as u32))
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+pub struct LocalizedStrings(Box<dyn LocalizedStringsBackend>);
 
 impl LocalizedStrings {
+    pub fn new<T: LocalizedStringsBackend + 'static>(backend: T) -> Self {
+        Self(Box::new(backend))
+    }
+
+    pub fn backend(&self) -> &dyn LocalizedStringsBackend {
+        self.0.as_ref()
+    }
+
+    #[cfg(windows)]
+    pub fn as_dwrite(&self) -> Option<&IDWriteLocalizedStrings> {
+        self.0.as_any().downcast_ref()
+    }
+
     pub fn get_count(&self) -> u32 {
-        unsafe { self.0.GetCount() }
+        self.0.get_count()
     }
 
-    pub fn get_string(&self, index: u32) -> anyhow::Result<String> {
-        get_string(&self.0, index)
+    pub fn get_string(&self, index: u32) -> Result<String> {
+        self.0.get_string(index)
     }
 
-    pub fn get_locale_name(&self, index: u32) -> anyhow::Result<String> {
-        get_locale_name(&self.0, index)
+    pub fn get_locale_name(&self, index: u32) -> Result<String> {
+        self.0.get_locale_name(index)
     }
 
-    pub fn find_locale_name(&self, locale: &str) -> anyhow::Result<Option<u32>> {
-        find_locale_name(&self.0, locale)
+    pub fn find_locale_name(&self, locale: &str) -> Result<Option<u32>> {
+        self.0.find_locale_name(locale)
     }
 
-    pub fn get(&self, locale: &str) -> anyhow::Result<Option<String>> {
+    pub fn get(&self, locale: &str) -> Result<Option<String>> {
         let index = self.find_locale_name(locale)?;
         match index {
             Some(index) => Ok(Some(self.get_string(index)?)),
             None => Ok(None),
         }
     }
+
+    // locales の先頭から順に，切り詰めやスクリプトも考慮して一番近いロケールの文字列を探す．
+    pub fn best_match<S: AsRef<str>>(&self, locales: &[S]) -> Result<Option<LocalizedString>> {
+        let available = (0..self.get_count())
+            .map(|index| self.get_locale_name(index))
+            .collect::<Result<Vec<_>>>()?;
+        match locale::best_match(&available, locales) {
+            Some(index) => Ok(Some(LocalizedString::from_backend(
+                self.backend(),
+                index as u32,
+            )?)),
+            None => Ok(None),
+        }
+    }
+
+    // 既定の優先順位 (locale::default_locales) で探し，どれもなければ最初の文字列を返す．
+    pub fn get_preferred(&self) -> Result<Option<String>> {
+        if let Some(string) = self.best_match(&default_locales())? {
+            return Ok(Some(string.string));
+        }
+        if self.get_count() > 0 {
+            Ok(Some(self.get_string(0)?))
+        } else {
+            Ok(None)
+        }
+    }
+
+    // COM オブジェクトなどのバックエンドから切り離した，所有権を持つ文字列の集合にする．
+    pub fn to_set(&self) -> Result<LocalizedStringSet> {
+        self.into_iter().collect()
+    }
+}
+
+impl From<LocalizedStringSet> for LocalizedStrings {
+    fn from(value: LocalizedStringSet) -> Self {
+        Self::new(value)
+    }
 }
 
 pub struct LocalizedStringsIter<'a> {
@@ -73,34 +193,32 @@ pub struct LocalizedStringsIter<'a> {
 }
 
 impl<'a> Iterator for LocalizedStringsIter<'a> {
-    type Item = LocalizedString;
+    type Item = Result<LocalizedString>;
     fn next(&mut self) -> Option<Self::Item> {
-        if self.index < self.len() {
-            let string = LocalizedString::from(&self.strings.0, self.index as u32);
+        if self.index < self.strings.get_count() as usize {
+            let item = LocalizedString::from_backend(self.strings.backend(), self.index as u32);
             self.index += 1;
-            match string {
-                Ok(string) => Some(string),
-                Err(e) => {
-                    eprintln!("{}", e);
-                    assert!(false);
-                    None
-                }
-            }
+            Some(item)
         } else {
             None
         }
     }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = self.len();
+        (len, Some(len))
+    }
 }
 
 impl<'a> ExactSizeIterator for LocalizedStringsIter<'a> {
     #[inline]
     fn len(&self) -> usize {
-        self.strings.get_count() as usize
+        (self.strings.get_count() as usize).saturating_sub(self.index)
     }
 }
 
 impl<'a> IntoIterator for &'a LocalizedStrings {
-    type Item = LocalizedString;
+    type Item = Result<LocalizedString>;
     type IntoIter = LocalizedStringsIter<'a>;
     fn into_iter(self) -> Self::IntoIter {
         Self::IntoIter {
@@ -111,13 +229,23 @@ impl<'a> IntoIterator for &'a LocalizedStrings {
 }
 
 // IDWriteLocalizedStrings に含まれるロケール文字列一つ分を表す構造体．
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct LocalizedString {
     pub string: String,
     pub locale: String,
 }
 
 impl LocalizedString {
-    pub fn from(strings: &IDWriteLocalizedStrings, index: u32) -> anyhow::Result<Self> {
+    pub fn from_backend(strings: &dyn LocalizedStringsBackend, index: u32) -> Result<Self> {
+        Ok(Self {
+            string: strings.get_string(index)?,
+            locale: strings.get_locale_name(index)?,
+        })
+    }
+
+    #[cfg(windows)]
+    pub fn from(strings: &IDWriteLocalizedStrings, index: u32) -> Result<Self> {
         Ok(Self {
             string: get_string(strings, index)?,
             locale: get_locale_name(strings, index)?,
@@ -130,3 +258,265 @@ impl fmt::Display for LocalizedString {
         write!(f, "{} ({})", self.string, self.locale)
     }
 }
+
+// examples と同じく ja-jp，en-us，その他のロケールの順に並べる．
+pub fn sort_names(names: &mut [LocalizedString]) {
+    names.sort_by_cached_key(|name| {
+        let rank = match name.locale.as_str() {
+            "ja-jp" => 0,
+            "en-us" => 1,
+            _ => 2,
+        };
+        (rank, name.locale.clone())
+    });
+}
+
+// ロケールごとに一つずつ文字列を持つ，所有権のある集合．順番は追加した順で，比較やハッシュも順番を含む．
+// ロケールは DirectWrite の FindLocaleName と同じく大文字と小文字を区別しない．
+// Vec などから作る場合，同じロケールの文字列は最初のものだけ残す．
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(from = "Vec<LocalizedString>", into = "Vec<LocalizedString>")
+)]
+pub struct LocalizedStringSet(Vec<LocalizedString>);
+
+impl LocalizedStringSet {
+    pub fn new() -> Self {
+        Self(vec![])
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    pub fn iter(&self) -> std::slice::Iter<'_, LocalizedString> {
+        self.0.iter()
+    }
+
+    pub fn as_slice(&self) -> &[LocalizedString] {
+        &self.0
+    }
+
+    pub fn into_vec(self) -> Vec<LocalizedString> {
+        self.0
+    }
+
+    fn position(&self, locale: &str) -> Option<usize> {
+        self.0
+            .iter()
+            .position(|string| string.locale.eq_ignore_ascii_case(locale))
+    }
+
+    pub fn contains_locale(&self, locale: &str) -> bool {
+        self.position(locale).is_some()
+    }
+
+    pub fn get(&self, locale: &str) -> Option<&str> {
+        self.position(locale)
+            .map(|index| self.0[index].string.as_str())
+    }
+
+    // 同じロケールの文字列があれば置き換えて，元の文字列を返す．
+    pub fn insert<L: Into<String>, S: Into<String>>(
+        &mut self,
+        locale: L,
+        string: S,
+    ) -> Option<String> {
+        let locale = locale.into();
+        let string = string.into();
+        match self.position(&locale) {
+            Some(index) => Some(std::mem::replace(&mut self.0[index].string, string)),
+            None => {
+                self.0.push(LocalizedString { string, locale });
+                None
+            }
+        }
+    }
+
+    pub fn remove(&mut self, locale: &str) -> Option<String> {
+        self.position(locale)
+            .map(|index| self.0.remove(index).string)
+    }
+
+    // ja-jp，en-us，その他のロケールの順に並べる．
+    pub fn sort(&mut self) {
+        sort_names(&mut self.0);
+    }
+
+    pub fn best_match<S: AsRef<str>>(&self, locales: &[S]) -> Option<&LocalizedString> {
+        let available = self
+            .0
+            .iter()
+            .map(|string| &string.locale)
+            .collect::<Vec<_>>();
+        locale::best_match(&available, locales).map(|index| &self.0[index])
+    }
+
+    // 既定の優先順位 (locale::default_locales) で探し，どれもなければ最初の文字列を返す．
+    pub fn get_preferred(&self) -> Option<&str> {
+        self.best_match(&default_locales())
+            .or(self.0.first())
+            .map(|string| string.string.as_str())
+    }
+}
+
+impl LocalizedStringsBackend for LocalizedStringSet {
+    fn get_count(&self) -> u32 {
+        self.0.get_count()
+    }
+
+    fn get_string(&self, index: u32) -> Result<String> {
+        self.0.get_string(index)
+    }
+
+    fn get_locale_name(&self, index: u32) -> Result<String> {
+        self.0.get_locale_name(index)
+    }
+
+    fn find_locale_name(&self, locale: &str) -> Result<Option<u32>> {
+        self.0.find_locale_name(locale)
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+impl Extend<LocalizedString> for LocalizedStringSet {
+    fn extend<T: IntoIterator<Item = LocalizedString>>(&mut self, iter: T) {
+        for string in iter {
+            if !self.contains_locale(&string.locale) {
+                self.0.push(string);
+            }
+        }
+    }
+}
+
+impl FromIterator<LocalizedString> for LocalizedStringSet {
+    fn from_iter<T: IntoIterator<Item = LocalizedString>>(iter: T) -> Self {
+        let mut set = Self::new();
+        set.extend(iter);
+        set
+    }
+}
+
+impl From<Vec<LocalizedString>> for LocalizedStringSet {
+    fn from(value: Vec<LocalizedString>) -> Self {
+        value.into_iter().collect()
+    }
+}
+
+impl From<LocalizedStringSet> for Vec<LocalizedString> {
+    fn from(value: LocalizedStringSet) -> Self {
+        value.0
+    }
+}
+
+impl TryFrom<&LocalizedStrings> for LocalizedStringSet {
+    type Error = Error;
+    fn try_from(value: &LocalizedStrings) -> Result<Self> {
+        value.to_set()
+    }
+}
+
+#[cfg(windows)]
+impl TryFrom<&IDWriteLocalizedStrings> for LocalizedStringSet {
+    type Error = Error;
+    fn try_from(value: &IDWriteLocalizedStrings) -> Result<Self> {
+        (0..value.get_count())
+            .map(|index| LocalizedString::from(value, index))
+            .collect()
+    }
+}
+
+impl IntoIterator for LocalizedStringSet {
+    type Item = LocalizedString;
+    type IntoIter = std::vec::IntoIter<LocalizedString>;
+    fn into_iter(self) -> Self::IntoIter {
+        self.0.into_iter()
+    }
+}
+
+impl<'a> IntoIterator for &'a LocalizedStringSet {
+    type Item = &'a LocalizedString;
+    type IntoIter = std::slice::Iter<'a, LocalizedString>;
+    fn into_iter(self) -> Self::IntoIter {
+        self.0.iter()
+    }
+}
+
+// Arial (en-us), Arial (ja-jp)
+impl fmt::Display for LocalizedStringSet {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (index, string) in self.0.iter().enumerate() {
+            if index > 0 {
+                write!(f, ", ")?;
+            }
+            write!(f, "{}", string)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn name(locale: &str, string: &str) -> LocalizedString {
+        LocalizedString {
+            string: string.to_string(),
+            locale: locale.to_string(),
+        }
+    }
+
+    // 同じロケールは大文字と小文字を区別せずに最初のものだけ残す．
+    #[test]
+    fn set_from_vec() {
+        let mut set = LocalizedStringSet::from(vec![
+            name("en-us", "Arial"),
+            name("ja-jp", "アリアル"),
+            name("EN-US", "Arial 2"),
+        ]);
+        assert_eq!(set.len(), 2);
+        assert_eq!(set.get("En-Us"), Some("Arial"));
+        assert_eq!(
+            set.insert("JA-JP", "Arial JA"),
+            Some("アリアル".to_string())
+        );
+        assert_eq!(set.insert("fr-fr", "Arial FR"), None);
+        assert_eq!(set.remove("en-US"), Some("Arial".to_string()));
+        assert!(!set.contains_locale("en-us"));
+        assert_eq!(set.to_string(), "Arial JA (ja-jp), Arial FR (fr-fr)");
+        set.extend([name("en-us", "Arial"), name("fr-FR", "Arial 3")]);
+        set.sort();
+        let locales: Vec<_> = set.iter().map(|name| name.locale.as_str()).collect();
+        assert_eq!(locales, vec!["ja-jp", "en-us", "fr-fr"]);
+        assert_eq!(set.get_preferred(), Some("Arial"));
+        assert!(LocalizedStringSet::new().get_preferred().is_none());
+    }
+
+    // LocalizedStrings との間で変換しても順番と文字列は変わらない．
+    #[test]
+    fn convert_localized_strings() {
+        let set =
+            LocalizedStringSet::from(vec![name("ja-jp", "メイリオ"), name("en-us", "Meiryo")]);
+        let strings = LocalizedStrings::from(set.clone());
+        assert_eq!(strings.get_count(), 2);
+        assert_eq!(strings.find_locale_name("EN-us").unwrap(), Some(1));
+        assert_eq!(strings.get("ja-jp").unwrap().as_deref(), Some("メイリオ"));
+        assert!(matches!(
+            strings.get_string(2),
+            Err(Error::IndexOutOfRange { index: 2, count: 2 })
+        ));
+        assert_eq!(LocalizedStringSet::try_from(&strings).unwrap(), set);
+        let best = set.best_match(&["en-gb"]).unwrap();
+        assert_eq!(best.string, "Meiryo");
+        assert_eq!(Vec::from(set).len(), 2);
+    }
+}
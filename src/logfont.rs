@@ -0,0 +1,454 @@
+#[cfg(windows)]
+use windows::Win32::Graphics::Gdi::{
+    FONT_CHARSET, FONT_CLIP_PRECISION, FONT_OUTPUT_PRECISION, FONT_QUALITY, LOGFONTW,
+};
+
+use crate::{
+    error::{Error, Result},
+    font::{Font, InformationalStringId, Stretch, Style, Weight},
+    font_collection::FontCollection,
+    font_matching::{FontMatch, MatchKey},
+    localized_strings::LocalizedStringSet,
+    os2::{
+        get_simulations, read_mac_style, FsSelection, Os2, HEAD, MAC_STYLE_BOLD, MAC_STYLE_ITALIC,
+        OS2,
+    },
+};
+
+// GdiInterop の ConvertFontToLOGFONT と CreateFontFromLOGFONT を，フォントのテーブルから Rust で再現する．
+// どのバックエンドのフォントでも使える．
+
+// lfFaceName の長さ (終端の 0 を含む)．
+pub const LF_FACESIZE: usize = 32;
+
+pub const ANSI_CHARSET: u8 = 0;
+pub const DEFAULT_CHARSET: u8 = 1;
+pub const SYMBOL_CHARSET: u8 = 2;
+
+pub const FIXED_PITCH: u8 = 1;
+pub const VARIABLE_PITCH: u8 = 2;
+pub const FF_DONTCARE: u8 = 0x00;
+pub const FF_ROMAN: u8 = 0x10;
+pub const FF_SWISS: u8 = 0x20;
+pub const FF_MODERN: u8 = 0x30;
+pub const FF_SCRIPT: u8 = 0x40;
+pub const FF_DECORATIVE: u8 = 0x50;
+
+// OS/2 の ulCodePageRange1 のビットと，GDI の文字セット．
+const CODE_PAGE_CHARSETS: [(u32, u8); 16] = [
+    (0, ANSI_CHARSET),
+    (1, 238),  // EASTEUROPE_CHARSET
+    (2, 204),  // RUSSIAN_CHARSET
+    (3, 161),  // GREEK_CHARSET
+    (4, 162),  // TURKISH_CHARSET
+    (5, 177),  // HEBREW_CHARSET
+    (6, 178),  // ARABIC_CHARSET
+    (7, 186),  // BALTIC_CHARSET
+    (8, 163),  // VIETNAMESE_CHARSET
+    (16, 222), // THAI_CHARSET
+    (17, 128), // SHIFTJIS_CHARSET
+    (18, 134), // GB2312_CHARSET
+    (19, 129), // HANGUL_CHARSET
+    (20, 136), // CHINESEBIG5_CHARSET
+    (21, 130), // JOHAB_CHARSET
+    (31, SYMBOL_CHARSET),
+];
+
+// GDI の LOGFONTW と同じ内容を持つ，どのプラットフォームでも使える構造体．
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct LogFont {
+    pub height: i32,
+    pub width: i32,
+    pub escapement: i32,
+    pub orientation: i32,
+    // 0 (FW_DONTCARE) は 400 とみなす．
+    pub weight: i32,
+    pub italic: bool,
+    pub underline: bool,
+    pub strike_out: bool,
+    pub char_set: u8,
+    pub out_precision: u8,
+    pub clip_precision: u8,
+    pub quality: u8,
+    pub pitch_and_family: u8,
+    // UTF-16 で 31 単位まで．
+    pub face_name: String,
+}
+
+impl LogFont {
+    // ConvertFontToLOGFONT と同じく，lfFaceName，lfWeight，lfItalic，lfCharSet，lfPitchAndFamily を埋める．
+    // それ以外のフィールドは 0 のまま．
+    pub fn from_font(font: &Font) -> Result<Self> {
+        let os2 = match optional(font.get_font_table(OS2))?.flatten() {
+            Some(table) => Some(Os2::parse(&table)?),
+            None => None,
+        };
+        let (weight, italic) = match &os2 {
+            Some(os2) => get_os2_weight_and_italic(os2),
+            None => get_fallback_weight_and_italic(font)?,
+        };
+        let (char_set, pitch_and_family) = match &os2 {
+            Some(os2) => (get_char_set(os2), get_pitch_and_family(os2)),
+            None => (DEFAULT_CHARSET, VARIABLE_PITCH | FF_DONTCARE),
+        };
+        Ok(Self {
+            weight,
+            italic,
+            char_set,
+            pitch_and_family,
+            face_name: get_face_name(font)?,
+            ..Default::default()
+        })
+    }
+
+    // CreateFontFromLOGFONT と同じく，Win32 ファミリー名が lfFaceName と一致するフォントから，
+    // lfWeight と lfItalic に一番近いものを探す．ファミリーの分け方には依らない．
+    pub fn find_font(&self, collection: &FontCollection) -> Result<Option<FontMatch>> {
+        let weight = match self.weight {
+            0 => Weight(400),
+            weight => Weight(weight),
+        };
+        let style = if self.italic {
+            Style::Italic
+        } else {
+            Style::Normal
+        };
+        let request = (weight, Stretch::Normal, style);
+        let mut best: Option<(MatchKey, u32, Font, Self)> = None;
+        // 読めないファミリーやフォントがあっても，残りのフォントから探す．
+        for family in collection.into_iter().flatten() {
+            for (index, font) in family.into_iter().enumerate() {
+                let Ok(font) = font else {
+                    continue;
+                };
+                if font.is_simulation() || !has_face_name(&font, &self.face_name).unwrap_or(false) {
+                    continue;
+                }
+                let Ok(logfont) = Self::from_font(&font) else {
+                    continue;
+                };
+                let attributes = (Weight(logfont.weight), Stretch::Normal, logfont.get_style());
+                let key = MatchKey::new(attributes, request);
+                let is_better = match &best {
+                    Some((best, _, _, _)) => key < *best,
+                    None => true,
+                };
+                if is_better {
+                    best = Some((key, index as u32, font, logfont));
+                }
+            }
+        }
+        Ok(best.map(|(_, index, font, logfont)| FontMatch {
+            font,
+            index,
+            simulations: get_simulations(
+                Weight(logfont.weight),
+                logfont.get_style(),
+                weight,
+                style,
+            ),
+        }))
+    }
+
+    pub fn get_style(&self) -> Style {
+        if self.italic {
+            Style::Italic
+        } else {
+            Style::Normal
+        }
+    }
+}
+
+// GDI が列挙するフェイスの lfWeight は usWeightClass そのもので，fsSelection の BOLD は見ない．
+// 斜体は fsSelection の ITALIC だけを見る．
+fn get_os2_weight_and_italic(os2: &Os2) -> (i32, bool) {
+    (
+        os2.weight_class as i32,
+        os2.fs_selection.contains(FsSelection::Italic),
+    )
+}
+
+// OS/2 テーブルが無ければ Win32 のサブファミリー名 (Bold Italic など) を，
+// それも無ければ head の macStyle を，テーブルを読めなければバックエンドの属性を使う．
+fn get_fallback_weight_and_italic(font: &Font) -> Result<(i32, bool)> {
+    let subfamily = font
+        .get_informational_strings(InformationalStringId::Win32SubfamilyNames)?
+        .map(|names| names.to_set())
+        .transpose()?
+        .and_then(|names| {
+            names
+                .get("en-us")
+                .or(names.iter().next().map(|name| name.string.as_str()))
+                .map(|name| name.to_ascii_lowercase())
+        });
+    if let Some(subfamily) = subfamily {
+        let weight = if subfamily.contains("bold") { 700 } else { 400 };
+        let italic = subfamily.contains("italic") || subfamily.contains("oblique");
+        return Ok((weight, italic));
+    }
+    match optional(font.get_font_table(HEAD))?.flatten() {
+        Some(head) => {
+            let mac_style = read_mac_style(&head)?;
+            let weight = if mac_style & MAC_STYLE_BOLD != 0 {
+                700
+            } else {
+                400
+            };
+            Ok((weight, mac_style & MAC_STYLE_ITALIC != 0))
+        }
+        None => Ok((font.get_weight().0, font.get_style() != Style::Normal)),
+    }
+}
+
+// フォントが対応している GDI の文字セット．
+pub fn get_char_sets(os2: &Os2) -> Vec<u8> {
+    let Some([code_page_range, _]) = os2.code_page_range else {
+        return vec![];
+    };
+    CODE_PAGE_CHARSETS
+        .iter()
+        .filter(|(bit, _)| code_page_range & (1 << bit) != 0)
+        .map(|(_, char_set)| *char_set)
+        .collect()
+}
+
+// 記号フォントなら SYMBOL_CHARSET，ANSI_CHARSET に対応していればそれ，それ以外は最初の文字セット．
+fn get_char_set(os2: &Os2) -> u8 {
+    let char_sets = get_char_sets(os2);
+    if char_sets.contains(&SYMBOL_CHARSET) {
+        SYMBOL_CHARSET
+    } else if char_sets.contains(&ANSI_CHARSET) {
+        ANSI_CHARSET
+    } else {
+        char_sets.first().copied().unwrap_or(DEFAULT_CHARSET)
+    }
+}
+
+// ピッチは PANOSE の bProportion，ファミリーは sFamilyClass から決める．
+fn get_pitch_and_family(os2: &Os2) -> u8 {
+    let is_monospaced = os2.panose[0] == 2 && os2.panose[3] == 9;
+    let pitch = if is_monospaced {
+        FIXED_PITCH
+    } else {
+        VARIABLE_PITCH
+    };
+    let family = match os2.family_class >> 8 {
+        _ if is_monospaced => FF_MODERN,
+        1..=5 | 7 => FF_ROMAN,
+        8 => FF_SWISS,
+        9 | 12 => FF_DECORATIVE,
+        10 => FF_SCRIPT,
+        _ => FF_DONTCARE,
+    };
+    pitch | family
+}
+
+// Win32 ファミリー名を既定のロケールの優先順位で選ぶ．無ければファミリー名を使う．
+fn get_face_name(font: &Font) -> Result<String> {
+    let names = get_win32_family_names(font)?;
+    let name = match names.get_preferred() {
+        Some(name) => name.to_string(),
+        None => font
+            .get_font_family()?
+            .get_family_names()?
+            .get_preferred()?
+            .unwrap_or_default(),
+    };
+    Ok(truncate_face_name(&name))
+}
+
+fn get_win32_family_names(font: &Font) -> Result<LocalizedStringSet> {
+    match font.get_informational_strings(InformationalStringId::Win32FamilyNames)? {
+        Some(names) => names.to_set(),
+        None => Ok(LocalizedStringSet::new()),
+    }
+}
+
+// GDI と同じく大文字と小文字を区別せず，どのロケールの名前とも比べる．
+fn has_face_name(font: &Font, face_name: &str) -> Result<bool> {
+    let names = match get_win32_family_names(font)? {
+        names if names.is_empty() => font.get_font_family()?.get_family_names()?.to_set()?,
+        names => names,
+    };
+    Ok(names
+        .iter()
+        .any(|name| truncate_face_name(&name.string).eq_ignore_ascii_case(face_name)))
+}
+
+// lfFaceName に入る長さ (UTF-16 で 31 単位) に切り詰める．サロゲートペアの途中では切らない．
+pub fn truncate_face_name(name: &str) -> String {
+    let mut len = 0;
+    name.chars()
+        .take_while(|c| {
+            len += c.len_utf16();
+            len < LF_FACESIZE
+        })
+        .collect()
+}
+
+// バックエンドが対応していないテーブルは無いものとする．
+fn optional<T>(result: Result<T>) -> Result<Option<T>> {
+    match result {
+        Ok(value) => Ok(Some(value)),
+        Err(Error::NotSupported(_)) => Ok(None),
+        Err(error) => Err(error),
+    }
+}
+
+#[cfg(windows)]
+impl From<&LOGFONTW> for LogFont {
+    fn from(value: &LOGFONTW) -> Self {
+        let len = value
+            .lfFaceName
+            .iter()
+            .position(|c| *c == 0)
+            .unwrap_or(LF_FACESIZE);
+        Self {
+            height: value.lfHeight,
+            width: value.lfWidth,
+            escapement: value.lfEscapement,
+            orientation: value.lfOrientation,
+            weight: value.lfWeight,
+            italic: value.lfItalic != 0,
+            underline: value.lfUnderline != 0,
+            strike_out: value.lfStrikeOut != 0,
+            char_set: value.lfCharSet.0,
+            out_precision: value.lfOutPrecision.0,
+            clip_precision: value.lfClipPrecision.0,
+            quality: value.lfQuality.0,
+            pitch_and_family: value.lfPitchAndFamily,
+            face_name: String::from_utf16_lossy(&value.lfFaceName[..len]),
+        }
+    }
+}
+
+#[cfg(windows)]
+impl From<&LogFont> for LOGFONTW {
+    fn from(value: &LogFont) -> Self {
+        let mut face_name = [0_u16; LF_FACESIZE];
+        for (dst, src) in face_name
+            .iter_mut()
+            .zip(truncate_face_name(&value.face_name).encode_utf16())
+        {
+            *dst = src;
+        }
+        Self {
+            lfHeight: value.height,
+            lfWidth: value.width,
+            lfEscapement: value.escapement,
+            lfOrientation: value.orientation,
+            lfWeight: value.weight,
+            lfItalic: value.italic as u8,
+            lfUnderline: value.underline as u8,
+            lfStrikeOut: value.strike_out as u8,
+            lfCharSet: FONT_CHARSET(value.char_set),
+            lfOutPrecision: FONT_OUTPUT_PRECISION(value.out_precision),
+            lfClipPrecision: FONT_CLIP_PRECISION(value.clip_precision),
+            lfQuality: FONT_QUALITY(value.quality),
+            lfPitchAndFamily: value.pitch_and_family,
+            lfFaceName: face_name,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::*;
+    use crate::{
+        error::DWRITE_E_FILEFORMAT,
+        factory::Factory,
+        font::Simulations,
+        font_file::FontFileReference,
+        mock::{names, MockFont, MockFontCollection, MockFontFamily},
+        test_fonts::{build_font, FS_SELECTION_BOLD, FS_SELECTION_ITALIC},
+    };
+
+    // lfWeight は fsSelection の BOLD に関係なく usWeightClass になる．
+    #[test]
+    fn from_font() {
+        let collection = Factory::portable()
+            .create_font_collection(&[
+                FontFileReference::from_memory(build_font(
+                    "Test Sans",
+                    "Bold",
+                    500,
+                    FS_SELECTION_BOLD,
+                )),
+                FontFileReference::from_memory(build_font(
+                    "Test Sans",
+                    "Italic",
+                    400,
+                    FS_SELECTION_ITALIC,
+                )),
+            ])
+            .unwrap();
+        let (fonts, report) = collection.get_fonts();
+        assert!(report.is_complete());
+        let logfonts = fonts
+            .iter()
+            .map(|font| LogFont::from_font(font).unwrap())
+            .collect::<Vec<_>>();
+        let attributes = logfonts
+            .iter()
+            .map(|logfont| (logfont.weight, logfont.italic))
+            .collect::<Vec<_>>();
+        assert_eq!(attributes, vec![(400, true), (500, false)]);
+        for logfont in &logfonts {
+            assert_eq!(logfont.char_set, ANSI_CHARSET);
+            assert_eq!(logfont.pitch_and_family, VARIABLE_PITCH | FF_DONTCARE);
+        }
+
+        let logfont = LogFont {
+            face_name: "TEST SANS".to_string(),
+            weight: 700,
+            ..Default::default()
+        };
+        let font_match = logfont.find_font(&collection).unwrap().unwrap();
+        assert_eq!(font_match.font.get_weight(), Weight(500));
+        assert_eq!(font_match.simulations, Simulations::Bold);
+    }
+
+    // 読めないファミリーやフォントは飛ばす．
+    #[test]
+    fn find_font_skips_broken_fonts() {
+        let collection: FontCollection = MockFontCollection {
+            families: vec![
+                MockFontFamily {
+                    names: names(&[("en-us", "Broken")]),
+                    error: Some(DWRITE_E_FILEFORMAT),
+                    ..Default::default()
+                },
+                MockFontFamily {
+                    names: names(&[("en-us", "Arial")]),
+                    fonts: vec![
+                        MockFont {
+                            tables: HashMap::from([(OS2, vec![0, 9])]),
+                            ..Default::default()
+                        },
+                        MockFont {
+                            error: Some(DWRITE_E_FILEFORMAT),
+                            ..Default::default()
+                        },
+                        MockFont {
+                            face_names: names(&[("en-us", "Bold")]),
+                            weight: Weight(700),
+                            ..Default::default()
+                        },
+                    ],
+                    ..Default::default()
+                },
+            ],
+        }
+        .into();
+        let logfont = LogFont {
+            face_name: "Arial".to_string(),
+            ..Default::default()
+        };
+        let font_match = logfont.find_font(&collection).unwrap().unwrap();
+        assert_eq!(font_match.index, 2);
+        assert_eq!(font_match.simulations, Simulations::None);
+    }
+}
@@ -1,3 +1,760 @@
-fn main() {
-    println!("Hello, world!");
+use std::{
+    fmt,
+    io::Write,
+    path::{Path, PathBuf},
+    process::ExitCode,
+};
+
+use dwrite_rs::{
+    factory::{Factory, FactoryType},
+    family_model::FontFamilyModel,
+    font::{Font, InformationalStringId, Simulations},
+    font_collection::{EnumerationReport, FontCollection},
+    font_collection_builder::FontCollectionBuilder,
+    font_descriptor::FontDescriptor,
+    font_file::FontFileReference,
+    json::Json,
+    locale,
+    localized_strings::{LocalizedString, LocalizedStrings},
+    sfnt::{tag_to_string, Sfnt},
+    snapshot::CollectionSnapshot,
+};
+
+// フォントコレクションを調べるコマンドラインツール．
+// Windows では DirectWrite のシステムフォントコレクションを，それ以外では portable バックエンドを使う．
+// --path を指定すると，システムフォントの代わりにそのファイルやディレクトリのフォントを使う．
+
+const USAGE: &str = "\
+usage: dwrite-rs [options] <command> [argument]
+
+commands:
+  list                  list font families
+  find <family>         list the fonts of a family
+  info <family|file>    show everything known about each font
+  names                 list the names of all fonts
+  tables <family|file>  list the sfnt tables of each font
+  dump                  dump the collection in the snapshot format
+
+options:
+  --path <file|dir>         use font files instead of the system collection (repeatable)
+  --model <typo|wss>        font family model (default: wss)
+  --format <text|tsv|json>  output format (default: text)
+  --locale <locale>         preferred locale for names (default: en-us)
+  --simulations             include fonts with bold or oblique simulations
+  -h, --help                show this help
+";
+
+#[derive(Debug)]
+enum CliError {
+    Usage(String),
+    NotFound(String),
+    Font(dwrite_rs::Error),
+}
+
+impl fmt::Display for CliError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Usage(reason) => write!(f, "{}\n\n{}", reason, USAGE.trim_end()),
+            Self::NotFound(family) => write!(f, "font family not found: {}", family),
+            Self::Font(error) => write!(f, "{}", error),
+        }
+    }
+}
+
+impl From<dwrite_rs::Error> for CliError {
+    fn from(value: dwrite_rs::Error) -> Self {
+        Self::Font(value)
+    }
+}
+
+type CliResult<T> = std::result::Result<T, CliError>;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Format {
+    Text,
+    Tsv,
+    Json,
+}
+
+#[derive(Debug)]
+struct Args {
+    command: String,
+    argument: Option<String>,
+    paths: Vec<PathBuf>,
+    model: FontFamilyModel,
+    format: Format,
+    locale: String,
+    simulations: bool,
+}
+
+impl Args {
+    // --name value と --name=value の両方を受け付ける．
+    fn parse<I: Iterator<Item = String>>(mut args: I) -> CliResult<Option<Self>> {
+        let mut positional = vec![];
+        let mut paths = vec![];
+        let mut model = FontFamilyModel::WeightStretchStyle;
+        let mut format = Format::Text;
+        let mut locale = "en-us".to_string();
+        let mut simulations = false;
+        while let Some(arg) = args.next() {
+            if !arg.starts_with('-') {
+                positional.push(arg);
+                continue;
+            }
+            let (name, inline) = match arg.split_once('=') {
+                Some((name, value)) => (name.to_string(), Some(value.to_string())),
+                None => (arg, None),
+            };
+            let mut value = || {
+                inline
+                    .clone()
+                    .or_else(|| args.next())
+                    .ok_or_else(|| CliError::Usage(format!("{} requires a value", name)))
+            };
+            match name.as_str() {
+                "-h" | "--help" => return Ok(None),
+                "--path" => paths.push(PathBuf::from(value()?)),
+                "--model" => {
+                    model = match value()?.as_str() {
+                        "typo" | "typographic" => FontFamilyModel::Typographic,
+                        "wss" => FontFamilyModel::WeightStretchStyle,
+                        other => return Err(CliError::Usage(format!("unknown model: {}", other))),
+                    }
+                }
+                "--format" => {
+                    format = match value()?.as_str() {
+                        "text" => Format::Text,
+                        "tsv" => Format::Tsv,
+                        "json" => Format::Json,
+                        other => return Err(CliError::Usage(format!("unknown format: {}", other))),
+                    }
+                }
+                "--locale" => locale = value()?,
+                "--simulations" => simulations = true,
+                _ => return Err(CliError::Usage(format!("unknown option: {}", name))),
+            }
+        }
+        let mut positional = positional.into_iter();
+        let Some(command) = positional.next() else {
+            return Err(CliError::Usage("missing command".to_string()));
+        };
+        let argument = positional.next();
+        if let Some(extra) = positional.next() {
+            return Err(CliError::Usage(format!("unexpected argument: {}", extra)));
+        }
+        Ok(Some(Self {
+            command,
+            argument,
+            paths,
+            model,
+            format,
+            locale,
+            simulations,
+        }))
+    }
+
+    fn require_argument(&self, name: &str) -> CliResult<&str> {
+        self.argument
+            .as_deref()
+            .ok_or_else(|| CliError::Usage(format!("{} requires <{}>", self.command, name)))
+    }
+
+    fn reject_argument(&self) -> CliResult<()> {
+        match &self.argument {
+            Some(argument) => Err(CliError::Usage(format!(
+                "{} takes no argument: {}",
+                self.command, argument
+            ))),
+            None => Ok(()),
+        }
+    }
+
+    // --path のファイルやディレクトリが無ければエラーにする．
+    fn collection(&self, factory: &Factory) -> CliResult<FontCollection> {
+        if self.paths.is_empty() {
+            return Ok(factory.get_system_font_collection_with_model(false, self.model)?);
+        }
+        let mut builder = FontCollectionBuilder::new().family_model(self.model);
+        for path in &self.paths {
+            builder = if path.is_dir() {
+                builder.add_directory(path)
+            } else {
+                builder.add_file(path.as_path())
+            };
+        }
+        Ok(builder.build(factory)?)
+    }
+
+    // 引数がファイルならそのファイルのフォントを，そうでなければファミリーのフォントを返す．
+    fn fonts(&self, factory: &Factory, argument: &str) -> CliResult<Vec<Font>> {
+        let path = Path::new(argument);
+        let (collection, family) = if path.is_file() {
+            let file = FontFileReference::from(path);
+            let collection = factory.create_font_collection_with_model(&[file], self.model)?;
+            (collection, None)
+        } else {
+            (self.collection(factory)?, Some(argument))
+        };
+        let mut fonts = vec![];
+        match family {
+            Some(family) => {
+                let family = collection
+                    .find_family(family)
+                    .ok_or_else(|| CliError::NotFound(family.to_string()))?;
+                for font in &family {
+                    fonts.push(font?);
+                }
+            }
+            None => {
+                let (all, report) = collection.get_fonts();
+                warn(&report);
+                fonts = all;
+            }
+        }
+        fonts.retain(|font| self.simulations || !font.is_simulation());
+        Ok(fonts)
+    }
+}
+
+// セルの値．JSON では型を保ち，テキストと TSV では文字列にする．
+enum Value {
+    Str(String),
+    Int(i64),
+    Null,
+}
+
+impl From<String> for Value {
+    fn from(value: String) -> Self {
+        Self::Str(value)
+    }
+}
+
+impl From<&str> for Value {
+    fn from(value: &str) -> Self {
+        Self::Str(value.to_string())
+    }
+}
+
+impl From<i64> for Value {
+    fn from(value: i64) -> Self {
+        Self::Int(value)
+    }
+}
+
+impl<T: Into<Value>> From<Option<T>> for Value {
+    fn from(value: Option<T>) -> Self {
+        value.map(Into::into).unwrap_or(Self::Null)
+    }
+}
+
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Str(value) => write!(f, "{}", value),
+            Self::Int(value) => write!(f, "{}", value),
+            Self::Null => Ok(()),
+        }
+    }
+}
+
+struct Table {
+    columns: &'static [&'static str],
+    rows: Vec<Vec<Value>>,
+}
+
+impl Table {
+    fn new(columns: &'static [&'static str]) -> Self {
+        Self {
+            columns,
+            rows: vec![],
+        }
+    }
+
+    fn push(&mut self, row: Vec<Value>) {
+        self.rows.push(row);
+    }
+
+    fn render(&self, format: Format) -> String {
+        match format {
+            Format::Text => self.to_text(),
+            Format::Tsv => self.to_tsv(),
+            Format::Json => self.to_json(),
+        }
+    }
+
+    // 列を揃える．幅は文字数で数える．
+    fn to_text(&self) -> String {
+        let cells: Vec<Vec<String>> = std::iter::once(
+            self.columns
+                .iter()
+                .map(|column| column.to_string())
+                .collect(),
+        )
+        .chain(self.rows.iter().map(|row| {
+            row.iter()
+                .map(|value| single_line(&value.to_string()))
+                .collect()
+        }))
+        .collect();
+        let mut widths = vec![0; self.columns.len()];
+        for row in &cells {
+            for (width, cell) in widths.iter_mut().zip(row) {
+                *width = (*width).max(cell.chars().count());
+            }
+        }
+        let mut s = String::new();
+        for row in &cells {
+            let mut line = String::new();
+            for (index, cell) in row.iter().enumerate() {
+                if index + 1 < row.len() {
+                    let padding = widths[index] - cell.chars().count() + 2;
+                    line.push_str(cell);
+                    line.push_str(&" ".repeat(padding));
+                } else {
+                    line.push_str(cell);
+                }
+            }
+            s.push_str(line.trim_end());
+            s.push('\n');
+        }
+        s
+    }
+
+    fn to_tsv(&self) -> String {
+        let mut s = self.columns.join("\t");
+        s.push('\n');
+        for row in &self.rows {
+            let row: Vec<String> = row
+                .iter()
+                .map(|value| single_line(&value.to_string()))
+                .collect();
+            s.push_str(&row.join("\t"));
+            s.push('\n');
+        }
+        s
+    }
+
+    // 一行に一つのオブジェクトを書く．
+    fn to_json(&self) -> String {
+        Json::array(self.rows.iter().map(|row| {
+            Json::object(self.columns.iter().zip(row).map(|(column, value)| {
+                let value = match value {
+                    Value::Str(value) => value.as_str().into(),
+                    Value::Int(value) => (*value).into(),
+                    Value::Null => Json::Null,
+                };
+                (*column, value)
+            }))
+        }))
+        .to_string_pretty(1)
+    }
+}
+
+// テキストと TSV は一行に一つのレコードなので，タブと改行は空白にする．
+fn single_line(s: &str) -> String {
+    s.replace(['\t', '\r', '\n'], " ")
+}
+
+fn warn(report: &EnumerationReport) {
+    for failure in &report.failures {
+        eprintln!("warning: {}", failure);
+    }
+}
+
+// locale (切り詰めたものも含む)，en-us，先頭の名前の順に探す．
+fn pick_name(names: &[LocalizedString], locale: &str) -> Option<String> {
+    let locales = names.iter().map(|name| &name.locale).collect::<Vec<_>>();
+    locale::best_match(&locales, &[locale, "en-us"])
+        .and_then(|index| names.get(index))
+        .or(names.first())
+        .map(|name| name.string.clone())
+}
+
+fn pick_string(strings: dwrite_rs::Result<LocalizedStrings>, locale: &str) -> Option<String> {
+    let names = strings
+        .ok()?
+        .into_iter()
+        .collect::<dwrite_rs::Result<Vec<_>>>()
+        .ok()?;
+    pick_name(&names, locale)
+}
+
+fn pick_informational_string(
+    font: &Font,
+    id: InformationalStringId,
+    locale: &str,
+) -> Option<String> {
+    match font.get_informational_strings(id) {
+        Ok(Some(strings)) => pick_string(Ok(strings), locale),
+        _ => None,
+    }
+}
+
+fn simulations_name(simulations: Simulations) -> String {
+    let names: Vec<&str> = simulations
+        .iter_names()
+        .map(|(name, _)| name)
+        .filter(|name| *name != "None")
+        .collect();
+    if names.is_empty() {
+        "None".to_string()
+    } else {
+        names.join("|")
+    }
+}
+
+// フルネーム，なければファミリー名とフォント名．
+fn font_label(font: &Font, locale: &str) -> String {
+    if let Some(name) = pick_informational_string(font, InformationalStringId::FullName, locale) {
+        return name;
+    }
+    let family = font
+        .get_font_family()
+        .ok()
+        .and_then(|family| pick_string(family.get_family_names(), locale))
+        .unwrap_or_default();
+    let face = pick_string(font.get_face_names(), locale).unwrap_or_default();
+    format!("{} {}", family, face).trim().to_string()
+}
+
+fn list(args: &Args, factory: &Factory) -> CliResult<String> {
+    args.reject_argument()?;
+    let collection = args.collection(factory)?;
+    let mut rows = vec![];
+    for family in &collection {
+        let family = family?;
+        let count = family
+            .into_iter()
+            .filter_map(|font| font.ok())
+            .filter(|font| args.simulations || !font.is_simulation())
+            .count();
+        let name = pick_string(family.get_family_names(), &args.locale).unwrap_or_default();
+        rows.push((name, count));
+    }
+    rows.sort();
+    let mut table = Table::new(&["family", "fonts"]);
+    for (name, count) in rows {
+        table.push(vec![name.into(), (count as i64).into()]);
+    }
+    Ok(table.render(args.format))
+}
+
+fn find(args: &Args, factory: &Factory) -> CliResult<String> {
+    let name = args.require_argument("family")?;
+    let collection = args.collection(factory)?;
+    let family = collection
+        .find_family(name)
+        .ok_or_else(|| CliError::NotFound(name.to_string()))?;
+    let family_name = pick_string(family.get_family_names(), &args.locale);
+    let mut table = Table::new(&[
+        "family",
+        "face",
+        "weight",
+        "style",
+        "stretch",
+        "simulations",
+        "file",
+        "index",
+    ]);
+    for font in &family {
+        let font = font?;
+        if !args.simulations && font.is_simulation() {
+            continue;
+        }
+        table.push(vec![
+            family_name.clone().into(),
+            pick_string(font.get_face_names(), &args.locale).into(),
+            (font.get_weight().0 as i64).into(),
+            font.get_style().to_string().into(),
+            font.get_stretch().to_string().into(),
+            simulations_name(font.get_simulations()).into(),
+            font.get_filepath().ok().into(),
+            font.get_face_index().ok().map(i64::from).into(),
+        ]);
+    }
+    Ok(table.render(args.format))
+}
+
+fn info(args: &Args, factory: &Factory) -> CliResult<String> {
+    let argument = args.require_argument("family|file")?;
+    let mut descriptors = vec![];
+    for font in args.fonts(factory, argument)? {
+        descriptors.push((
+            font_label(&font, &args.locale),
+            FontDescriptor::from_font(&font)?,
+        ));
+    }
+    if args.format == Format::Json {
+        let descriptors = descriptors
+            .iter()
+            .map(|(_, descriptor)| descriptor.to_json_value());
+        return Ok(Json::array(descriptors).to_string_pretty(3));
+    }
+    let mut table = Table::new(&["font", "field", "locale", "value"]);
+    for (label, descriptor) in &descriptors {
+        let mut push = |field: String, locale: Option<&str>, value: String| {
+            table.push(vec![
+                label.as_str().into(),
+                field.into(),
+                locale.into(),
+                value.into(),
+            ]);
+        };
+        for name in &descriptor.family_names {
+            push(
+                "family_names".into(),
+                Some(&name.locale),
+                name.string.clone(),
+            );
+        }
+        for name in &descriptor.face_names {
+            push("face_names".into(), Some(&name.locale), name.string.clone());
+        }
+        for (id, strings) in &descriptor.informational_strings {
+            for string in strings {
+                push(
+                    id.name().into(),
+                    Some(&string.locale),
+                    string.string.clone(),
+                );
+            }
+        }
+        push("weight".into(), None, descriptor.weight.0.to_string());
+        push("style".into(), None, descriptor.style.to_string());
+        push("stretch".into(), None, descriptor.stretch.to_string());
+        push(
+            "simulations".into(),
+            None,
+            simulations_name(descriptor.simulations),
+        );
+        if let Some(path) = &descriptor.file_path {
+            push("file_path".into(), None, path.display().to_string());
+        }
+        if let Some(index) = descriptor.face_index {
+            push("face_index".into(), None, index.to_string());
+        }
+        push(
+            "is_variable_font".into(),
+            None,
+            descriptor.is_variable_font.to_string(),
+        );
+        for axis in &descriptor.axes {
+            push(
+                format!("axis {}", axis.tag),
+                None,
+                format!(
+                    "{} {}..{} (default {})",
+                    pick_name(&axis.names, &args.locale).unwrap_or_default(),
+                    axis.min_value,
+                    axis.max_value,
+                    axis.default_value
+                ),
+            );
+        }
+    }
+    Ok(table.render(args.format))
+}
+
+fn names(args: &Args, factory: &Factory) -> CliResult<String> {
+    args.reject_argument()?;
+    let collection = args.collection(factory)?;
+    let (fonts, report) = collection.get_fonts();
+    warn(&report);
+    let ids = [
+        InformationalStringId::FullName,
+        InformationalStringId::Win32FamilyNames,
+        InformationalStringId::Win32SubfamilyNames,
+        InformationalStringId::TypographicFamilyNames,
+        InformationalStringId::TypographicSubfamilyNames,
+        InformationalStringId::PostscriptName,
+    ];
+    let mut table = Table::new(&[
+        "full name",
+        "win32 family",
+        "win32 subfamily",
+        "typographic family",
+        "typographic subfamily",
+        "postscript name",
+    ]);
+    for font in fonts {
+        if !args.simulations && font.is_simulation() {
+            continue;
+        }
+        table.push(
+            ids.iter()
+                .map(|id| pick_informational_string(&font, *id, &args.locale).into())
+                .collect(),
+        );
+    }
+    Ok(table.render(args.format))
+}
+
+fn tables(args: &Args, factory: &Factory) -> CliResult<String> {
+    let argument = args.require_argument("family|file")?;
+    let mut table = Table::new(&["font", "tag", "checksum", "length"]);
+    for font in args.fonts(factory, argument)? {
+        let label = font_label(&font, &args.locale);
+        let data = font.read_face_data()?;
+        let sfnt = Sfnt::parse(&data)?;
+        for record in &sfnt.tables {
+            table.push(vec![
+                label.as_str().into(),
+                tag_to_string(record.tag).into(),
+                format!("{:08X}", record.checksum).into(),
+                (record.length as i64).into(),
+            ]);
+        }
+    }
+    Ok(table.render(args.format))
+}
+
+fn dump(args: &Args, factory: &Factory) -> CliResult<String> {
+    args.reject_argument()?;
+    let collection = args.collection(factory)?;
+    let mut snapshot = CollectionSnapshot::from_collection(&collection);
+    if !args.simulations {
+        snapshot.remove_simulations();
+    }
+    match args.format {
+        Format::Text => Ok(snapshot.to_text()),
+        Format::Json => Ok(snapshot.to_json()),
+        Format::Tsv => {
+            let mut table = Table::new(&[
+                "family",
+                "face",
+                "weight",
+                "style",
+                "stretch",
+                "simulations",
+                "error",
+            ]);
+            for family in &snapshot.families {
+                let family_name = pick_name(&family.names, &args.locale).or(family.error.clone());
+                for face in &family.faces {
+                    let attributes = face.attributes;
+                    table.push(vec![
+                        family_name.clone().into(),
+                        pick_name(&face.names, &args.locale).into(),
+                        attributes.map(|a| a.weight.0 as i64).into(),
+                        attributes.map(|a| a.style.to_string()).into(),
+                        attributes.map(|a| a.stretch.to_string()).into(),
+                        attributes.map(|a| simulations_name(a.simulations)).into(),
+                        face.error.clone().into(),
+                    ]);
+                }
+            }
+            Ok(table.render(args.format))
+        }
+    }
+}
+
+fn run(args: &Args) -> CliResult<String> {
+    let factory = Factory::new(FactoryType::Shared)?;
+    match args.command.as_str() {
+        "list" => list(args, &factory),
+        "find" => find(args, &factory),
+        "info" => info(args, &factory),
+        "names" => names(args, &factory),
+        "tables" => tables(args, &factory),
+        "dump" => dump(args, &factory),
+        other => Err(CliError::Usage(format!("unknown command: {}", other))),
+    }
+}
+
+fn main() -> ExitCode {
+    let result = Args::parse(std::env::args().skip(1)).and_then(|args| match args {
+        Some(args) => run(&args),
+        None => Ok(USAGE.to_string()),
+    });
+    match result {
+        Ok(output) => {
+            // パイプの先が閉じられた場合などは何もしない．
+            let _ = std::io::stdout().lock().write_all(output.as_bytes());
+            ExitCode::SUCCESS
+        }
+        Err(error) => {
+            eprintln!("error: {}", error);
+            match error {
+                CliError::Usage(_) => ExitCode::from(2),
+                _ => ExitCode::FAILURE,
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(args: &[&str]) -> CliResult<Option<Args>> {
+        Args::parse(args.iter().map(|arg| arg.to_string()))
+    }
+
+    #[test]
+    fn parse_args() {
+        let args = parse(&[
+            "--model=typo",
+            "find",
+            "--path",
+            "a.ttf",
+            "Arial",
+            "--format",
+            "json",
+        ])
+        .unwrap()
+        .unwrap();
+        assert_eq!(args.command, "find");
+        assert_eq!(args.argument.as_deref(), Some("Arial"));
+        assert_eq!(args.paths, vec![PathBuf::from("a.ttf")]);
+        assert_eq!(args.model, FontFamilyModel::Typographic);
+        assert_eq!(args.format, Format::Json);
+        assert!(parse(&["list", "--help"]).unwrap().is_none());
+        for bad in [
+            &["--format", "xml", "list"][..],
+            &["list", "--path"],
+            &["--unknown", "list"],
+            &[],
+            &["find", "a", "b"],
+        ] {
+            assert!(matches!(parse(bad), Err(CliError::Usage(_))), "{:?}", bad);
+        }
+    }
+
+    // JSON は一行に一つの行を書き，空の表は [] にする．
+    #[test]
+    fn table_json() {
+        let mut table = Table::new(&["field", "value"]);
+        assert_eq!(table.to_json(), "[]\n");
+        table.push(vec![
+            InformationalStringId::FullName.name().into(),
+            1.into(),
+        ]);
+        table.push(vec!["a\"b".into(), Value::Null]);
+        assert_eq!(
+            table.to_json(),
+            "[\n  {\"field\": \"FullName\", \"value\": 1},\n  {\"field\": \"a\\\"b\", \"value\": null}\n]\n"
+        );
+    }
+
+    #[test]
+    fn reject_extra_argument() {
+        for command in ["list", "names", "dump"] {
+            let args = parse(&[command, "extra"]).unwrap().unwrap();
+            assert!(matches!(run(&args), Err(CliError::Usage(_))), "{}", command);
+        }
+        let args = parse(&["info"]).unwrap().unwrap();
+        assert!(matches!(run(&args), Err(CliError::Usage(_))));
+    }
+
+    #[test]
+    fn missing_path() {
+        let path = std::env::temp_dir().join("dwrite_rs_cli_missing.ttf");
+        let args = parse(&["list", "--path", &path.to_string_lossy()])
+            .unwrap()
+            .unwrap();
+        match run(&args) {
+            Err(CliError::Font(error)) => assert!(error.is_not_found()),
+            other => panic!("{:?}", other.map(|_| ())),
+        }
+    }
 }
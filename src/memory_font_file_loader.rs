@@ -0,0 +1,166 @@
+use std::{
+    collections::HashMap,
+    os::raw::c_void,
+    sync::{Arc, Mutex, OnceLock, Weak},
+};
+
+use windows::{
+    core::{implement, HRESULT},
+    Win32::Graphics::DirectWrite::{
+        IDWriteFactory, IDWriteFontFile, IDWriteFontFileLoader, IDWriteFontFileLoader_Impl,
+        IDWriteFontFileStream, IDWriteFontFileStream_Impl,
+    },
+};
+
+use crate::{
+    error::{Result, E_INVALIDARG, E_NOTIMPL, E_POINTER},
+    factory::register_font_file_loader,
+};
+
+// メモリ上のフォントデータを DirectWrite に渡すローダー．
+// 参照キーは KEY_PREFIX と登録番号 (u64, リトルエンディアン) から成る．番号は二度使わない．
+
+const KEY_PREFIX: &[u8; 8] = b"dwrs-mem";
+
+// 登録したデータは弱い参照で持つ．データを読めるのは，呼び出し側が FontFileReference を持っている間か，
+// DirectWrite がストリームを開いている間だけ．
+#[derive(Default)]
+struct Registry {
+    next_id: u64,
+    entries: HashMap<u64, Weak<[u8]>>,
+    // データの先頭アドレスから登録番号を引く．
+    ids: HashMap<usize, u64>,
+}
+
+impl Registry {
+    fn register(&mut self, data: &Arc<[u8]>) -> u64 {
+        let address = data.as_ptr() as usize;
+        if let Some(id) = self.ids.get(&address) {
+            let entry = self.entries.get(id).and_then(Weak::upgrade);
+            if entry.is_some_and(|entry| Arc::ptr_eq(&entry, data)) {
+                return *id;
+            }
+        }
+        // 解放されたデータの登録を消す．同じアドレスが別のデータに再利用されることもある．
+        self.entries.retain(|_, entry| entry.strong_count() > 0);
+        let entries = &self.entries;
+        self.ids.retain(|_, id| entries.contains_key(id));
+        let id = self.next_id;
+        self.next_id += 1;
+        self.entries.insert(id, Arc::downgrade(data));
+        self.ids.insert(address, id);
+        id
+    }
+}
+
+fn registry() -> &'static Mutex<Registry> {
+    static REGISTRY: OnceLock<Mutex<Registry>> = OnceLock::new();
+    REGISTRY.get_or_init(Default::default)
+}
+
+// 同じデータは，解放されるまで同じキーになる．
+pub fn register_data(data: &Arc<[u8]>) -> Vec<u8> {
+    let id = registry().lock().unwrap().register(data);
+    let mut key = KEY_PREFIX.to_vec();
+    key.extend_from_slice(&id.to_le_bytes());
+    key
+}
+
+// このローダーのキーでないか，データが解放されていれば None を返す．
+pub fn get_data(key: &[u8]) -> Option<Arc<[u8]>> {
+    let id = key.strip_prefix(KEY_PREFIX)?;
+    let id = u64::from_le_bytes(id.try_into().ok()?);
+    registry().lock().unwrap().entries.get(&id)?.upgrade()
+}
+
+struct Loader(IDWriteFontFileLoader);
+
+// DirectWrite はローダーを任意のスレッドから呼ぶ．MemoryFontFileLoader は状態を持たないので，
+// 一つのローダーをすべてのスレッドで共有する．
+unsafe impl Send for Loader {}
+unsafe impl Sync for Loader {}
+
+// 参照キーがこのローダーのものかどうかは，ローダーが同じオブジェクトかどうかで調べる．
+pub fn get_loader() -> IDWriteFontFileLoader {
+    static LOADER: OnceLock<Loader> = OnceLock::new();
+    LOADER
+        .get_or_init(|| Loader(MemoryFontFileLoader.into()))
+        .0
+        .clone()
+}
+
+pub fn create_font_file(factory: &IDWriteFactory, data: &Arc<[u8]>) -> Result<IDWriteFontFile> {
+    let loader = get_loader();
+    register_font_file_loader(factory, &loader)?;
+    let key = register_data(data);
+    Ok(unsafe {
+        factory.CreateCustomFontFileReference(
+            key.as_ptr() as *const c_void,
+            key.len() as u32,
+            &loader,
+        )
+    }?)
+}
+
+#[implement(IDWriteFontFileLoader)]
+pub struct MemoryFontFileLoader;
+
+impl IDWriteFontFileLoader_Impl for MemoryFontFileLoader {
+    fn CreateStreamFromKey(
+        &self,
+        key: *const c_void,
+        size: u32,
+    ) -> windows::core::Result<IDWriteFontFileStream> {
+        if key.is_null() {
+            return Err(HRESULT(E_INVALIDARG).into());
+        }
+        let key = unsafe { std::slice::from_raw_parts(key as *const u8, size as usize) };
+        match get_data(key) {
+            Some(data) => Ok(MemoryFontFileStream(data).into()),
+            None => Err(HRESULT(E_INVALIDARG).into()),
+        }
+    }
+}
+
+#[implement(IDWriteFontFileStream)]
+pub struct MemoryFontFileStream(Arc<[u8]>);
+
+impl IDWriteFontFileStream_Impl for MemoryFontFileStream {
+    // データ全体がメモリ上にあるので，コピーせずに位置を返す．
+    // SAFETY: シグネチャは windows-rs が生成したもので，unsafe にできない．ポインターは null でないことを確かめてから書く．
+    #[allow(clippy::not_unsafe_ptr_arg_deref)]
+    fn ReadFileFragment(
+        &self,
+        fragment_start: *mut *mut c_void,
+        file_offset: u64,
+        fragment_size: u64,
+        fragment_context: *mut *mut c_void,
+    ) -> windows::core::Result<()> {
+        if fragment_start.is_null() || fragment_context.is_null() {
+            return Err(HRESULT(E_POINTER).into());
+        }
+        unsafe {
+            *fragment_start = std::ptr::null_mut();
+            *fragment_context = std::ptr::null_mut();
+        }
+        match file_offset.checked_add(fragment_size) {
+            Some(end) if end <= self.0.len() as u64 => {
+                let start = self.0[file_offset as usize..].as_ptr();
+                unsafe { *fragment_start = start as *mut c_void };
+                Ok(())
+            }
+            _ => Err(HRESULT(E_INVALIDARG).into()),
+        }
+    }
+
+    fn ReleaseFileFragment(&self, _fragment_context: *mut c_void) {}
+
+    fn GetFileSize(&self) -> windows::core::Result<u64> {
+        Ok(self.0.len() as u64)
+    }
+
+    // 最終更新時刻はない．
+    fn GetLastWriteTime(&self) -> windows::core::Result<u64> {
+        Err(HRESULT(E_NOTIMPL).into())
+    }
+}
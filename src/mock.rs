@@ -0,0 +1,465 @@
+use std::{any::Any, collections::HashMap, sync::Arc};
+
+use crate::{
+    backend::{FactoryBackend, FontBackend, FontCollectionBackend, FontFamilyBackend},
+    error::{Error, Result},
+    factory::Factory,
+    font::{Font, InformationalStringId, Simulations, Stretch, Style, Weight},
+    font_collection::FontCollection,
+    font_family::FontFamily,
+    localized_strings::{LocalizedString, LocalizedStringSet, LocalizedStrings},
+};
+
+// テストのために，Rust のデータからフォントコレクションを組み立てるバックエンド．
+//
+// let collection: FontCollection = MockFontCollection {
+//     families: vec![MockFontFamily {
+//         names: names(&[("en-us", "Arial")]),
+//         fonts: vec![MockFont {
+//             face_names: names(&[("en-us", "Bold")]),
+//             weight: Weight(700),
+//             ..Default::default()
+//         }],
+//         ..Default::default()
+//     }],
+// }
+// .into();
+//
+// error に HRESULT (DWRITE_E_REMOTEFONT など) を入れると，そのファミリーやフォントの取得が
+// DirectWrite と同じエラーで失敗する．
+
+// (locale, string) の組から LocalizedString の列を作る．
+pub fn names(names: &[(&str, &str)]) -> Vec<LocalizedString> {
+    names
+        .iter()
+        .map(|(locale, string)| LocalizedString {
+            string: string.to_string(),
+            locale: locale.to_string(),
+        })
+        .collect()
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct MockFontCollection {
+    pub families: Vec<MockFontFamily>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct MockFontFamily {
+    pub names: Vec<LocalizedString>,
+    pub fonts: Vec<MockFont>,
+    // FontCollection::get_font_family が返すエラーの HRESULT．
+    pub error: Option<i32>,
+}
+
+#[derive(Debug, Clone)]
+pub struct MockFont {
+    pub face_names: Vec<LocalizedString>,
+    pub weight: Weight,
+    pub style: Style,
+    pub stretch: Stretch,
+    pub simulations: Simulations,
+    // None の場合は get_filepath がエラーを返す．
+    pub filepath: Option<String>,
+    pub face_index: u32,
+    pub is_variable_font: bool,
+    pub informational_strings: HashMap<InformationalStringId, Vec<LocalizedString>>,
+    pub tables: HashMap<u32, Vec<u8>>,
+    // FontFamily::get_font が返すエラーの HRESULT．
+    pub error: Option<i32>,
+}
+
+impl Default for MockFont {
+    fn default() -> Self {
+        Self {
+            face_names: names(&[("en-us", "Regular")]),
+            weight: Weight(400),
+            style: Style::Normal,
+            stretch: Stretch::Normal,
+            simulations: Simulations::None,
+            filepath: None,
+            face_index: 0,
+            is_variable_font: false,
+            informational_strings: HashMap::new(),
+            tables: HashMap::new(),
+            error: None,
+        }
+    }
+}
+
+impl MockFont {
+    pub fn with_informational_strings(
+        mut self,
+        informational_string_id: InformationalStringId,
+        strings: Vec<LocalizedString>,
+    ) -> Self {
+        self.informational_strings
+            .insert(informational_string_id.canonical(), strings);
+        self
+    }
+}
+
+impl From<MockFontCollection> for FontCollection {
+    fn from(value: MockFontCollection) -> Self {
+        FontCollection::new(MockCollectionBackend::new(value))
+    }
+}
+
+// get_system_font_collection で同じコレクションを返す Factory．
+pub struct MockFactory(pub MockFontCollection);
+
+impl FactoryBackend for MockFactory {
+    fn get_system_font_collection(&self, _check_for_updates: bool) -> Result<FontCollection> {
+        Ok(self.0.clone().into())
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+impl From<MockFontCollection> for Factory {
+    fn from(value: MockFontCollection) -> Self {
+        Factory::from_backend(MockFactory(value))
+    }
+}
+
+fn mock_error(code: i32) -> Error {
+    Error::from_hresult(code, "mock error".to_string())
+}
+
+struct MockCollectionBackend {
+    families: Vec<Arc<MockFontFamily>>,
+}
+
+impl MockCollectionBackend {
+    fn new(collection: MockFontCollection) -> Self {
+        Self {
+            families: collection.families.into_iter().map(Arc::new).collect(),
+        }
+    }
+}
+
+impl FontCollectionBackend for MockCollectionBackend {
+    fn get_font_family_count(&self) -> u32 {
+        self.families.len() as u32
+    }
+
+    fn get_font_family(&self, index: u32) -> Result<FontFamily> {
+        match self.families.get(index as usize) {
+            Some(family) => {
+                if let Some(code) = family.error {
+                    return Err(mock_error(code));
+                }
+                Ok(FontFamily::new(MockFamilyBackend(family.clone())))
+            }
+            None => Err(Error::IndexOutOfRange {
+                index,
+                count: self.get_font_family_count(),
+            }),
+        }
+    }
+
+    fn find_family_name(&self, family_name: &str) -> Result<(u32, bool)> {
+        let index = self.families.iter().position(|family| {
+            family
+                .names
+                .iter()
+                .any(|name| name.string.eq_ignore_ascii_case(family_name))
+        });
+        match index {
+            Some(index) => Ok((index as u32, true)),
+            None => Ok((u32::MAX, false)),
+        }
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+struct MockFamilyBackend(Arc<MockFontFamily>);
+
+impl FontFamilyBackend for MockFamilyBackend {
+    fn get_family_names(&self) -> Result<LocalizedStrings> {
+        Ok(LocalizedStringSet::from(self.0.names.clone()).into())
+    }
+
+    fn get_font_count(&self) -> u32 {
+        self.0.fonts.len() as u32
+    }
+
+    fn get_font(&self, index: u32) -> Result<Font> {
+        if index >= self.get_font_count() {
+            return Err(Error::IndexOutOfRange {
+                index,
+                count: self.get_font_count(),
+            });
+        }
+        if let Some(code) = self.0.fonts[index as usize].error {
+            return Err(mock_error(code));
+        }
+        Ok(Font::new(MockFontBackend {
+            family: self.0.clone(),
+            index: index as usize,
+        }))
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+struct MockFontBackend {
+    family: Arc<MockFontFamily>,
+    index: usize,
+}
+
+impl MockFontBackend {
+    fn font(&self) -> &MockFont {
+        &self.family.fonts[self.index]
+    }
+}
+
+impl FontBackend for MockFontBackend {
+    fn get_font_family(&self) -> Result<FontFamily> {
+        Ok(FontFamily::new(MockFamilyBackend(self.family.clone())))
+    }
+
+    fn get_simulations(&self) -> Simulations {
+        self.font().simulations
+    }
+
+    fn get_weight(&self) -> Weight {
+        self.font().weight
+    }
+
+    fn get_style(&self) -> Style {
+        self.font().style
+    }
+
+    fn get_stretch(&self) -> Stretch {
+        self.font().stretch
+    }
+
+    fn get_face_names(&self) -> Result<LocalizedStrings> {
+        Ok(LocalizedStringSet::from(self.font().face_names.clone()).into())
+    }
+
+    fn get_informational_strings(
+        &self,
+        informational_string_id: InformationalStringId,
+    ) -> Result<Option<LocalizedStrings>> {
+        let strings = self
+            .font()
+            .informational_strings
+            .iter()
+            .find(|(id, _)| id.canonical() == informational_string_id.canonical())
+            .map(|(_, strings)| strings);
+        match strings {
+            Some(strings) if !strings.is_empty() => {
+                Ok(Some(LocalizedStringSet::from(strings.clone()).into()))
+            }
+            _ => Ok(None),
+        }
+    }
+
+    fn get_filepath(&self) -> Result<String> {
+        match &self.font().filepath {
+            Some(filepath) => Ok(filepath.clone()),
+            None => Err(Error::NotSupported("the font has no file path".to_string())),
+        }
+    }
+
+    fn get_face_index(&self) -> Result<u32> {
+        Ok(self.font().face_index)
+    }
+
+    fn is_variable_font(&self) -> Result<bool> {
+        Ok(self.font().is_variable_font)
+    }
+
+    fn get_font_table(&self, tag: u32) -> Result<Option<Vec<u8>>> {
+        Ok(self.font().tables.get(&tag).cloned())
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        error::{DWRITE_E_FILEFORMAT, DWRITE_E_REMOTEFONT},
+        name::NAME,
+        sfnt::HEAD,
+    };
+
+    fn create_test_collection() -> FontCollection {
+        MockFontCollection {
+            families: vec![
+                MockFontFamily {
+                    names: names(&[
+                        ("en-us", "Arial"),
+                        ("ja-jp", "アリアル"),
+                        ("zh-tw", "亞利爾"),
+                    ]),
+                    fonts: vec![
+                        MockFont::default(),
+                        MockFont {
+                            face_names: names(&[("en-us", "Bold")]),
+                            weight: Weight(700),
+                            filepath: Some("C:\\Windows\\Fonts\\arialbd.ttf".to_string()),
+                            ..Default::default()
+                        },
+                        MockFont {
+                            face_names: names(&[("en-us", "Italic")]),
+                            style: Style::Italic,
+                            error: Some(DWRITE_E_REMOTEFONT),
+                            ..Default::default()
+                        },
+                    ],
+                    ..Default::default()
+                },
+                MockFontFamily {
+                    names: names(&[("en-us", "Broken")]),
+                    error: Some(DWRITE_E_FILEFORMAT),
+                    ..Default::default()
+                },
+                MockFontFamily {
+                    names: names(&[("en-us", "Meiryo")]),
+                    fonts: vec![MockFont {
+                        weight: Weight(700),
+                        simulations: Simulations::Bold,
+                        is_variable_font: true,
+                        ..Default::default()
+                    }
+                    .with_informational_strings(
+                        InformationalStringId::PreferredFamilyNames,
+                        names(&[("en-us", "Meiryo")]),
+                    )],
+                    ..Default::default()
+                },
+            ],
+        }
+        .into()
+    }
+
+    #[test]
+    fn iterate_with_errors() {
+        let collection = create_test_collection();
+        let families = collection.into_iter();
+        assert_eq!(families.len(), 3);
+        let families: Vec<_> = families.collect();
+        assert!(matches!(families[1], Err(Error::InvalidFont(_))));
+        let family = families[0].as_ref().unwrap();
+        let fonts: Vec<_> = family.into_iter().collect();
+        assert_eq!(fonts.len(), 3);
+        assert_eq!(fonts[1].as_ref().unwrap().get_weight(), Weight(700));
+        assert!(fonts[2].as_ref().is_err_and(Error::is_remote_font));
+
+        assert!(matches!(
+            collection.get_font_family(3),
+            Err(Error::IndexOutOfRange { index: 3, count: 3 })
+        ));
+        assert!(matches!(
+            family.get_font(3),
+            Err(Error::IndexOutOfRange { index: 3, count: 3 })
+        ));
+    }
+
+    #[test]
+    fn enumeration_report() {
+        let collection = create_test_collection();
+        let mut weights = vec![];
+        let report = collection.for_each_font(|_, font| {
+            weights.push(font.get_weight());
+            Ok(())
+        });
+        assert_eq!(weights, vec![Weight(400), Weight(700), Weight(700)]);
+        assert_eq!((report.family_count, report.font_count), (2, 3));
+        assert_eq!(report.remote_font_count(), 1);
+        let failures: Vec<_> = report
+            .failures
+            .iter()
+            .map(|failure| {
+                let family_name = failure.family_name.as_deref();
+                (failure.family_index, failure.font_index, family_name)
+            })
+            .collect();
+        assert_eq!(failures, vec![(0, Some(2), Some("Arial")), (1, None, None)]);
+    }
+
+    #[test]
+    fn localized_names() {
+        let collection = create_test_collection();
+        let family = collection.find_family("ARIAL").unwrap();
+        assert!(collection.find_family("アリアル").is_some());
+        assert!(collection.find_family("Helvetica").is_none());
+        let names = family.get_family_names().unwrap();
+        assert_eq!(names.get_count(), 3);
+        assert_eq!(names.find_locale_name("JA-jp").unwrap(), Some(1));
+        assert_eq!(names.get("ko-kr").unwrap(), None);
+        assert!(matches!(
+            names.get_string(3),
+            Err(Error::IndexOutOfRange { index: 3, count: 3 })
+        ));
+        let best = names.best_match(&["zh-Hant-TW", "en-us"]).unwrap().unwrap();
+        assert_eq!(
+            (best.locale.as_str(), best.string.as_str()),
+            ("zh-tw", "亞利爾")
+        );
+        let best = names.best_match(&["fr-fr", "en-gb"]).unwrap().unwrap();
+        assert_eq!(best.string, "Arial");
+        assert!(names.best_match(&["fr-fr"]).unwrap().is_none());
+        let strings = names.into_iter().collect::<Result<Vec<_>>>().unwrap();
+        assert_eq!(strings[1].string, "アリアル");
+    }
+
+    #[test]
+    fn font_properties() {
+        let factory = Factory::from(MockFontCollection {
+            families: vec![MockFontFamily {
+                names: names(&[("en-us", "Arial")]),
+                fonts: vec![MockFont {
+                    filepath: Some("arial.ttf".to_string()),
+                    face_index: 1,
+                    tables: HashMap::from([(HEAD, vec![0, 1])]),
+                    ..Default::default()
+                }],
+                ..Default::default()
+            }],
+        });
+        let collection = factory.get_system_font_collection(false).unwrap();
+        let font = collection.get_font_family(0).unwrap().get_font(0).unwrap();
+        assert_eq!(font.get_filepath().unwrap(), "arial.ttf");
+        assert_eq!(font.get_face_index().unwrap(), 1);
+        assert_eq!(font.get_font_table(HEAD).unwrap(), Some(vec![0, 1]));
+        assert_eq!(font.get_font_table(NAME).unwrap(), None);
+        assert!(!font.is_simulation());
+        let family = font.get_font_family().unwrap();
+        assert_eq!(family.get_font_count(), 1);
+
+        let collection = create_test_collection();
+        let font = collection
+            .find_family("Meiryo")
+            .unwrap()
+            .get_font(0)
+            .unwrap();
+        assert!(font.is_simulation());
+        assert!(font.is_variable_font().unwrap());
+        assert!(matches!(font.get_filepath(), Err(Error::NotSupported(_))));
+        // 別名の ID でも同じ文字列を返す．
+        let strings = font
+            .get_informational_strings(InformationalStringId::TypographicFamilyNames)
+            .unwrap()
+            .unwrap();
+        assert_eq!(strings.get("en-us").unwrap().as_deref(), Some("Meiryo"));
+        assert!(font
+            .get_informational_strings(InformationalStringId::Designer)
+            .unwrap()
+            .is_none());
+    }
+}
@@ -0,0 +1,722 @@
+#[cfg(windows)]
+use windows::Win32::Graphics::DirectWrite::IDWriteFontFace;
+
+#[cfg(windows)]
+use crate::font_face;
+use crate::{
+    error::Result,
+    font::{Font, InformationalStringId},
+    localized_strings::{LocalizedString, LocalizedStringSet, LocalizedStrings},
+    sfnt::{make_tag, Reader, Sfnt},
+    woff,
+};
+
+// OpenType の name テーブルを読み，DirectWrite と同じロケール名で文字列を返す．
+
+pub const NAME: u32 = make_tag(b"name");
+
+pub const PLATFORM_UNICODE: u16 = 0;
+pub const PLATFORM_MACINTOSH: u16 = 1;
+pub const PLATFORM_ISO: u16 = 2;
+pub const PLATFORM_WINDOWS: u16 = 3;
+
+// Windows プラットフォームの言語 ID と，DirectWrite が返すロケール名の対応．
+// 二分探索するので ID の順に並べる．
+const WINDOWS_LANGUAGE_IDS: [(u16, &str); 210] = [
+    (0x0401, "ar-sa"),
+    (0x0402, "bg-bg"),
+    (0x0403, "ca-es"),
+    (0x0404, "zh-tw"),
+    (0x0405, "cs-cz"),
+    (0x0406, "da-dk"),
+    (0x0407, "de-de"),
+    (0x0408, "el-gr"),
+    (0x0409, "en-us"),
+    (0x040A, "es-es_tradnl"),
+    (0x040B, "fi-fi"),
+    (0x040C, "fr-fr"),
+    (0x040D, "he-il"),
+    (0x040E, "hu-hu"),
+    (0x040F, "is-is"),
+    (0x0410, "it-it"),
+    (0x0411, "ja-jp"),
+    (0x0412, "ko-kr"),
+    (0x0413, "nl-nl"),
+    (0x0414, "nb-no"),
+    (0x0415, "pl-pl"),
+    (0x0416, "pt-br"),
+    (0x0417, "rm-ch"),
+    (0x0418, "ro-ro"),
+    (0x0419, "ru-ru"),
+    (0x041A, "hr-hr"),
+    (0x041B, "sk-sk"),
+    (0x041C, "sq-al"),
+    (0x041D, "sv-se"),
+    (0x041E, "th-th"),
+    (0x041F, "tr-tr"),
+    (0x0420, "ur-pk"),
+    (0x0421, "id-id"),
+    (0x0422, "uk-ua"),
+    (0x0423, "be-by"),
+    (0x0424, "sl-si"),
+    (0x0425, "et-ee"),
+    (0x0426, "lv-lv"),
+    (0x0427, "lt-lt"),
+    (0x0428, "tg-cyrl-tj"),
+    (0x0429, "fa-ir"),
+    (0x042A, "vi-vn"),
+    (0x042B, "hy-am"),
+    (0x042C, "az-latn-az"),
+    (0x042D, "eu-es"),
+    (0x042E, "hsb-de"),
+    (0x042F, "mk-mk"),
+    (0x0432, "tn-za"),
+    (0x0434, "xh-za"),
+    (0x0435, "zu-za"),
+    (0x0436, "af-za"),
+    (0x0437, "ka-ge"),
+    (0x0438, "fo-fo"),
+    (0x0439, "hi-in"),
+    (0x043A, "mt-mt"),
+    (0x043B, "se-no"),
+    (0x043E, "ms-my"),
+    (0x043F, "kk-kz"),
+    (0x0440, "ky-kg"),
+    (0x0441, "sw-ke"),
+    (0x0442, "tk-tm"),
+    (0x0443, "uz-latn-uz"),
+    (0x0444, "tt-ru"),
+    (0x0445, "bn-in"),
+    (0x0446, "pa-in"),
+    (0x0447, "gu-in"),
+    (0x0448, "or-in"),
+    (0x0449, "ta-in"),
+    (0x044A, "te-in"),
+    (0x044B, "kn-in"),
+    (0x044C, "ml-in"),
+    (0x044D, "as-in"),
+    (0x044E, "mr-in"),
+    (0x044F, "sa-in"),
+    (0x0450, "mn-mn"),
+    (0x0451, "bo-cn"),
+    (0x0452, "cy-gb"),
+    (0x0453, "km-kh"),
+    (0x0454, "lo-la"),
+    (0x0455, "my-mm"),
+    (0x0456, "gl-es"),
+    (0x0457, "kok-in"),
+    (0x045A, "syr-sy"),
+    (0x045B, "si-lk"),
+    (0x045C, "chr-cher-us"),
+    (0x045D, "iu-cans-ca"),
+    (0x045E, "am-et"),
+    (0x0461, "ne-np"),
+    (0x0462, "fy-nl"),
+    (0x0463, "ps-af"),
+    (0x0464, "fil-ph"),
+    (0x0465, "dv-mv"),
+    (0x0468, "ha-latn-ng"),
+    (0x046A, "yo-ng"),
+    (0x046B, "quz-bo"),
+    (0x046C, "nso-za"),
+    (0x046D, "ba-ru"),
+    (0x046E, "lb-lu"),
+    (0x046F, "kl-gl"),
+    (0x0470, "ig-ng"),
+    (0x0478, "ii-cn"),
+    (0x047A, "arn-cl"),
+    (0x047C, "moh-ca"),
+    (0x047E, "br-fr"),
+    (0x0480, "ug-cn"),
+    (0x0481, "mi-nz"),
+    (0x0482, "oc-fr"),
+    (0x0483, "co-fr"),
+    (0x0484, "gsw-fr"),
+    (0x0485, "sah-ru"),
+    (0x0486, "qut-gt"),
+    (0x0487, "rw-rw"),
+    (0x0488, "wo-sn"),
+    (0x048C, "prs-af"),
+    (0x0491, "gd-gb"),
+    (0x0492, "ku-arab-iq"),
+    (0x0801, "ar-iq"),
+    (0x0804, "zh-cn"),
+    (0x0807, "de-ch"),
+    (0x0809, "en-gb"),
+    (0x080A, "es-mx"),
+    (0x080C, "fr-be"),
+    (0x0810, "it-ch"),
+    (0x0813, "nl-be"),
+    (0x0814, "nn-no"),
+    (0x0816, "pt-pt"),
+    (0x081A, "sr-latn-cs"),
+    (0x081D, "sv-fi"),
+    (0x082C, "az-cyrl-az"),
+    (0x082E, "dsb-de"),
+    (0x083B, "se-se"),
+    (0x083C, "ga-ie"),
+    (0x083E, "ms-bn"),
+    (0x0843, "uz-cyrl-uz"),
+    (0x0845, "bn-bd"),
+    (0x0850, "mn-mong-cn"),
+    (0x085D, "iu-latn-ca"),
+    (0x085F, "tzm-latn-dz"),
+    (0x086B, "quz-ec"),
+    (0x0C01, "ar-eg"),
+    (0x0C04, "zh-hk"),
+    (0x0C07, "de-at"),
+    (0x0C09, "en-au"),
+    (0x0C0A, "es-es"),
+    (0x0C0C, "fr-ca"),
+    (0x0C1A, "sr-cyrl-cs"),
+    (0x0C3B, "se-fi"),
+    (0x0C6B, "quz-pe"),
+    (0x1001, "ar-ly"),
+    (0x1004, "zh-sg"),
+    (0x1007, "de-lu"),
+    (0x1009, "en-ca"),
+    (0x100A, "es-gt"),
+    (0x100C, "fr-ch"),
+    (0x101A, "hr-ba"),
+    (0x103B, "smj-no"),
+    (0x1401, "ar-dz"),
+    (0x1404, "zh-mo"),
+    (0x1407, "de-li"),
+    (0x1409, "en-nz"),
+    (0x140A, "es-cr"),
+    (0x140C, "fr-lu"),
+    (0x141A, "bs-latn-ba"),
+    (0x143B, "smj-se"),
+    (0x1801, "ar-ma"),
+    (0x1809, "en-ie"),
+    (0x180A, "es-pa"),
+    (0x180C, "fr-mc"),
+    (0x181A, "sr-latn-ba"),
+    (0x183B, "sma-no"),
+    (0x1C01, "ar-tn"),
+    (0x1C09, "en-za"),
+    (0x1C0A, "es-do"),
+    (0x1C1A, "sr-cyrl-ba"),
+    (0x1C3B, "sma-se"),
+    (0x2001, "ar-om"),
+    (0x2009, "en-jm"),
+    (0x200A, "es-ve"),
+    (0x201A, "bs-cyrl-ba"),
+    (0x203B, "sms-fi"),
+    (0x2401, "ar-ye"),
+    (0x2409, "en-029"),
+    (0x240A, "es-co"),
+    (0x243B, "smn-fi"),
+    (0x2801, "ar-sy"),
+    (0x2809, "en-bz"),
+    (0x280A, "es-pe"),
+    (0x2C01, "ar-jo"),
+    (0x2C09, "en-tt"),
+    (0x2C0A, "es-ar"),
+    (0x3001, "ar-lb"),
+    (0x3009, "en-zw"),
+    (0x300A, "es-ec"),
+    (0x3401, "ar-kw"),
+    (0x3409, "en-ph"),
+    (0x340A, "es-cl"),
+    (0x3801, "ar-ae"),
+    (0x380A, "es-uy"),
+    (0x3C01, "ar-bh"),
+    (0x3C0A, "es-py"),
+    (0x4001, "ar-qa"),
+    (0x4009, "en-in"),
+    (0x400A, "es-bo"),
+    (0x4409, "en-my"),
+    (0x440A, "es-sv"),
+    (0x4809, "en-sg"),
+    (0x480A, "es-hn"),
+    (0x4C0A, "es-ni"),
+    (0x500A, "es-pr"),
+    (0x540A, "es-us"),
+];
+
+// Macintosh プラットフォームの言語 ID (0..=94, 128..=150) に対応するロケール名．
+const MAC_LANGUAGE_IDS: [&str; 95] = [
+    "en-us",
+    "fr-fr",
+    "de-de",
+    "it-it",
+    "nl-nl",
+    "sv-se",
+    "es-es",
+    "da-dk",
+    "pt-br",
+    "nb-no",
+    "he-il",
+    "ja-jp",
+    "ar-sa",
+    "fi-fi",
+    "el-gr",
+    "is-is",
+    "mt-mt",
+    "tr-tr",
+    "hr-hr",
+    "zh-tw",
+    "ur-pk",
+    "hi-in",
+    "th-th",
+    "ko-kr",
+    "lt-lt",
+    "pl-pl",
+    "hu-hu",
+    "et-ee",
+    "lv-lv",
+    "se-no",
+    "fo-fo",
+    "fa-ir",
+    "ru-ru",
+    "zh-cn",
+    "nl-be",
+    "ga-ie",
+    "sq-al",
+    "ro-ro",
+    "cs-cz",
+    "sk-sk",
+    "sl-si",
+    "yi",
+    "sr-cyrl-cs",
+    "mk-mk",
+    "bg-bg",
+    "uk-ua",
+    "be-by",
+    "uz-cyrl-uz",
+    "kk-kz",
+    "az-cyrl-az",
+    "az-arab",
+    "hy-am",
+    "ka-ge",
+    "ro-md",
+    "ky-kg",
+    "tg-cyrl-tj",
+    "tk-tm",
+    "mn-mong-cn",
+    "mn-mn",
+    "ps-af",
+    "ku",
+    "ks",
+    "sd",
+    "bo-cn",
+    "ne-np",
+    "sa-in",
+    "mr-in",
+    "bn-in",
+    "as-in",
+    "gu-in",
+    "pa-in",
+    "or-in",
+    "ml-in",
+    "kn-in",
+    "ta-in",
+    "te-in",
+    "si-lk",
+    "my-mm",
+    "km-kh",
+    "lo-la",
+    "vi-vn",
+    "id-id",
+    "fil-ph",
+    "ms-my",
+    "ms-arab",
+    "am-et",
+    "ti-et",
+    "om-et",
+    "so-so",
+    "sw-ke",
+    "rw-rw",
+    "rn",
+    "ny",
+    "mg",
+    "eo",
+];
+
+const MAC_LANGUAGE_IDS_128: [&str; 23] = [
+    "cy-gb",
+    "eu-es",
+    "ca-es",
+    "la",
+    "qu",
+    "gn",
+    "ay",
+    "tt-ru",
+    "ug-cn",
+    "dz",
+    "jv",
+    "su",
+    "gl-es",
+    "af-za",
+    "br-fr",
+    "iu-cans-ca",
+    "gd-gb",
+    "gv",
+    "ga-ie",
+    "to",
+    "el-gr",
+    "kl-gl",
+    "az-latn-az",
+];
+
+// Mac Roman の 0x80..=0xFF．
+const MAC_ROMAN: [char; 128] = [
+    '\u{00C4}', '\u{00C5}', '\u{00C7}', '\u{00C9}', '\u{00D1}', '\u{00D6}', '\u{00DC}', '\u{00E1}',
+    '\u{00E0}', '\u{00E2}', '\u{00E4}', '\u{00E3}', '\u{00E5}', '\u{00E7}', '\u{00E9}', '\u{00E8}',
+    '\u{00EA}', '\u{00EB}', '\u{00ED}', '\u{00EC}', '\u{00EE}', '\u{00EF}', '\u{00F1}', '\u{00F3}',
+    '\u{00F2}', '\u{00F4}', '\u{00F6}', '\u{00F5}', '\u{00FA}', '\u{00F9}', '\u{00FB}', '\u{00FC}',
+    '\u{2020}', '\u{00B0}', '\u{00A2}', '\u{00A3}', '\u{00A7}', '\u{2022}', '\u{00B6}', '\u{00DF}',
+    '\u{00AE}', '\u{00A9}', '\u{2122}', '\u{00B4}', '\u{00A8}', '\u{2260}', '\u{00C6}', '\u{00D8}',
+    '\u{221E}', '\u{00B1}', '\u{2264}', '\u{2265}', '\u{00A5}', '\u{00B5}', '\u{2202}', '\u{2211}',
+    '\u{220F}', '\u{03C0}', '\u{222B}', '\u{00AA}', '\u{00BA}', '\u{03A9}', '\u{00E6}', '\u{00F8}',
+    '\u{00BF}', '\u{00A1}', '\u{00AC}', '\u{221A}', '\u{0192}', '\u{2248}', '\u{2206}', '\u{00AB}',
+    '\u{00BB}', '\u{2026}', '\u{00A0}', '\u{00C0}', '\u{00C3}', '\u{00D5}', '\u{0152}', '\u{0153}',
+    '\u{2013}', '\u{2014}', '\u{201C}', '\u{201D}', '\u{2018}', '\u{2019}', '\u{00F7}', '\u{25CA}',
+    '\u{00FF}', '\u{0178}', '\u{2044}', '\u{20AC}', '\u{2039}', '\u{203A}', '\u{FB01}', '\u{FB02}',
+    '\u{2021}', '\u{00B7}', '\u{201A}', '\u{201E}', '\u{2030}', '\u{00C2}', '\u{00CA}', '\u{00C1}',
+    '\u{00CB}', '\u{00C8}', '\u{00CD}', '\u{00CE}', '\u{00CF}', '\u{00CC}', '\u{00D3}', '\u{00D4}',
+    '\u{F8FF}', '\u{00D2}', '\u{00DA}', '\u{00DB}', '\u{00D9}', '\u{0131}', '\u{02C6}', '\u{02DC}',
+    '\u{00AF}', '\u{02D8}', '\u{02D9}', '\u{02DA}', '\u{00B8}', '\u{02DD}', '\u{02DB}', '\u{02C7}',
+];
+
+pub fn get_windows_locale_name(language_id: u16) -> Option<&'static str> {
+    WINDOWS_LANGUAGE_IDS
+        .binary_search_by_key(&language_id, |(id, _)| *id)
+        .ok()
+        .map(|index| WINDOWS_LANGUAGE_IDS[index].1)
+}
+
+pub fn get_mac_locale_name(language_id: u16) -> Option<&'static str> {
+    match language_id {
+        0..=94 => Some(MAC_LANGUAGE_IDS[language_id as usize]),
+        128..=150 => Some(MAC_LANGUAGE_IDS_128[language_id as usize - 128]),
+        _ => None,
+    }
+}
+
+// ロケール名から Windows の言語 ID を引く．大文字と小文字は区別しない．
+pub fn get_windows_language_id(locale: &str) -> Option<u16> {
+    WINDOWS_LANGUAGE_IDS
+        .iter()
+        .find(|(_, name)| name.eq_ignore_ascii_case(locale))
+        .map(|(id, _)| *id)
+}
+
+fn decode_utf16_be(bytes: &[u8]) -> String {
+    let units: Vec<u16> = bytes
+        .chunks_exact(2)
+        .map(|unit| u16::from_be_bytes([unit[0], unit[1]]))
+        .collect();
+    String::from_utf16_lossy(&units)
+}
+
+pub(crate) fn mac_roman_to_char(c: u8) -> char {
+    match c {
+        0..=0x7F => c as char,
+        _ => MAC_ROMAN[c as usize - 0x80],
+    }
+}
+
+pub(crate) fn char_to_mac_roman(c: char) -> Option<u8> {
+    if c.is_ascii() {
+        return Some(c as u8);
+    }
+    MAC_ROMAN
+        .iter()
+        .position(|&m| m == c)
+        .map(|index| (index + 0x80) as u8)
+}
+
+fn decode_mac_roman(bytes: &[u8]) -> String {
+    bytes.iter().map(|&c| mac_roman_to_char(c)).collect()
+}
+
+// デコードできないエンコーディング (Windows の Shift_JIS など，Mac の Roman 以外) は None．
+fn decode(platform_id: u16, encoding_id: u16, bytes: &[u8]) -> Option<String> {
+    match (platform_id, encoding_id) {
+        (PLATFORM_UNICODE, _) => Some(decode_utf16_be(bytes)),
+        (PLATFORM_MACINTOSH, 0) => Some(decode_mac_roman(bytes)),
+        (PLATFORM_ISO, 0) => Some(bytes.iter().map(|&c| (c & 0x7F) as char).collect()),
+        (PLATFORM_ISO, 1) => Some(decode_utf16_be(bytes)),
+        (PLATFORM_ISO, 2) => Some(bytes.iter().map(|&c| c as char).collect()),
+        (PLATFORM_WINDOWS, 0 | 1 | 10) => Some(decode_utf16_be(bytes)),
+        _ => None,
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct NameRecord {
+    pub platform_id: u16,
+    pub encoding_id: u16,
+    pub language_id: u16,
+    pub name_id: u16,
+    // 言語 ID に対応するロケール名．format 1 の言語タグも小文字にして入れる．
+    pub locale: Option<String>,
+    pub string: Option<String>,
+}
+
+impl NameRecord {
+    pub fn to_localized_string(&self) -> Option<LocalizedString> {
+        Some(LocalizedString {
+            string: self.string.clone()?,
+            locale: self.locale.clone()?,
+        })
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct NameTable {
+    pub format: u16,
+    pub records: Vec<NameRecord>,
+    // format 1 の言語タグ．言語 ID 0x8000 以降に対応する．
+    pub lang_tags: Vec<String>,
+}
+
+impl NameTable {
+    pub fn parse(data: &[u8]) -> Result<Self> {
+        let mut reader = Reader::table(NAME, data);
+        let format = reader.read_u16()?;
+        if format > 1 {
+            return Err(reader.error(format!("unknown format {}", format)));
+        }
+        let count = reader.read_u16()?;
+        let storage_offset = reader.read_u16()? as usize;
+        let mut headers = Vec::with_capacity(count as usize);
+        for _ in 0..count {
+            headers.push([
+                reader.read_u16()?,
+                reader.read_u16()?,
+                reader.read_u16()?,
+                reader.read_u16()?,
+                reader.read_u16()?,
+                reader.read_u16()?,
+            ]);
+        }
+
+        let mut lang_tags = vec![];
+        if format == 1 {
+            let lang_tag_count = reader.read_u16()?;
+            for _ in 0..lang_tag_count {
+                let length = reader.read_u16()? as usize;
+                let offset = reader.read_u16()? as usize;
+                let bytes = reader.jump(storage_offset + offset)?.read_bytes(length)?;
+                lang_tags.push(decode_utf16_be(bytes).to_ascii_lowercase());
+            }
+        }
+
+        // 文字列がテーブルの外にあるレコードは読み飛ばす．
+        let mut records = Vec::with_capacity(headers.len());
+        for [platform_id, encoding_id, language_id, name_id, length, offset] in headers {
+            let bytes = reader
+                .jump(storage_offset + offset as usize)
+                .and_then(|mut reader| reader.read_bytes(length as usize));
+            let Ok(bytes) = bytes else {
+                continue;
+            };
+            let locale = match (platform_id, language_id) {
+                (_, 0x8000..) => lang_tags.get(language_id as usize - 0x8000).cloned(),
+                (PLATFORM_WINDOWS, _) => get_windows_locale_name(language_id).map(str::to_string),
+                (PLATFORM_MACINTOSH, _) => get_mac_locale_name(language_id).map(str::to_string),
+                _ => None,
+            };
+            records.push(NameRecord {
+                platform_id,
+                encoding_id,
+                language_id,
+                name_id,
+                locale,
+                string: decode(platform_id, encoding_id, bytes),
+            });
+        }
+        Ok(Self {
+            format,
+            records,
+            lang_tags,
+        })
+    }
+
+    // フォントファイルのデータから読む．name テーブルが無い場合は None．
+    pub fn from_font_data(data: &[u8]) -> Result<Option<Self>> {
+        let data = woff::decode_font_data(data)?;
+        match Sfnt::parse(&data)?.get_font_table(NAME)? {
+            Some(table) => Ok(Some(Self::parse(table)?)),
+            None => Ok(None),
+        }
+    }
+
+    pub fn from_font(font: &Font) -> Result<Option<Self>> {
+        match font.get_font_table(NAME)? {
+            Some(table) => Ok(Some(Self::parse(&table)?)),
+            None => Ok(None),
+        }
+    }
+
+    #[cfg(windows)]
+    pub fn from_font_face(face: &IDWriteFontFace) -> Result<Option<Self>> {
+        let table = font_face::get_font_table(face, NAME)?;
+        if table.is_empty() {
+            Ok(None)
+        } else {
+            Ok(Some(Self::parse(&table)?))
+        }
+    }
+
+    // DirectWrite と同じく Windows プラットフォームの文字列を優先し，
+    // 無い場合だけ他のプラットフォームの文字列を使う．同じロケールは最初のものだけ残す．
+    pub fn get_names(&self, name_id: u16) -> Vec<LocalizedString> {
+        let records = || {
+            self.records
+                .iter()
+                .filter(move |record| record.name_id == name_id)
+        };
+        let has_windows = records().any(|record| {
+            record.platform_id == PLATFORM_WINDOWS && record.to_localized_string().is_some()
+        });
+        let mut names: Vec<LocalizedString> = vec![];
+        for record in records() {
+            if has_windows && record.platform_id != PLATFORM_WINDOWS {
+                continue;
+            }
+            let Some(name) = record.to_localized_string() else {
+                continue;
+            };
+            if !names
+                .iter()
+                .any(|other| other.locale.eq_ignore_ascii_case(&name.locale))
+            {
+                names.push(name);
+            }
+        }
+        names
+    }
+
+    pub fn get_strings(&self, name_id: u16) -> Option<LocalizedStrings> {
+        let names = self.get_names(name_id);
+        if names.is_empty() {
+            None
+        } else {
+            Some(LocalizedStringSet::from(names).into())
+        }
+    }
+
+    pub fn get_informational_strings(
+        &self,
+        informational_string_id: InformationalStringId,
+    ) -> Option<LocalizedStrings> {
+        self.get_strings(get_name_id(informational_string_id)?)
+    }
+}
+
+pub fn get_name_id(informational_string_id: InformationalStringId) -> Option<u16> {
+    match informational_string_id {
+        InformationalStringId::CopyrightNotice => Some(0),
+        InformationalStringId::VersionStrings => Some(5),
+        InformationalStringId::Trademark => Some(7),
+        InformationalStringId::Manufacturer => Some(8),
+        InformationalStringId::Designer => Some(9),
+        InformationalStringId::DesignerUrl => Some(12),
+        InformationalStringId::Description => Some(10),
+        InformationalStringId::FontVendorUrl => Some(11),
+        InformationalStringId::LicenseDescription => Some(13),
+        InformationalStringId::LicenseInfoUrl => Some(14),
+        InformationalStringId::Win32FamilyNames => Some(1),
+        InformationalStringId::Win32SubfamilyNames => Some(2),
+        InformationalStringId::TypographicFamilyNames
+        | InformationalStringId::PreferredFamilyNames => Some(16),
+        InformationalStringId::TypographicSubfamilyNames
+        | InformationalStringId::PreferredSubfamilyNames => Some(17),
+        InformationalStringId::SampleText => Some(19),
+        InformationalStringId::FullName => Some(4),
+        InformationalStringId::PostscriptName => Some(6),
+        InformationalStringId::PostscriptCidName => Some(20),
+        InformationalStringId::WeightStretchStyleFamilyName
+        | InformationalStringId::WwsFamilyName => Some(21),
+        InformationalStringId::None
+        | InformationalStringId::DesignScriptLanguageTag
+        | InformationalStringId::SupportedScriptLanguageTag => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_fonts::{build_font, FS_SELECTION_REGULAR};
+
+    fn write_u16s(out: &mut Vec<u8>, values: &[u16]) {
+        for value in values {
+            out.extend_from_slice(&value.to_be_bytes());
+        }
+    }
+
+    #[test]
+    fn locale_names() {
+        assert!(WINDOWS_LANGUAGE_IDS
+            .windows(2)
+            .all(|ids| ids[0].0 < ids[1].0));
+        assert_eq!(get_windows_locale_name(0x040A), Some("es-es_tradnl"));
+        assert_eq!(get_windows_locale_name(0x0C0A), Some("es-es"));
+        assert_eq!(get_windows_locale_name(0x0429), Some("fa-ir"));
+        assert_eq!(get_windows_locale_name(0x0492), Some("ku-arab-iq"));
+        assert_eq!(get_windows_locale_name(0x0001), None);
+        assert_eq!(get_windows_language_id("ZH-TW"), Some(0x0404));
+        assert_eq!(get_windows_language_id("chr-Cher-US"), Some(0x045C));
+        assert_eq!(get_mac_locale_name(0), Some("en-us"));
+        assert_eq!(get_mac_locale_name(95), None);
+    }
+
+    #[test]
+    fn parse_font() {
+        let font = build_font("Test Sans", "Regular", 400, FS_SELECTION_REGULAR);
+        let names = NameTable::from_font_data(&font).unwrap().unwrap();
+        assert_eq!(names.format, 0);
+        let family_names = names.get_names(1);
+        assert_eq!(family_names.len(), 2);
+        assert_eq!(family_names[0].string, "Test Sans");
+        assert_eq!(family_names[0].locale, "en-us");
+        assert_eq!(family_names[1].string, "テスト");
+        assert_eq!(family_names[1].locale, "ja-jp");
+        assert_eq!(names.get_names(4)[0].string, "Test Sans Regular");
+        assert!(names.get_strings(3).is_none());
+    }
+
+    // Mac Roman のレコードと，format 1 の言語タグを使う Windows のレコード．
+    #[test]
+    fn language_tags() {
+        let mut data = vec![];
+        write_u16s(&mut data, &[1, 2, 6 + 2 * 12 + 2 + 4]);
+        write_u16s(&mut data, &[1, 0, 0, 1, 4, 0]);
+        write_u16s(&mut data, &[3, 1, 0x8000, 1, 4, 4]);
+        write_u16s(&mut data, &[1, 10, 8]);
+        data.extend_from_slice(b"Caf\x8e");
+        data.extend_from_slice(&[0, b'A', 0, b'b']);
+        data.extend_from_slice(&[0, b'd', 0, b'e', 0, b'-', 0, b'A', 0, b'T']);
+        let names = NameTable::parse(&data).unwrap();
+        assert_eq!(names.records[0].string.as_deref(), Some("Café"));
+        assert_eq!(names.records[0].locale.as_deref(), Some("en-us"));
+        assert_eq!(names.lang_tags, vec!["de-at".to_string()]);
+        // Windows の文字列があれば他のプラットフォームの文字列は使わない．
+        let strings = names.get_strings(1).unwrap();
+        assert_eq!(strings.get_count(), 1);
+        assert_eq!(strings.get("DE-at").unwrap().as_deref(), Some("Ab"));
+    }
+
+    // 文字列がテーブルの外にあるレコードだけを読み飛ばす．
+    #[test]
+    fn skip_out_of_range_records() {
+        let mut data = vec![];
+        write_u16s(&mut data, &[0, 3, 6 + 3 * 12]);
+        write_u16s(&mut data, &[3, 1, 0x0409, 1, 4, 0]);
+        write_u16s(&mut data, &[3, 1, 0x0409, 2, 4, 2]);
+        write_u16s(&mut data, &[3, 1, 0x0409, 4, 2, 0xFFFF]);
+        data.extend_from_slice(&[0, b'A', 0, b'b']);
+        let names = NameTable::parse(&data).unwrap();
+        assert_eq!(names.records.len(), 1);
+        assert_eq!(names.get_names(1)[0].string, "Ab");
+        assert!(names.get_names(2).is_empty());
+
+        assert!(NameTable::parse(&[0, 2, 0, 0, 0, 0]).is_err());
+        // レコードの並び自体が途中で切れている場合はエラーにする．
+        assert!(NameTable::parse(&data[..20]).is_err());
+    }
+}
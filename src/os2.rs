@@ -0,0 +1,326 @@
+use bitflags::bitflags;
+
+use crate::{
+    error::Result,
+    font::{Font, Simulations, Stretch, Style, Weight},
+    sfnt::{make_tag, Reader, Sfnt},
+};
+
+pub use crate::sfnt::HEAD;
+
+// OpenType の OS/2 テーブル (バージョン 0..=5．それより新しいバージョンは 5 として読む) と，DirectWrite と同じ Weight/Style/Stretch への変換．
+
+pub const OS2: u32 = make_tag(b"OS/2");
+
+bitflags! {
+    #[repr(transparent)]
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+    pub struct FsSelection: u16 {
+        const Italic = 1 << 0;
+        const Underscore = 1 << 1;
+        const Negative = 1 << 2;
+        const Outlined = 1 << 3;
+        const Strikeout = 1 << 4;
+        const Bold = 1 << 5;
+        const Regular = 1 << 6;
+        // バージョン 4 以降．
+        const UseTypoMetrics = 1 << 7;
+        const Wws = 1 << 8;
+        const Oblique = 1 << 9;
+    }
+}
+
+// head テーブルの macStyle．
+pub const MAC_STYLE_BOLD: u16 = 1 << 0;
+pub const MAC_STYLE_ITALIC: u16 = 1 << 1;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct ScriptMetrics {
+    pub x_size: i16,
+    pub y_size: i16,
+    pub x_offset: i16,
+    pub y_offset: i16,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Os2 {
+    pub version: u16,
+    pub x_avg_char_width: i16,
+    pub weight_class: u16,
+    pub width_class: u16,
+    pub fs_type: u16,
+    pub subscript: ScriptMetrics,
+    pub superscript: ScriptMetrics,
+    pub strikeout_size: i16,
+    pub strikeout_position: i16,
+    pub family_class: i16,
+    pub panose: [u8; 10],
+    pub unicode_range: [u32; 4],
+    pub vendor_id: u32,
+    pub fs_selection: FsSelection,
+    pub first_char_index: u16,
+    pub last_char_index: u16,
+    // 古い Apple のフォントのバージョン 0 はここから先が無いことがあり，その場合は 0 になる．
+    // バージョンに対してテーブルが短い場合も，無いフィールドは 0 か None にする．
+    pub typo_ascender: i16,
+    pub typo_descender: i16,
+    pub typo_line_gap: i16,
+    pub win_ascent: u16,
+    pub win_descent: u16,
+    // バージョン 1 以降．
+    pub code_page_range: Option<[u32; 2]>,
+    // バージョン 2 以降．
+    pub x_height: Option<i16>,
+    pub cap_height: Option<i16>,
+    pub default_char: Option<u16>,
+    pub break_char: Option<u16>,
+    pub max_context: Option<u16>,
+    // バージョン 5 以降．単位は 1/20 ポイント．
+    pub lower_optical_point_size: Option<u16>,
+    pub upper_optical_point_size: Option<u16>,
+}
+
+fn read_script_metrics(reader: &mut Reader) -> Result<ScriptMetrics> {
+    Ok(ScriptMetrics {
+        x_size: reader.read_i16()?,
+        y_size: reader.read_i16()?,
+        x_offset: reader.read_i16()?,
+        y_offset: reader.read_i16()?,
+    })
+}
+
+impl Os2 {
+    pub fn parse(data: &[u8]) -> Result<Self> {
+        let mut reader = Reader::table(OS2, data);
+        let version = reader.read_u16()?;
+        let x_avg_char_width = reader.read_i16()?;
+        let weight_class = reader.read_u16()?;
+        let width_class = reader.read_u16()?;
+        let fs_type = reader.read_u16()?;
+        let subscript = read_script_metrics(&mut reader)?;
+        let superscript = read_script_metrics(&mut reader)?;
+        let strikeout_size = reader.read_i16()?;
+        let strikeout_position = reader.read_i16()?;
+        let family_class = reader.read_i16()?;
+        let panose = reader.read_array()?;
+        let unicode_range = [
+            reader.read_u32()?,
+            reader.read_u32()?,
+            reader.read_u32()?,
+            reader.read_u32()?,
+        ];
+        let vendor_id = reader.read_tag()?;
+        let fs_selection = FsSelection::from_bits_retain(reader.read_u16()?);
+        let first_char_index = reader.read_u16()?;
+        let last_char_index = reader.read_u16()?;
+
+        let mut os2 = Self {
+            version,
+            x_avg_char_width,
+            weight_class,
+            width_class,
+            fs_type,
+            subscript,
+            superscript,
+            strikeout_size,
+            strikeout_position,
+            family_class,
+            panose,
+            unicode_range,
+            vendor_id,
+            fs_selection,
+            first_char_index,
+            last_char_index,
+            typo_ascender: 0,
+            typo_descender: 0,
+            typo_line_gap: 0,
+            win_ascent: 0,
+            win_descent: 0,
+            code_page_range: None,
+            x_height: None,
+            cap_height: None,
+            default_char: None,
+            break_char: None,
+            max_context: None,
+            lower_optical_point_size: None,
+            upper_optical_point_size: None,
+        };
+        if reader.remaining() < 10 {
+            return Ok(os2);
+        }
+        os2.typo_ascender = reader.read_i16()?;
+        os2.typo_descender = reader.read_i16()?;
+        os2.typo_line_gap = reader.read_i16()?;
+        os2.win_ascent = reader.read_u16()?;
+        os2.win_descent = reader.read_u16()?;
+        if version < 1 || reader.remaining() < 8 {
+            return Ok(os2);
+        }
+        os2.code_page_range = Some([reader.read_u32()?, reader.read_u32()?]);
+        if version < 2 || reader.remaining() < 10 {
+            return Ok(os2);
+        }
+        os2.x_height = Some(reader.read_i16()?);
+        os2.cap_height = Some(reader.read_i16()?);
+        os2.default_char = Some(reader.read_u16()?);
+        os2.break_char = Some(reader.read_u16()?);
+        os2.max_context = Some(reader.read_u16()?);
+        if version < 5 || reader.remaining() < 4 {
+            return Ok(os2);
+        }
+        os2.lower_optical_point_size = Some(reader.read_u16()?);
+        os2.upper_optical_point_size = Some(reader.read_u16()?);
+        Ok(os2)
+    }
+
+    pub fn from_font(font: &Font) -> Result<Option<Self>> {
+        match font.get_font_table(OS2)? {
+            Some(table) => Ok(Some(Self::parse(&table)?)),
+            None => Ok(None),
+        }
+    }
+
+    pub fn get_weight(&self) -> Weight {
+        Weight::from_weight_class(self.weight_class)
+    }
+
+    // OBLIQUE はバージョン 4 で追加されたビットなので，それより前のバージョンでは見ない．
+    pub fn get_style(&self) -> Style {
+        if self.version >= 4 && self.fs_selection.contains(FsSelection::Oblique) {
+            Style::Oblique
+        } else if self.fs_selection.contains(FsSelection::Italic) {
+            Style::Italic
+        } else {
+            Style::Normal
+        }
+    }
+
+    pub fn get_stretch(&self) -> Stretch {
+        Stretch::from_width_class(self.width_class)
+    }
+
+    pub fn get_simulations(&self, weight: Weight, style: Style) -> Simulations {
+        get_simulations(self.get_weight(), self.get_style(), weight, style)
+    }
+}
+
+// font_weight, font_style のフォントで weight と style を表示するときに DirectWrite が行うシミュレーション．
+// 太さが 600 以上で，フォント自体が 500 以下なら太字にする．
+// 斜体でないフォントで斜体を求められたら斜めにする．
+pub fn get_simulations(
+    font_weight: Weight,
+    font_style: Style,
+    weight: Weight,
+    style: Style,
+) -> Simulations {
+    let mut simulations = Simulations::None;
+    if weight.0 >= 600 && font_weight.0 <= 500 {
+        simulations |= Simulations::Bold;
+    }
+    if style != Style::Normal && font_style == Style::Normal {
+        simulations |= Simulations::Oblique;
+    }
+    simulations
+}
+
+pub fn read_mac_style(head: &[u8]) -> Result<u16> {
+    Reader::table(HEAD, head).jump(44)?.read_u16()
+}
+
+// OS/2 テーブルが無い場合は head の macStyle を使い，それも無ければ Regular とする．
+pub fn read_attributes(sfnt: &Sfnt) -> Result<(Weight, Style, Stretch)> {
+    if let Some(os2) = sfnt.get_font_table(OS2)? {
+        let os2 = Os2::parse(os2)?;
+        return Ok((os2.get_weight(), os2.get_style(), os2.get_stretch()));
+    }
+    let mac_style = match sfnt.get_font_table(HEAD)? {
+        Some(head) => read_mac_style(head)?,
+        None => 0,
+    };
+    let weight = if mac_style & MAC_STYLE_BOLD != 0 {
+        Weight(700)
+    } else {
+        Weight(400)
+    };
+    let style = if mac_style & MAC_STYLE_ITALIC != 0 {
+        Style::Italic
+    } else {
+        Style::Normal
+    };
+    Ok((weight, style, Stretch::Normal))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_fonts::{self, FS_SELECTION_BOLD, FS_SELECTION_ITALIC};
+
+    fn with_version(data: &[u8], version: u16) -> Vec<u8> {
+        let mut data = data.to_vec();
+        data[..2].copy_from_slice(&version.to_be_bytes());
+        data
+    }
+
+    #[test]
+    fn parse_versions() {
+        let data = test_fonts::os2(700, 3, FS_SELECTION_BOLD);
+        let os2 = Os2::parse(&data).unwrap();
+        assert_eq!(os2.version, 4);
+        assert_eq!(os2.vendor_id, make_tag(b"TEST"));
+        assert_eq!(os2.typo_ascender, 800);
+        assert_eq!(os2.typo_descender, -200);
+        assert_eq!(os2.code_page_range, Some([0x0002_0001, 0]));
+        assert_eq!(os2.x_height, Some(500));
+        assert_eq!(os2.max_context, Some(2));
+        assert_eq!(os2.lower_optical_point_size, None);
+        assert_eq!(
+            (os2.get_weight(), os2.get_style(), os2.get_stretch()),
+            (Weight(700), Style::Normal, Stretch::Condensed)
+        );
+
+        // バージョン 5 より新しいテーブルも 5 のフィールドを読む．
+        for version in [5, 6] {
+            let mut data = with_version(&data, version);
+            data.extend_from_slice(&[0, 160, 1, 64]);
+            let os2 = Os2::parse(&data).unwrap();
+            assert_eq!(os2.version, version);
+            assert_eq!(os2.lower_optical_point_size, Some(160));
+            assert_eq!(os2.upper_optical_point_size, Some(320));
+        }
+    }
+
+    // バージョンに対して短いテーブルは，あるフィールドだけを読む．
+    #[test]
+    fn truncated_tables() {
+        let data = test_fonts::os2(400, 5, FS_SELECTION_ITALIC);
+        let os2 = Os2::parse(&with_version(&data, 5)).unwrap();
+        assert_eq!(os2.max_context, Some(2));
+        assert_eq!(os2.lower_optical_point_size, None);
+        let os2 = Os2::parse(&data[..86]).unwrap();
+        assert_eq!(os2.code_page_range, Some([0x0002_0001, 0]));
+        assert_eq!(os2.x_height, None);
+        let os2 = Os2::parse(&data[..78]).unwrap();
+        assert_eq!(os2.win_descent, 200);
+        assert_eq!(os2.code_page_range, None);
+        let os2 = Os2::parse(&with_version(&data[..68], 0)).unwrap();
+        assert_eq!(os2.typo_ascender, 0);
+        assert_eq!(os2.get_style(), Style::Italic);
+        assert!(Os2::parse(&data[..60]).is_err());
+    }
+
+    #[test]
+    fn simulations() {
+        assert_eq!(
+            get_simulations(Weight(400), Style::Normal, Weight(700), Style::Italic),
+            Simulations::Bold | Simulations::Oblique
+        );
+        assert_eq!(
+            get_simulations(Weight(500), Style::Normal, Weight(600), Style::Normal),
+            Simulations::Bold
+        );
+        assert_eq!(
+            get_simulations(Weight(600), Style::Oblique, Weight(900), Style::Italic),
+            Simulations::None
+        );
+    }
+}
@@ -0,0 +1,871 @@
+use std::{
+    any::Any,
+    borrow::Cow,
+    fmt,
+    path::{Path, PathBuf},
+    sync::{Arc, OnceLock},
+};
+
+use crate::{
+    backend::{FactoryBackend, FontBackend, FontCollectionBackend, FontFamilyBackend},
+    cmap::{Cmap, CMAP},
+    error::{Error, Result},
+    family_model::{FontFamilyModel, TypographicNames, WssNames},
+    font::{Font, InformationalStringId, Simulations, Stretch, Style, Weight},
+    font_collection::FontCollection,
+    font_family::FontFamily,
+    font_file::FontFileReference,
+    fvar::{Fvar, FVAR},
+    localized_strings::{LocalizedString, LocalizedStringSet, LocalizedStrings},
+    name::{NameTable, NAME},
+    os2::read_attributes,
+    sfnt::{get_face_count, Sfnt},
+    stat::{Stat, STAT},
+    woff,
+};
+
+// DirectWrite を使わずに，フォントファイルを直接読んでフォントを列挙するバックエンド．
+
+pub const FONT_EXTENSIONS: [&str; 6] = ["ttf", "otf", "ttc", "otc", "woff", "woff2"];
+
+pub fn get_system_font_directories() -> Vec<PathBuf> {
+    let mut directories = vec![];
+    let home = std::env::var_os("HOME").map(PathBuf::from);
+    if cfg!(windows) {
+        if let Some(windir) = std::env::var_os("WINDIR") {
+            directories.push(PathBuf::from(windir).join("Fonts"));
+        }
+        if let Some(local) = std::env::var_os("LOCALAPPDATA") {
+            directories.push(PathBuf::from(local).join(r"Microsoft\Windows\Fonts"));
+        }
+    } else if cfg!(target_os = "macos") {
+        directories.push(PathBuf::from("/System/Library/Fonts"));
+        directories.push(PathBuf::from("/Library/Fonts"));
+        if let Some(home) = home {
+            directories.push(home.join("Library/Fonts"));
+        }
+    } else {
+        directories.push(PathBuf::from("/usr/share/fonts"));
+        directories.push(PathBuf::from("/usr/local/share/fonts"));
+        match std::env::var_os("XDG_DATA_HOME") {
+            Some(data_home) => directories.push(PathBuf::from(data_home).join("fonts")),
+            None => {
+                if let Some(home) = &home {
+                    directories.push(home.join(".local/share/fonts"));
+                }
+            }
+        }
+        if let Some(home) = home {
+            directories.push(home.join(".fonts"));
+        }
+    }
+    directories
+}
+
+// 拡張子は大文字と小文字を区別せず，先頭の "." はあってもなくてもよい．
+pub fn has_extension<S: AsRef<str>>(path: &Path, extensions: &[S]) -> bool {
+    match path.extension().and_then(|extension| extension.to_str()) {
+        Some(extension) => extensions.iter().any(|font_extension| {
+            let font_extension = font_extension.as_ref();
+            extension
+                .eq_ignore_ascii_case(font_extension.strip_prefix('.').unwrap_or(font_extension))
+        }),
+        None => false,
+    }
+}
+
+// シンボリックリンクのディレクトリはたどらない．読めないディレクトリは無視する．
+pub fn collect_font_files<S: AsRef<str>>(
+    directory: &Path,
+    extensions: &[S],
+    recursive: bool,
+    files: &mut Vec<PathBuf>,
+) {
+    let Ok(entries) = std::fs::read_dir(directory) else {
+        return;
+    };
+    for entry in entries.flatten() {
+        let Ok(file_type) = entry.file_type() else {
+            continue;
+        };
+        let path = entry.path();
+        if file_type.is_dir() {
+            if recursive {
+                collect_font_files(&path, extensions, recursive, files);
+            }
+        } else if has_extension(&path, extensions) {
+            files.push(path);
+        }
+    }
+}
+
+// ファイルから読み取った一つのフォントの情報．
+#[derive(Debug, Clone)]
+pub struct FontData {
+    pub file: FontFileReference,
+    pub face_index: u32,
+    // 可変フォントの名前付きインスタンスの位置．
+    pub instance_index: Option<u32>,
+    pub names: NameTable,
+    pub wss_names: WssNames,
+    pub typographic_names: TypographicNames,
+    pub weight: Weight,
+    pub style: Style,
+    pub stretch: Stretch,
+    pub is_variable_font: bool,
+    sfnt_data: SfntData,
+    cmap: CmapData,
+}
+
+// WOFF, WOFF2 を展開したファイル全体のデータ．同じファイルのフェイスで共有し，
+// 最初にテーブルを読むときに一度だけ読み込む．
+#[derive(Clone, Default)]
+struct SfntData(Arc<OnceLock<Arc<[u8]>>>);
+
+impl SfntData {
+    fn get_or_load(&self, file: &FontFileReference) -> Result<Arc<[u8]>> {
+        if let Some(data) = self.0.get() {
+            return Ok(data.clone());
+        }
+        let data = woff::decode_font_data(&file.read()?)?.into();
+        Ok(self.0.get_or_init(|| data).clone())
+    }
+}
+
+// 読み込んだ cmap テーブル．名前付きインスタンスは同じフェイスなので共有する．
+#[derive(Clone, Default)]
+struct CmapData(Arc<OnceLock<Option<Arc<Cmap>>>>);
+
+impl CmapData {
+    fn get_or_load(&self, font: &PortableFont) -> Result<Option<Arc<Cmap>>> {
+        if let Some(cmap) = self.0.get() {
+            return Ok(cmap.clone());
+        }
+        let cmap = match font.get_font_table(CMAP)? {
+            Some(table) => Some(Arc::new(Cmap::parse(&table)?)),
+            None => None,
+        };
+        Ok(self.0.get_or_init(|| cmap).clone())
+    }
+}
+
+impl fmt::Debug for CmapData {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.0.get() {
+            Some(_) => write!(f, "CmapData(loaded)"),
+            None => write!(f, "CmapData(not loaded)"),
+        }
+    }
+}
+
+impl fmt::Debug for SfntData {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.0.get() {
+            Some(data) => write!(f, "SfntData({} bytes)", data.len()),
+            None => write!(f, "SfntData(not loaded)"),
+        }
+    }
+}
+
+impl FontData {
+    // コレクションの場合は全てのフェイスを読む．
+    pub fn load(path: &Path) -> Result<Vec<Self>> {
+        Self::load_file(&FontFileReference::Local(path.to_path_buf()))
+    }
+
+    pub fn load_file(file: &FontFileReference) -> Result<Vec<Self>> {
+        Self::parse(file, &file.read()?)
+    }
+
+    // WOFF は sfnt に変換してから読む．可変フォントは名前付きインスタンスごとに分ける．
+    pub fn parse(file: &FontFileReference, data: &[u8]) -> Result<Vec<Self>> {
+        let data = woff::decode_font_data(data)?;
+        let mut fonts = vec![];
+        for face_index in 0..get_face_count(&data)? {
+            fonts.extend(Self::parse_face_instances(file, &data, face_index)?);
+        }
+        // メモリ上のファイルはすでに読み込まれているので，展開したデータもそのまま持っておく．
+        let sfnt_data = SfntData::default();
+        if let FontFileReference::Memory(memory) = file {
+            let data = match data {
+                Cow::Borrowed(_) => memory.clone(),
+                Cow::Owned(data) => data.into(),
+            };
+            let _ = sfnt_data.0.set(data);
+        }
+        for font in &mut fonts {
+            font.sfnt_data = sfnt_data.clone();
+        }
+        Ok(fonts)
+    }
+
+    // 名前付きインスタンスが無ければフェイスそのものを返す．
+    pub fn parse_face_instances(
+        file: &FontFileReference,
+        data: &[u8],
+        face_index: u32,
+    ) -> Result<Vec<Self>> {
+        let font = Self::parse_face(file, data, face_index)?;
+        let sfnt = Sfnt::parse_face(data, face_index)?;
+        let fvar = match sfnt.get_font_table(FVAR)? {
+            Some(table) => Fvar::parse(table)?,
+            None => return Ok(vec![font]),
+        };
+        if fvar.instances.is_empty() {
+            return Ok(vec![font]);
+        }
+        // STAT は名前を組み立てるためだけに使うので，読めなければ無いものとする．
+        let stat = sfnt
+            .get_font_table(STAT)
+            .ok()
+            .flatten()
+            .and_then(|table| Stat::parse(table).ok());
+        Ok((0..fvar.instances.len() as u32)
+            .map(|instance_index| font.with_named_instance(&fvar, stat.as_ref(), instance_index))
+            .collect())
+    }
+
+    pub fn parse_face(file: &FontFileReference, data: &[u8], face_index: u32) -> Result<Self> {
+        let sfnt = Sfnt::parse_face(data, face_index)?;
+        let names = match sfnt.get_font_table(NAME)? {
+            Some(table) => NameTable::parse(table)?,
+            None => NameTable::default(),
+        };
+        let wss_names = WssNames::from_name_table(&names);
+        let typographic_names = TypographicNames::from_name_table(&names);
+        let (weight, style, stretch) = read_attributes(&sfnt)?;
+        let (weight, style, stretch) = wss_names.apply(weight, style, stretch);
+        Ok(Self {
+            file: file.clone(),
+            face_index,
+            instance_index: None,
+            names,
+            wss_names,
+            typographic_names,
+            weight,
+            style,
+            stretch,
+            is_variable_font: sfnt.has_table(FVAR),
+            sfnt_data: SfntData::default(),
+            cmap: CmapData::default(),
+        })
+    }
+
+    // 名前とweight, style, stretch をインスタンスのものにする．
+    pub fn with_named_instance(
+        &self,
+        fvar: &Fvar,
+        stat: Option<&Stat>,
+        instance_index: u32,
+    ) -> Self {
+        let instance = &fvar.instances[instance_index as usize];
+        let (weight, style, stretch) =
+            fvar.get_instance_attributes(instance, (self.weight, self.style, self.stretch));
+        Self {
+            instance_index: Some(instance_index),
+            wss_names: WssNames::from_named_instance(&self.names, fvar, instance, stat),
+            typographic_names: TypographicNames::from_named_instance(
+                &self.names,
+                fvar,
+                instance,
+                stat,
+            ),
+            weight,
+            style,
+            stretch,
+            ..self.clone()
+        }
+    }
+
+    pub fn get_names(&self, name_id: u16) -> Vec<LocalizedString> {
+        self.names.get_names(name_id)
+    }
+
+    pub fn get_family_names(&self, model: FontFamilyModel) -> Vec<LocalizedString> {
+        match model {
+            FontFamilyModel::Typographic => self.typographic_names.family_names.clone(),
+            FontFamilyModel::WeightStretchStyle => self.wss_names.family_names.clone(),
+        }
+    }
+
+    pub fn get_face_names(&self, model: FontFamilyModel) -> Vec<LocalizedString> {
+        match model {
+            FontFamilyModel::Typographic => self.typographic_names.face_names.clone(),
+            FontFamilyModel::WeightStretchStyle => self.wss_names.face_names.clone(),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct FamilyData {
+    pub model: FontFamilyModel,
+    pub names: Vec<LocalizedString>,
+    pub fonts: Vec<FontData>,
+}
+
+fn get_sort_key(names: &[LocalizedString]) -> String {
+    let name = names
+        .iter()
+        .find(|name| name.locale == "en-us")
+        .or(names.first());
+    name.map(|name| name.string.to_lowercase())
+        .unwrap_or_default()
+}
+
+fn is_duplicate(model: FontFamilyModel, a: &FontData, b: &FontData) -> bool {
+    (a.weight, a.style, a.stretch) == (b.weight, b.style, b.stretch)
+        && get_sort_key(&a.get_face_names(model)) == get_sort_key(&b.get_face_names(model))
+}
+
+pub struct PortableFactory {
+    pub font_directories: Vec<PathBuf>,
+}
+
+impl PortableFactory {
+    pub fn new() -> Self {
+        Self::with_font_directories(get_system_font_directories())
+    }
+
+    pub fn with_font_directories(font_directories: Vec<PathBuf>) -> Self {
+        Self { font_directories }
+    }
+}
+
+impl Default for PortableFactory {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl FactoryBackend for PortableFactory {
+    // 毎回ディレクトリを走査するので check_for_updates は使わない．
+    fn get_system_font_collection(&self, _check_for_updates: bool) -> Result<FontCollection> {
+        self.get_system_font_collection_with_model(false, FontFamilyModel::WeightStretchStyle)
+    }
+
+    // ダウンロード可能なフォントは無いので include_downloadable_fonts は使わない．
+    fn get_system_font_collection_with_model(
+        &self,
+        _include_downloadable_fonts: bool,
+        model: FontFamilyModel,
+    ) -> Result<FontCollection> {
+        let mut files = vec![];
+        for directory in &self.font_directories {
+            collect_font_files(directory, &FONT_EXTENSIONS, true, &mut files);
+        }
+        let files: Vec<_> = files.into_iter().map(FontFileReference::Local).collect();
+        self.create_font_collection(&files, model)
+    }
+
+    fn create_font_collection(
+        &self,
+        files: &[FontFileReference],
+        model: FontFamilyModel,
+    ) -> Result<FontCollection> {
+        Ok(FontCollection::new(
+            PortableFontCollection::from_files_with_model(files, model),
+        ))
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+pub struct PortableFontCollection {
+    families: Vec<Arc<FamilyData>>,
+}
+
+impl PortableFontCollection {
+    // 読めないファイルは DirectWrite と同じく無視する．
+    pub fn load<P: AsRef<Path>>(paths: &[P]) -> Self {
+        let fonts = paths
+            .iter()
+            .filter_map(|path| FontData::load(path.as_ref()).ok())
+            .flatten()
+            .collect();
+        Self::from_fonts(fonts)
+    }
+
+    pub fn from_files(files: &[FontFileReference]) -> Self {
+        Self::from_files_with_model(files, FontFamilyModel::WeightStretchStyle)
+    }
+
+    pub fn from_files_with_model(files: &[FontFileReference], model: FontFamilyModel) -> Self {
+        let fonts = files
+            .iter()
+            .filter_map(|file| FontData::load_file(file).ok())
+            .flatten()
+            .collect();
+        Self::from_fonts_with_model(fonts, model)
+    }
+
+    pub fn from_fonts(fonts: Vec<FontData>) -> Self {
+        Self::from_fonts_with_model(fonts, FontFamilyModel::WeightStretchStyle)
+    }
+
+    // ファミリー名でファミリーにまとめる．
+    // 同じファミリーに weight, style, stretch とフェイス名が同じフォントがあれば，最初のものだけ残す．
+    pub fn from_fonts_with_model(fonts: Vec<FontData>, model: FontFamilyModel) -> Self {
+        let mut families: Vec<FamilyData> = vec![];
+        for font in fonts {
+            let names = font.get_family_names(model);
+            let key = get_sort_key(&names);
+            match families
+                .iter_mut()
+                .find(|family| get_sort_key(&family.names) == key)
+            {
+                Some(family) => {
+                    if !family
+                        .fonts
+                        .iter()
+                        .any(|other| is_duplicate(model, other, &font))
+                    {
+                        family.fonts.push(font);
+                    }
+                }
+                None => families.push(FamilyData {
+                    model,
+                    names,
+                    fonts: vec![font],
+                }),
+            }
+        }
+        families.sort_by_cached_key(|family| get_sort_key(&family.names));
+        for family in &mut families {
+            family
+                .fonts
+                .sort_by_key(|font| (font.weight, font.style.to_i32(), font.stretch.to_i32()));
+        }
+        Self {
+            families: families.into_iter().map(Arc::new).collect(),
+        }
+    }
+}
+
+impl FontCollectionBackend for PortableFontCollection {
+    fn get_font_family_count(&self) -> u32 {
+        self.families.len() as u32
+    }
+
+    fn get_font_family(&self, index: u32) -> Result<FontFamily> {
+        match self.families.get(index as usize) {
+            Some(family) => Ok(FontFamily::new(PortableFontFamily(family.clone()))),
+            None => Err(Error::IndexOutOfRange {
+                index,
+                count: self.get_font_family_count(),
+            }),
+        }
+    }
+
+    fn find_family_name(&self, family_name: &str) -> Result<(u32, bool)> {
+        let index = self.families.iter().position(|family| {
+            family
+                .names
+                .iter()
+                .any(|name| name.string.eq_ignore_ascii_case(family_name))
+        });
+        match index {
+            Some(index) => Ok((index as u32, true)),
+            None => Ok((u32::MAX, false)),
+        }
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+pub struct PortableFontFamily(Arc<FamilyData>);
+
+impl FontFamilyBackend for PortableFontFamily {
+    fn get_family_names(&self) -> Result<LocalizedStrings> {
+        Ok(LocalizedStringSet::from(self.0.names.clone()).into())
+    }
+
+    fn get_font_count(&self) -> u32 {
+        self.0.fonts.len() as u32
+    }
+
+    fn get_font(&self, index: u32) -> Result<Font> {
+        if index >= self.get_font_count() {
+            return Err(Error::IndexOutOfRange {
+                index,
+                count: self.get_font_count(),
+            });
+        }
+        Ok(Font::new(PortableFont {
+            family: self.0.clone(),
+            index: index as usize,
+        }))
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+pub struct PortableFont {
+    family: Arc<FamilyData>,
+    index: usize,
+}
+
+impl PortableFont {
+    pub fn data(&self) -> &FontData {
+        &self.family.fonts[self.index]
+    }
+}
+
+impl FontBackend for PortableFont {
+    fn get_font_family(&self) -> Result<FontFamily> {
+        Ok(FontFamily::new(PortableFontFamily(self.family.clone())))
+    }
+
+    // 太字や斜体のシミュレーションは作らない．
+    fn get_simulations(&self) -> Simulations {
+        Simulations::None
+    }
+
+    fn get_weight(&self) -> Weight {
+        self.data().weight
+    }
+
+    fn get_style(&self) -> Style {
+        self.data().style
+    }
+
+    fn get_stretch(&self) -> Stretch {
+        self.data().stretch
+    }
+
+    fn get_face_names(&self) -> Result<LocalizedStrings> {
+        Ok(LocalizedStringSet::from(self.data().get_face_names(self.family.model)).into())
+    }
+
+    fn get_informational_strings(
+        &self,
+        informational_string_id: InformationalStringId,
+    ) -> Result<Option<LocalizedStrings>> {
+        Ok(self
+            .data()
+            .names
+            .get_informational_strings(informational_string_id))
+    }
+
+    fn get_filepath(&self) -> Result<String> {
+        match self.data().file.get_filepath() {
+            Some(path) => Ok(path.to_string_lossy().into_owned()),
+            None => Err(Error::NotSupported(
+                "the font file is not a local file".to_string(),
+            )),
+        }
+    }
+
+    fn get_file_reference(&self) -> Result<FontFileReference> {
+        Ok(self.data().file.clone())
+    }
+
+    fn get_face_index(&self) -> Result<u32> {
+        Ok(self.data().face_index)
+    }
+
+    fn is_variable_font(&self) -> Result<bool> {
+        Ok(self.data().is_variable_font)
+    }
+
+    fn get_font_table(&self, tag: u32) -> Result<Option<Vec<u8>>> {
+        let data = self.data().sfnt_data.get_or_load(&self.data().file)?;
+        let sfnt = Sfnt::parse_face(&data, self.data().face_index)?;
+        Ok(sfnt.get_font_table(tag)?.map(|table| table.to_vec()))
+    }
+
+    fn get_cmap(&self) -> Result<Option<Arc<Cmap>>> {
+        self.data().cmap.get_or_load(self)
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        factory::Factory,
+        os2::{Os2, OS2},
+        sfnt::make_tag,
+        test_fonts::{
+            build_font, build_font_with_width, build_ttc, build_variable_font, FS_SELECTION_ITALIC,
+            FS_SELECTION_REGULAR,
+        },
+        woff2::GLYF,
+    };
+
+    fn create_test_collection() -> FontCollection {
+        Factory::portable()
+            .create_font_collection(&[
+                FontFileReference::from_memory(build_font(
+                    "Test Sans",
+                    "Italic",
+                    400,
+                    FS_SELECTION_ITALIC,
+                )),
+                FontFileReference::from_memory(build_ttc("Test Sans")),
+                FontFileReference::from_memory(build_font_with_width(
+                    "Test Serif",
+                    "Condensed",
+                    400,
+                    3,
+                    FS_SELECTION_REGULAR,
+                )),
+                // 読めないファイルは無視する．
+                FontFileReference::from_memory(&b"not a font"[..]),
+            ])
+            .unwrap()
+    }
+
+    fn get_name(strings: &LocalizedStrings) -> String {
+        strings.get("en-us").unwrap().unwrap()
+    }
+
+    #[test]
+    fn enumerate_fonts() {
+        let collection = create_test_collection();
+        assert_eq!(collection.get_font_family_count(), 2);
+        let mut families = vec![];
+        for family in &collection {
+            let family = family.unwrap();
+            let fonts = family
+                .into_iter()
+                .map(|font| {
+                    let font = font.unwrap();
+                    let face_names = font.get_face_names().unwrap();
+                    let attributes = (font.get_weight(), font.get_style(), font.get_stretch());
+                    (get_name(&face_names), attributes)
+                })
+                .collect::<Vec<_>>();
+            families.push((get_name(&family.get_family_names().unwrap()), fonts));
+        }
+        let normal = (Style::Normal, Stretch::Normal);
+        assert_eq!(
+            families,
+            vec![
+                (
+                    "Test Sans".to_string(),
+                    vec![
+                        ("Regular".to_string(), (Weight(400), normal.0, normal.1)),
+                        ("Italic".to_string(), (Weight(400), Style::Italic, normal.1)),
+                        ("Bold".to_string(), (Weight(700), normal.0, normal.1)),
+                    ]
+                ),
+                (
+                    "Test Serif".to_string(),
+                    vec![(
+                        "Condensed".to_string(),
+                        (Weight(400), normal.0, Stretch::Condensed)
+                    )]
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn family_names() {
+        let collection = create_test_collection();
+        let family = collection.find_family("test sans").unwrap();
+        let names = family.get_family_names().unwrap();
+        assert_eq!(names.get_count(), 2);
+        assert_eq!(names.get("ja-jp").unwrap().as_deref(), Some("テスト"));
+        assert_eq!(names.find_locale_name("en-US").unwrap(), Some(0));
+        assert!(collection.find_family("Test Mono").is_none());
+        assert_eq!(
+            collection.find_family_name("Test Mono").unwrap(),
+            (u32::MAX, false)
+        );
+        assert!(matches!(
+            collection.get_font_family(2),
+            Err(Error::IndexOutOfRange { index: 2, count: 2 })
+        ));
+        assert!(matches!(
+            family.get_font(3),
+            Err(Error::IndexOutOfRange { index: 3, count: 3 })
+        ));
+    }
+
+    #[test]
+    fn font_tables() {
+        let collection = create_test_collection();
+        let family = collection.find_family("Test Sans").unwrap();
+        let fonts = family.into_iter().collect::<Result<Vec<_>>>().unwrap();
+        // コレクションの二つのフェイスと，単体のフォント．
+        let face_indices = fonts
+            .iter()
+            .map(|font| font.get_face_index().unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(face_indices, vec![0, 0, 1]);
+        for font in &fonts {
+            let os2 = Os2::parse(&font.get_font_table(OS2).unwrap().unwrap()).unwrap();
+            assert_eq!(os2.get_weight(), font.get_weight());
+            assert_eq!(font.get_font_table(GLYF).unwrap().unwrap().len(), 84);
+            assert_eq!(font.get_font_table(make_tag(b"DSIG")).unwrap(), None);
+            assert!(font.has_character('A').unwrap());
+            assert!(!font.has_character('D').unwrap());
+            let font_family = font.get_font_family().unwrap();
+            assert_eq!(
+                get_name(&font_family.get_family_names().unwrap()),
+                "Test Sans"
+            );
+            assert!(matches!(
+                font.get_file_reference().unwrap(),
+                FontFileReference::Memory(_)
+            ));
+            assert!(matches!(font.get_filepath(), Err(Error::NotSupported(_))));
+            assert_eq!(font.get_simulations(), Simulations::None);
+            assert!(!font.is_variable_font().unwrap());
+        }
+        // コレクションのフェイスだけの sfnt を取り出せる．
+        let data = fonts[2].read_face_data().unwrap();
+        assert_eq!(get_face_count(&data).unwrap(), 1);
+        assert_eq!(
+            Sfnt::parse(&data).unwrap().get_font_table(OS2).unwrap(),
+            fonts[2].get_font_table(OS2).unwrap().as_deref()
+        );
+    }
+
+    #[test]
+    fn load_woff_files() {
+        let directory =
+            std::env::temp_dir().join(format!("dwrite_rs_portable_{}", std::process::id()));
+        std::fs::create_dir_all(&directory).unwrap();
+        let font = build_font("Test Sans", "Regular", 400, FS_SELECTION_REGULAR);
+        std::fs::write(directory.join("regular.woff"), woff::encode(&font).unwrap()).unwrap();
+        let font = build_font("Test Sans", "Italic", 400, FS_SELECTION_ITALIC);
+        std::fs::write(
+            directory.join("italic.woff2"),
+            crate::woff2::encode(&font).unwrap(),
+        )
+        .unwrap();
+        std::fs::write(directory.join("broken.ttf"), b"not a font").unwrap();
+        let collection = Factory::portable()
+            .create_font_collection_from_directory(&directory)
+            .unwrap();
+
+        assert_eq!(collection.get_font_family_count(), 1);
+        let family = collection.get_font_family(0).unwrap();
+        assert_eq!(family.get_font_count(), 2);
+        for font in &family {
+            let font = font.unwrap();
+            assert!(font
+                .get_filepath()
+                .unwrap()
+                .starts_with(&*directory.to_string_lossy()));
+            assert!(font.get_font_table(OS2).unwrap().is_some());
+        }
+        std::fs::remove_dir_all(&directory).unwrap();
+    }
+
+    // ファイルは最初にテーブルを読むときに一度だけ読み，同じファイルのフェイスで共有する．
+    #[test]
+    fn cache_font_file() {
+        let directory =
+            std::env::temp_dir().join(format!("dwrite_rs_cache_{}", std::process::id()));
+        std::fs::create_dir_all(&directory).unwrap();
+        let path = directory.join("test.ttc");
+        std::fs::write(&path, build_ttc("Test Sans")).unwrap();
+        let collection = PortableFontCollection::load(&[&path]);
+        let collection = FontCollection::new(collection);
+        let family = collection.get_font_family(0).unwrap();
+        let regular = family.get_font(0).unwrap();
+        let bold = family.get_font(1).unwrap();
+        assert!(regular.get_font_table(OS2).unwrap().is_some());
+        std::fs::remove_dir_all(&directory).unwrap();
+
+        assert!(regular.get_font_table(OS2).unwrap().is_some());
+        let os2 = Os2::parse(&bold.get_font_table(OS2).unwrap().unwrap()).unwrap();
+        assert_eq!(os2.weight_class, 700);
+        // ファイルを読んでいない別のコレクションは読めない．
+        let other = FontCollection::new(PortableFontCollection::from_fonts(
+            family
+                .into_iter()
+                .map(|font| {
+                    let font = font.unwrap();
+                    let data = font.backend().as_any().downcast_ref::<PortableFont>();
+                    FontData {
+                        sfnt_data: SfntData::default(),
+                        ..data.unwrap().data().clone()
+                    }
+                })
+                .collect(),
+        ));
+        let font = other.get_font_family(0).unwrap().get_font(0).unwrap();
+        assert!(font.get_font_table(OS2).unwrap_err().is_not_found());
+    }
+
+    // 名前付きインスタンスのフェイス名は STAT から組み立てる．
+    #[test]
+    fn named_instances() {
+        let file = FontFileReference::from_memory(build_variable_font("Test Var"));
+        let fonts = FontData::load_file(&file).unwrap();
+        let get_names = |model| {
+            fonts
+                .iter()
+                .map(|font| {
+                    let family = get_sort_key(&font.get_family_names(model));
+                    let face = font.get_face_names(model)[0].string.clone();
+                    (family, face)
+                })
+                .collect::<Vec<_>>()
+        };
+        let names = |faces: [&str; 3]| {
+            faces
+                .iter()
+                .map(|face| ("test var".to_string(), face.to_string()))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            get_names(FontFamilyModel::Typographic),
+            names(["Light", "Regular", "Bold Condensed"])
+        );
+        assert_eq!(
+            get_names(FontFamilyModel::WeightStretchStyle),
+            names(["Light", "Regular", "Bold Condensed"])
+        );
+        let attributes: Vec<_> = fonts
+            .iter()
+            .map(|font| (font.instance_index, font.weight, font.stretch))
+            .collect();
+        assert_eq!(
+            attributes,
+            vec![
+                (Some(0), Weight(300), Stretch::Normal),
+                (Some(1), Weight(400), Stretch::Normal),
+                (Some(2), Weight(700), Stretch::Condensed),
+            ]
+        );
+        assert!(fonts.iter().all(|font| font.is_variable_font));
+    }
+
+    // cmap は最初に文字を調べるときに一度だけ読み，同じフェイスの名前付きインスタンスで共有する．
+    #[test]
+    fn cache_cmap() {
+        let collection = FontCollection::new(PortableFontCollection::from_files(&[
+            FontFileReference::from_memory(build_variable_font("Test Var")),
+        ]));
+        let family = collection.get_font_family(0).unwrap();
+        let fonts = family.into_iter().collect::<Result<Vec<_>>>().unwrap();
+        let get_data = |font: &Font| {
+            let backend = font.backend().as_any().downcast_ref::<PortableFont>();
+            backend.unwrap().data().clone()
+        };
+        assert!(get_data(&fonts[1]).cmap.0.get().is_none());
+        assert!(fonts[0].has_character('B').unwrap());
+        assert!(!fonts[0].has_character('D').unwrap());
+        let cmap = get_data(&fonts[1]).cmap.0.get().cloned().flatten().unwrap();
+        assert!(Arc::ptr_eq(
+            &cmap,
+            &fonts[2].backend().get_cmap().unwrap().unwrap()
+        ));
+        assert_eq!(fonts[2].coverage().unwrap().len(), 3);
+    }
+}
@@ -0,0 +1,584 @@
+use crate::error::{Error, Result};
+
+// DWRITE_MAKE_OPENTYPE_TAG と同じく，先頭の文字が最下位バイトになる．
+pub const fn make_tag(tag: &[u8; 4]) -> u32 {
+    u32::from_le_bytes(*tag)
+}
+
+pub fn tag_to_string(tag: u32) -> String {
+    tag.to_le_bytes().iter().map(|&c| c as char).collect()
+}
+
+// ビッグエンディアンのフォントデータを先頭から読み進める．
+// tag を指定するとエラーは MalformedTable に，指定しない場合は InvalidFont になる．
+#[derive(Debug, Clone, Copy)]
+pub struct Reader<'a> {
+    data: &'a [u8],
+    offset: usize,
+    tag: Option<u32>,
+}
+
+impl<'a> Reader<'a> {
+    pub fn new(data: &'a [u8]) -> Self {
+        Self {
+            data,
+            offset: 0,
+            tag: None,
+        }
+    }
+
+    pub fn at(data: &'a [u8], offset: usize) -> Result<Self> {
+        Self::new(data).jump(offset)
+    }
+
+    pub fn table(tag: u32, data: &'a [u8]) -> Self {
+        Self {
+            data,
+            offset: 0,
+            tag: Some(tag),
+        }
+    }
+
+    // 同じデータの別の位置から読む Reader を返す．
+    pub fn jump(&self, offset: usize) -> Result<Self> {
+        if offset > self.data.len() {
+            return Err(self.error(format!(
+                "offset {} is out of range ({} bytes)",
+                offset,
+                self.data.len()
+            )));
+        }
+        Ok(Self {
+            data: self.data,
+            offset,
+            tag: self.tag,
+        })
+    }
+
+    pub fn error<S: Into<String>>(&self, reason: S) -> Error {
+        match self.tag {
+            Some(tag) => Error::malformed_table(tag, reason),
+            None => Error::InvalidFont(reason.into()),
+        }
+    }
+
+    pub fn data(&self) -> &'a [u8] {
+        self.data
+    }
+
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+
+    pub fn remaining(&self) -> usize {
+        self.data.len() - self.offset
+    }
+
+    pub fn skip(&mut self, len: usize) -> Result<()> {
+        self.read_bytes(len).map(|_| ())
+    }
+
+    pub fn read_bytes(&mut self, len: usize) -> Result<&'a [u8]> {
+        let end = self
+            .offset
+            .checked_add(len)
+            .filter(|&end| end <= self.data.len())
+            .ok_or_else(|| self.error("unexpected end of data"))?;
+        let bytes = &self.data[self.offset..end];
+        self.offset = end;
+        Ok(bytes)
+    }
+
+    pub fn read_array<const N: usize>(&mut self) -> Result<[u8; N]> {
+        let mut array = [0; N];
+        array.copy_from_slice(self.read_bytes(N)?);
+        Ok(array)
+    }
+
+    pub fn read_u8(&mut self) -> Result<u8> {
+        Ok(self.read_array::<1>()?[0])
+    }
+
+    pub fn read_i8(&mut self) -> Result<i8> {
+        Ok(self.read_u8()? as i8)
+    }
+
+    pub fn read_u16(&mut self) -> Result<u16> {
+        Ok(u16::from_be_bytes(self.read_array()?))
+    }
+
+    pub fn read_i16(&mut self) -> Result<i16> {
+        Ok(i16::from_be_bytes(self.read_array()?))
+    }
+
+    pub fn read_u24(&mut self) -> Result<u32> {
+        let [a, b, c] = self.read_array()?;
+        Ok(u32::from_be_bytes([0, a, b, c]))
+    }
+
+    pub fn read_u32(&mut self) -> Result<u32> {
+        Ok(u32::from_be_bytes(self.read_array()?))
+    }
+
+    pub fn read_i32(&mut self) -> Result<i32> {
+        Ok(i32::from_be_bytes(self.read_array()?))
+    }
+
+    // 16.16 固定小数点数．
+    pub fn read_fixed(&mut self) -> Result<f32> {
+        Ok(self.read_i32()? as f32 / 65536.0)
+    }
+
+    // 2.14 固定小数点数．
+    pub fn read_f2dot14(&mut self) -> Result<f32> {
+        Ok(self.read_i16()? as f32 / 16384.0)
+    }
+
+    pub fn read_tag(&mut self) -> Result<u32> {
+        Ok(u32::from_le_bytes(self.read_array()?))
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct TableRecord {
+    pub tag: u32,
+    pub checksum: u32,
+    pub offset: u32,
+    pub length: u32,
+}
+
+pub const TTCF: u32 = make_tag(b"ttcf");
+
+// TrueType/OpenType Collection (.ttc, .otc) のヘッダー．
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct TtcHeader {
+    pub major_version: u16,
+    pub minor_version: u16,
+    // 各フェイスのテーブルディレクトリの，ファイル先頭からの位置．
+    pub table_directory_offsets: Vec<u32>,
+    // バージョン 2 の DSIG テーブル (tag, length, offset)．
+    pub dsig: Option<(u32, u32, u32)>,
+}
+
+impl TtcHeader {
+    pub fn parse(data: &[u8]) -> Result<Self> {
+        let mut reader = Reader::new(data);
+        let tag = reader.read_tag()?;
+        if tag != TTCF {
+            return Err(Error::UnsupportedFileType(format!(
+                "not a font collection ('{}')",
+                tag_to_string(tag)
+            )));
+        }
+        let major_version = reader.read_u16()?;
+        let minor_version = reader.read_u16()?;
+        if !matches!(major_version, 1 | 2) {
+            return Err(Error::UnsupportedFileType(format!(
+                "unknown font collection version {}",
+                major_version
+            )));
+        }
+        let num_fonts = reader.read_u32()?;
+        let table_directory_offsets = (0..num_fonts)
+            .map(|_| reader.read_u32())
+            .collect::<Result<Vec<_>>>()?;
+        let dsig = if major_version == 2 {
+            let tag = reader.read_tag()?;
+            let length = reader.read_u32()?;
+            let offset = reader.read_u32()?;
+            // DSIG が無い場合は tag が 0 になる．
+            (tag != 0).then_some((tag, length, offset))
+        } else {
+            None
+        };
+        Ok(Self {
+            major_version,
+            minor_version,
+            table_directory_offsets,
+            dsig,
+        })
+    }
+}
+
+pub fn is_collection(data: &[u8]) -> bool {
+    data.len() >= 4 && make_tag(&[data[0], data[1], data[2], data[3]]) == TTCF
+}
+
+// 単体のフォントファイルは 1 を返す．
+pub fn get_face_count(data: &[u8]) -> Result<u32> {
+    if is_collection(data) {
+        Ok(TtcHeader::parse(data)?.table_directory_offsets.len() as u32)
+    } else {
+        Ok(1)
+    }
+}
+
+// 'true', 'typ1' は古い Mac の TrueType フォント．
+const SFNT_VERSIONS: [u32; 4] = [0x00010000, 0x4F54544F, 0x74727565, 0x74797031];
+
+// sfnt のテーブルディレクトリ．
+#[derive(Debug, Clone)]
+pub struct Sfnt<'a> {
+    data: &'a [u8],
+    pub sfnt_version: u32,
+    pub tables: Vec<TableRecord>,
+}
+
+impl<'a> Sfnt<'a> {
+    // コレクションの場合は最初のフェイスを読む．
+    pub fn parse(data: &'a [u8]) -> Result<Self> {
+        Self::parse_face(data, 0)
+    }
+
+    pub fn parse_face(data: &'a [u8], face_index: u32) -> Result<Self> {
+        if !is_collection(data) {
+            if face_index != 0 {
+                return Err(Error::IndexOutOfRange {
+                    index: face_index,
+                    count: 1,
+                });
+            }
+            return Self::parse_at(data, 0);
+        }
+        let header = TtcHeader::parse(data)?;
+        match header.table_directory_offsets.get(face_index as usize) {
+            Some(&offset) => Self::parse_at(data, offset as usize),
+            None => Err(Error::IndexOutOfRange {
+                index: face_index,
+                count: header.table_directory_offsets.len() as u32,
+            }),
+        }
+    }
+
+    // offset はファイル先頭からのテーブルディレクトリの位置．
+    pub fn parse_at(data: &'a [u8], offset: usize) -> Result<Self> {
+        let mut reader = Reader::at(data, offset)?;
+        let sfnt_version = reader.read_u32()?;
+        if !SFNT_VERSIONS.contains(&sfnt_version) {
+            return Err(Error::UnsupportedFileType(format!(
+                "unknown sfnt version {:#010x}",
+                sfnt_version
+            )));
+        }
+        let num_tables = reader.read_u16()?;
+        // searchRange, entrySelector, rangeShift
+        reader.skip(6)?;
+        let mut tables = Vec::with_capacity(num_tables as usize);
+        for _ in 0..num_tables {
+            tables.push(TableRecord {
+                tag: reader.read_tag()?,
+                checksum: reader.read_u32()?,
+                offset: reader.read_u32()?,
+                length: reader.read_u32()?,
+            });
+        }
+        Ok(Self {
+            data,
+            sfnt_version,
+            tables,
+        })
+    }
+
+    // このフェイスのテーブルだけで sfnt を作り直す．チェックサムは元の値を使う．
+    pub fn to_bytes(&self) -> Result<Vec<u8>> {
+        let tables = self
+            .tables
+            .iter()
+            .map(|record| {
+                let data = self.get_font_table(record.tag)?.unwrap_or_default();
+                Ok((record.tag, Some(record.checksum), data))
+            })
+            .collect::<Result<Vec<_>>>()?;
+        Ok(build_sfnt(self.sfnt_version, &tables))
+    }
+
+    pub fn get_table_record(&self, tag: u32) -> Option<&TableRecord> {
+        self.tables.iter().find(|record| record.tag == tag)
+    }
+
+    pub fn has_table(&self, tag: u32) -> bool {
+        self.get_table_record(tag).is_some()
+    }
+
+    pub fn get_font_table(&self, tag: u32) -> Result<Option<&'a [u8]>> {
+        let Some(record) = self.get_table_record(tag) else {
+            return Ok(None);
+        };
+        let start = record.offset as usize;
+        let end = start + record.length as usize;
+        match self.data.get(start..end) {
+            Some(table) => Ok(Some(table)),
+            None => Err(Error::InvalidFont(format!(
+                "'{}' table is out of range ({}..{} of {} bytes)",
+                tag_to_string(tag),
+                start,
+                end,
+                self.data.len()
+            ))),
+        }
+    }
+}
+
+pub const HEAD: u32 = make_tag(b"head");
+
+// テーブルのチェックサム．head は checksumAdjustment を 0 として計算する．
+pub fn calc_table_checksum(tag: u32, data: &[u8]) -> u32 {
+    let mut sum = 0_u32;
+    for (i, chunk) in data.chunks(4).enumerate() {
+        let mut word = [0; 4];
+        word[..chunk.len()].copy_from_slice(chunk);
+        // head の checksumAdjustment は 8..12 バイト目．
+        if tag == HEAD && i == 2 {
+            continue;
+        }
+        sum = sum.wrapping_add(u32::from_be_bytes(word));
+    }
+    sum
+}
+
+fn write_table_directory(out: &mut Vec<u8>, sfnt_version: u32, records: &[TableRecord]) {
+    let num_tables = records.len() as u16;
+    let entry_selector = if num_tables == 0 {
+        0
+    } else {
+        num_tables.ilog2() as u16
+    };
+    let search_range = (1_u16 << entry_selector) * 16;
+    let range_shift = (num_tables * 16).saturating_sub(search_range);
+    out.extend_from_slice(&sfnt_version.to_be_bytes());
+    out.extend_from_slice(&num_tables.to_be_bytes());
+    out.extend_from_slice(&search_range.to_be_bytes());
+    out.extend_from_slice(&entry_selector.to_be_bytes());
+    out.extend_from_slice(&range_shift.to_be_bytes());
+    for record in records {
+        out.extend_from_slice(&record.tag.to_le_bytes());
+        out.extend_from_slice(&record.checksum.to_be_bytes());
+        out.extend_from_slice(&record.offset.to_be_bytes());
+        out.extend_from_slice(&record.length.to_be_bytes());
+    }
+}
+
+// テーブルを tag の順に並べ，4 バイト境界に揃えた sfnt を作る．
+// tables は (tag, checksum, data) で，checksum が None の場合は計算する．
+pub fn build_sfnt(sfnt_version: u32, tables: &[(u32, Option<u32>, &[u8])]) -> Vec<u8> {
+    let indices = (0..tables.len()).collect();
+    build_fonts(None, &[(sfnt_version, indices)], tables)
+}
+
+// fonts は各フェイスの (sfntVersion, tables の添字) で，複数のフェイスが同じテーブルを共有できる．
+pub fn build_collection(
+    major_version: u16,
+    fonts: &[(u32, Vec<usize>)],
+    tables: &[(u32, Option<u32>, &[u8])],
+) -> Vec<u8> {
+    build_fonts(Some(major_version), fonts, tables)
+}
+
+fn build_fonts(
+    collection_version: Option<u16>,
+    fonts: &[(u32, Vec<usize>)],
+    tables: &[(u32, Option<u32>, &[u8])],
+) -> Vec<u8> {
+    let header_size = match collection_version {
+        Some(2) => 12 + fonts.len() * 4 + 12,
+        Some(_) => 12 + fonts.len() * 4,
+        None => 0,
+    };
+    let directory_offsets: Vec<usize> = fonts
+        .iter()
+        .scan(header_size, |offset, (_, indices)| {
+            let directory_offset = *offset;
+            *offset += 12 + indices.len() * 16;
+            Some(directory_offset)
+        })
+        .collect();
+    let mut offset = header_size
+        + fonts
+            .iter()
+            .map(|(_, indices)| 12 + indices.len() * 16)
+            .sum::<usize>();
+    let mut records = Vec::with_capacity(tables.len());
+    for &(tag, checksum, data) in tables {
+        records.push(TableRecord {
+            tag,
+            checksum: checksum.unwrap_or_else(|| calc_table_checksum(tag, data)),
+            offset: offset as u32,
+            length: data.len() as u32,
+        });
+        offset += data.len().next_multiple_of(4);
+    }
+
+    let mut out = Vec::with_capacity(offset);
+    if let Some(major_version) = collection_version {
+        out.extend_from_slice(&TTCF.to_le_bytes());
+        out.extend_from_slice(&major_version.to_be_bytes());
+        out.extend_from_slice(&0_u16.to_be_bytes());
+        out.extend_from_slice(&(fonts.len() as u32).to_be_bytes());
+        for &directory_offset in &directory_offsets {
+            out.extend_from_slice(&(directory_offset as u32).to_be_bytes());
+        }
+        // DSIG は付けない．
+        if major_version == 2 {
+            out.extend_from_slice(&[0; 12]);
+        }
+    }
+    for (sfnt_version, indices) in fonts {
+        let mut font_records: Vec<TableRecord> = indices.iter().map(|&i| records[i]).collect();
+        // タグはバイト列としての順に並べる．
+        font_records.sort_by_key(|record| record.tag.swap_bytes());
+        write_table_directory(&mut out, *sfnt_version, &font_records);
+    }
+    for &(_, _, data) in tables {
+        out.extend_from_slice(data);
+        out.resize(out.len().next_multiple_of(4), 0);
+    }
+    out
+}
+
+// head の checksumAdjustment を計算し直す．コレクションでは何もしない．
+pub fn update_checksum_adjustment(font: &mut [u8]) -> Result<()> {
+    if is_collection(font) {
+        return Ok(());
+    }
+    let Some(head) = Sfnt::parse(font)?.get_table_record(HEAD).copied() else {
+        return Ok(());
+    };
+    let position = head.offset as usize + 8;
+    if head.length < 12 || position + 4 > font.len() {
+        return Err(Error::malformed_table(HEAD, "too short"));
+    }
+    font[position..position + 4].fill(0);
+    let sum = calc_table_checksum(0, font);
+    let adjustment = 0xB1B0AFBA_u32.wrapping_sub(sum);
+    font[position..position + 4].copy_from_slice(&adjustment.to_be_bytes());
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        name::NAME,
+        os2::{Os2, OS2},
+        test_fonts::{build_font, build_ttc, FS_SELECTION_REGULAR, TRUETYPE},
+    };
+
+    fn weight_class(sfnt: &Sfnt) -> u16 {
+        Os2::parse(sfnt.get_font_table(OS2).unwrap().unwrap())
+            .unwrap()
+            .weight_class
+    }
+
+    #[test]
+    fn parse_collection() {
+        let ttc = build_ttc("Test Sans");
+        assert!(is_collection(&ttc));
+        let header = TtcHeader::parse(&ttc).unwrap();
+        assert_eq!((header.major_version, header.minor_version), (2, 0));
+        assert_eq!(header.table_directory_offsets.len(), 2);
+        assert_eq!(header.dsig, None);
+        assert_eq!(get_face_count(&ttc).unwrap(), 2);
+        let regular = Sfnt::parse_face(&ttc, 0).unwrap();
+        let bold = Sfnt::parse_face(&ttc, 1).unwrap();
+        assert_eq!((weight_class(&regular), weight_class(&bold)), (400, 700));
+        // name と OS/2 以外のテーブルは共有している．
+        assert_eq!(regular.get_table_record(HEAD), bold.get_table_record(HEAD));
+        assert_ne!(regular.get_table_record(NAME), bold.get_table_record(NAME));
+        assert!(matches!(
+            Sfnt::parse_face(&ttc, 2),
+            Err(Error::IndexOutOfRange { index: 2, count: 2 })
+        ));
+
+        // フェイスだけを取り出した sfnt は単体のフォントとして読める．
+        let face = bold.to_bytes().unwrap();
+        assert!(!is_collection(&face));
+        assert_eq!(get_face_count(&face).unwrap(), 1);
+        let sfnt = Sfnt::parse(&face).unwrap();
+        assert_eq!(sfnt.tables.len(), bold.tables.len());
+        assert_eq!(weight_class(&sfnt), 700);
+    }
+
+    #[test]
+    fn collection_versions() {
+        let font = build_font("Test Sans", "Regular", 400, FS_SELECTION_REGULAR);
+        let sfnt = Sfnt::parse(&font).unwrap();
+        let tables: Vec<_> = sfnt
+            .tables
+            .iter()
+            .map(|record| {
+                (
+                    record.tag,
+                    None,
+                    sfnt.get_font_table(record.tag).unwrap().unwrap(),
+                )
+            })
+            .collect();
+        let indices: Vec<_> = (0..tables.len()).collect();
+        let v1 = build_collection(1, &[(TRUETYPE, indices.clone())], &tables);
+        let header = TtcHeader::parse(&v1).unwrap();
+        assert_eq!(header.major_version, 1);
+        assert_eq!(header.table_directory_offsets, vec![16]);
+        assert_eq!(weight_class(&Sfnt::parse(&v1).unwrap()), 400);
+
+        // バージョン 2 の DSIG．
+        let mut v2 = build_collection(2, &[(TRUETYPE, indices)], &tables);
+        assert_eq!(
+            TtcHeader::parse(&v2).unwrap().table_directory_offsets,
+            vec![28]
+        );
+        v2[16..28].copy_from_slice(&[b'D', b'S', b'I', b'G', 0, 0, 0, 8, 0, 0, 1, 0]);
+        let header = TtcHeader::parse(&v2).unwrap();
+        assert_eq!(header.dsig, Some((make_tag(b"DSIG"), 8, 256)));
+    }
+
+    #[test]
+    fn malformed_collections() {
+        let ttc = build_ttc("Test Sans");
+        // 知らないバージョン．
+        let mut bad = ttc.clone();
+        bad[5] = 3;
+        assert!(matches!(
+            TtcHeader::parse(&bad),
+            Err(Error::UnsupportedFileType(_))
+        ));
+        // フェイスの数に対してヘッダーが短い．
+        let mut bad = ttc.clone();
+        bad[8..12].copy_from_slice(&1000_u32.to_be_bytes());
+        assert!(get_face_count(&bad).is_err());
+        assert!(Sfnt::parse_face(&bad[..64], 0).is_err());
+        // テーブルディレクトリがファイルの外にある．
+        let mut bad = ttc.clone();
+        bad[16..20].copy_from_slice(&0x00FF_FFFF_u32.to_be_bytes());
+        assert!(Sfnt::parse_face(&bad, 1).is_err());
+        assert!(Sfnt::parse_face(&bad, 0).is_ok());
+        // テーブルディレクトリが sfnt ではない．
+        let mut bad = ttc.clone();
+        bad[12..16].copy_from_slice(&12_u32.to_be_bytes());
+        assert!(matches!(
+            Sfnt::parse_face(&bad, 0),
+            Err(Error::UnsupportedFileType(_))
+        ));
+        assert!(TtcHeader::parse(b"ttcf").is_err());
+        assert!(TtcHeader::parse(&[0; 16]).is_err());
+    }
+
+    #[test]
+    fn malformed_fonts() {
+        let font = build_font("Test Sans", "Regular", 400, FS_SELECTION_REGULAR);
+        assert!(matches!(
+            Sfnt::parse_face(&font, 1),
+            Err(Error::IndexOutOfRange { index: 1, count: 1 })
+        ));
+        // テーブルがファイルの外にある．
+        let sfnt = Sfnt::parse(&font).unwrap();
+        let record = *sfnt.get_table_record(OS2).unwrap();
+        let truncated = &font[..record.offset as usize + 10];
+        let sfnt = Sfnt::parse(truncated).unwrap();
+        assert!(matches!(
+            sfnt.get_font_table(OS2),
+            Err(Error::InvalidFont(_))
+        ));
+        assert!(Sfnt::parse(&font[..20]).is_err());
+        assert!(Sfnt::parse(&[0; 6]).is_err());
+    }
+}
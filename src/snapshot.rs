@@ -0,0 +1,851 @@
+use core::fmt;
+use std::{
+    borrow::Cow,
+    collections::{HashMap, HashSet},
+    str::FromStr,
+};
+
+use crate::{
+    error::{Error, Result},
+    font::{Font, Simulations, Stretch, Style, Weight},
+    font_collection::FontCollection,
+    font_family::FontFamily,
+    json::{self, Json},
+    localized_strings::{LocalizedString, LocalizedStrings},
+};
+
+pub use crate::localized_strings::sort_names;
+
+// フォントコレクションの内容を記録したもの．
+// テキスト形式は examples の typo.txt や wss.txt と同じ形で，属性はタブの後に続ける (省略できる)．
+//
+// family count 1
+// Arial (en-us), Arial (ja-jp)
+// 	Regular (en-us), Normal (fr-fr)	weight=400 style=0 stretch=5 simulations=0
+// 	フォント リソースにアクセスできませんでした。 (Error)
+//
+// 取得できなかったファミリーやフォントは，エラーメッセージをロケール Error の名前として書く．
+// 名前もエラーもなければ (no names) と書く．
+
+const ERROR_LOCALE: &str = "Error";
+const NO_NAMES: &str = "(no names)";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct FaceAttributes {
+    pub weight: Weight,
+    pub style: Style,
+    pub stretch: Stretch,
+    pub simulations: Simulations,
+}
+
+impl FaceAttributes {
+    pub fn from_font(font: &Font) -> Self {
+        Self {
+            weight: font.get_weight(),
+            style: font.get_style(),
+            stretch: font.get_stretch(),
+            simulations: font.get_simulations(),
+        }
+    }
+}
+
+// DirectWrite の列挙値と同じ数値で書く．
+impl fmt::Display for FaceAttributes {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "weight={} style={} stretch={} simulations={}",
+            self.weight.0,
+            self.style.to_i32(),
+            self.stretch.to_i32(),
+            self.simulations.bits()
+        )
+    }
+}
+
+impl FromStr for FaceAttributes {
+    type Err = Error;
+    fn from_str(s: &str) -> Result<Self> {
+        let mut values = HashMap::new();
+        for field in s.split_whitespace() {
+            let (key, value) = field
+                .split_once('=')
+                .ok_or_else(|| Error::Parse(format!("invalid attribute: {}", field)))?;
+            let value = value
+                .parse::<i32>()
+                .map_err(|_| Error::Parse(format!("invalid attribute value: {}", field)))?;
+            values.insert(key, value);
+        }
+        let get = |key: &str| {
+            values
+                .get(key)
+                .copied()
+                .ok_or_else(|| Error::Parse(format!("missing attribute: {}", key)))
+        };
+        Ok(Self {
+            weight: Weight(get("weight")?),
+            style: Style::from_i32(get("style")?),
+            stretch: Stretch::from_i32(get("stretch")?),
+            simulations: Simulations::from_bits_retain(get("simulations")?),
+        })
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct FaceSnapshot {
+    pub names: Vec<LocalizedString>,
+    // テキスト形式から読んだ場合は属性がないことがある．
+    pub attributes: Option<FaceAttributes>,
+    // フォントを取得できなかった場合のエラーメッセージ．
+    pub error: Option<String>,
+}
+
+impl FaceSnapshot {
+    pub fn from_font(font: &Font) -> Self {
+        let attributes = Some(FaceAttributes::from_font(font));
+        match get_names(font.get_face_names()) {
+            Ok(names) => Self {
+                names,
+                attributes,
+                error: None,
+            },
+            Err(error) => Self {
+                names: vec![],
+                attributes,
+                error: Some(error.to_string()),
+            },
+        }
+    }
+
+    pub fn from_error(error: &Error) -> Self {
+        Self {
+            error: Some(error.to_string()),
+            ..Default::default()
+        }
+    }
+
+    // 差分で使う名前．
+    pub fn name(&self) -> &str {
+        get_key(&self.names, &self.error)
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct FamilySnapshot {
+    pub names: Vec<LocalizedString>,
+    pub faces: Vec<FaceSnapshot>,
+    // ファミリーを取得できなかった場合のエラーメッセージ．
+    pub error: Option<String>,
+}
+
+impl FamilySnapshot {
+    pub fn from_family(family: &FontFamily) -> Self {
+        let faces = family
+            .into_iter()
+            .map(|font| match font {
+                Ok(font) => FaceSnapshot::from_font(&font),
+                Err(error) => FaceSnapshot::from_error(&error),
+            })
+            .collect();
+        match get_names(family.get_family_names()) {
+            Ok(names) => Self {
+                names,
+                faces,
+                error: None,
+            },
+            Err(error) => Self {
+                names: vec![],
+                faces,
+                error: Some(error.to_string()),
+            },
+        }
+    }
+
+    pub fn from_error(error: &Error) -> Self {
+        Self {
+            error: Some(error.to_string()),
+            ..Default::default()
+        }
+    }
+
+    // 差分で使う名前．
+    pub fn name(&self) -> &str {
+        get_key(&self.names, &self.error)
+    }
+
+    // examples と同じく先頭の名前で並べる．
+    fn sort_key(&self) -> &str {
+        match (self.names.first(), &self.error) {
+            (Some(name), _) => &name.string,
+            (None, Some(error)) => error,
+            (None, None) => "",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct CollectionSnapshot {
+    pub families: Vec<FamilySnapshot>,
+}
+
+impl CollectionSnapshot {
+    // 取得できないファミリーやフォントもエラーとして記録する．
+    pub fn from_collection(collection: &FontCollection) -> Self {
+        let families = collection
+            .into_iter()
+            .map(|family| match family {
+                Ok(family) => FamilySnapshot::from_family(&family),
+                Err(error) => FamilySnapshot::from_error(&error),
+            })
+            .collect();
+        let mut snapshot = Self { families };
+        snapshot.sort();
+        snapshot
+    }
+
+    // ファミリーを先頭の名前で並べる．フォントの順番は変えない．
+    pub fn sort(&mut self) {
+        self.families.sort_by(|a, b| a.sort_key().cmp(b.sort_key()));
+    }
+
+    // examples のダンプと同じく，シミュレーションで作られたフォントを除く．
+    pub fn remove_simulations(&mut self) {
+        for family in &mut self.families {
+            family.faces.retain(|face| match face.attributes {
+                Some(attributes) => attributes.simulations.is_empty(),
+                None => true,
+            });
+        }
+    }
+
+    // 属性を持たない既存のダンプと比べるときに使う．
+    pub fn clear_attributes(&mut self) {
+        for face in self.families.iter_mut().flat_map(|f| f.faces.iter_mut()) {
+            face.attributes = None;
+        }
+    }
+
+    pub fn to_text(&self) -> String {
+        self.to_string()
+    }
+
+    // テキスト形式を読む．属性のない既存のダンプも読める．
+    pub fn parse(text: &str) -> Result<Self> {
+        let mut lines = text
+            .lines()
+            .enumerate()
+            .map(|(index, line)| (index + 1, line))
+            .filter(|(_, line)| !line.trim().is_empty());
+        let family_count = match lines.next() {
+            Some((number, line)) => line
+                .trim_start_matches('\u{feff}')
+                .strip_prefix("family count ")
+                .and_then(|count| count.trim().parse::<usize>().ok())
+                .ok_or_else(|| parse_error(number, "expected \"family count N\""))?,
+            None => return Err(parse_error(1, "empty snapshot")),
+        };
+        let mut families: Vec<FamilySnapshot> = vec![];
+        for (number, line) in lines {
+            if let Some(line) = line.strip_prefix('\t') {
+                let family = families
+                    .last_mut()
+                    .ok_or_else(|| parse_error(number, "face without family"))?;
+                let (names, attributes) = match line.split_once('\t') {
+                    Some((names, attributes)) => (
+                        names,
+                        Some(
+                            attributes
+                                .parse::<FaceAttributes>()
+                                .map_err(|e| parse_error(number, e))?,
+                        ),
+                    ),
+                    None => (line, None),
+                };
+                let (names, error) = parse_names(names)
+                    .ok_or_else(|| parse_error(number, format!("invalid face names: {}", names)))?;
+                family.faces.push(FaceSnapshot {
+                    names,
+                    attributes,
+                    error,
+                });
+            } else {
+                let (names, error) = parse_names(line).ok_or_else(|| {
+                    parse_error(number, format!("invalid family names: {}", line))
+                })?;
+                families.push(FamilySnapshot {
+                    names,
+                    faces: vec![],
+                    error,
+                });
+            }
+        }
+        if families.len() != family_count {
+            return Err(Error::Parse(format!(
+                "family count is {} but {} families were found",
+                family_count,
+                families.len()
+            )));
+        }
+        Ok(Self { families })
+    }
+
+    // キーの順番とインデントが固定された JSON．
+    pub fn to_json(&self) -> String {
+        let families = self.families.iter().map(|family| {
+            let mut fields = vec![("names", json::names(&family.names))];
+            if let Some(error) = &family.error {
+                fields.push(("error", error.as_str().into()));
+            }
+            fields.push(("faces", Json::array(family.faces.iter().map(face_to_json))));
+            Json::object(fields)
+        });
+        Json::object([
+            ("family_count", self.families.len().into()),
+            ("families", Json::array(families)),
+        ])
+        .to_string_pretty(4)
+    }
+
+    // self を古いスナップショットとして new との違いを返す．
+    pub fn diff(&self, new: &CollectionSnapshot) -> SnapshotDiff {
+        diff(&self.families, &new.families)
+    }
+}
+
+impl fmt::Display for CollectionSnapshot {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "family count {}", self.families.len())?;
+        for family in &self.families {
+            writeln!(f, "{}", join_names(&family.names, &family.error))?;
+            for face in &family.faces {
+                write!(f, "\t{}", join_names(&face.names, &face.error))?;
+                if let Some(attributes) = face.attributes {
+                    write!(f, "\t{}", attributes)?;
+                }
+                writeln!(f)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for CollectionSnapshot {
+    type Err = Error;
+    fn from_str(s: &str) -> Result<Self> {
+        Self::parse(s)
+    }
+}
+
+fn get_names(strings: Result<LocalizedStrings>) -> Result<Vec<LocalizedString>> {
+    let mut names = strings?.into_iter().collect::<Result<Vec<_>>>()?;
+    sort_names(&mut names);
+    Ok(names)
+}
+
+// en-us の名前，なければ先頭の名前．名前がなければエラーメッセージ．
+fn get_key<'a>(names: &'a [LocalizedString], error: &'a Option<String>) -> &'a str {
+    let name = names
+        .iter()
+        .find(|name| name.locale.eq_ignore_ascii_case("en-us"))
+        .or(names.first());
+    match (name, error) {
+        (Some(name), _) => &name.string,
+        (None, Some(error)) => error,
+        (None, None) => "",
+    }
+}
+
+// 一行に一つのファミリーまたはフォントを書くので，タブと改行は空白にする．
+fn clean(s: &str) -> Cow<'_, str> {
+    if s.contains(['\t', '\n', '\r']) {
+        Cow::Owned(s.replace(['\t', '\n', '\r'], " "))
+    } else {
+        Cow::Borrowed(s)
+    }
+}
+
+fn join_names(names: &[LocalizedString], error: &Option<String>) -> String {
+    match (names.is_empty(), error) {
+        (true, Some(error)) => format!("{} ({})", clean(error), ERROR_LOCALE),
+        (true, None) => NO_NAMES.to_string(),
+        _ => names
+            .iter()
+            .map(|name| format!("{} ({})", clean(&name.string), clean(&name.locale)))
+            .collect::<Vec<_>>()
+            .join(", "),
+    }
+}
+
+fn parse_error<T: fmt::Display>(line: usize, reason: T) -> Error {
+    Error::Parse(format!("line {}: {}", line, reason))
+}
+
+fn is_locale(s: &str) -> bool {
+    !s.is_empty()
+        && s.chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+}
+
+// "名前 (ロケール), 名前 (ロケール)" を読む．"メッセージ (Error)" はエラーとして返す．
+// "(no names)" は名前のない行．名前の並びとして読める行は必ず " (" を含むので，紛れることはない．
+// 名前に "), " が含まれていても，ロケールとして読めない区切りは名前の一部とみなす．
+fn parse_names(line: &str) -> Option<(Vec<LocalizedString>, Option<String>)> {
+    if let Some(error) = line.strip_suffix(&format!(" ({})", ERROR_LOCALE)) {
+        return Some((vec![], Some(error.to_string())));
+    }
+    if line == NO_NAMES {
+        return Some((vec![], None));
+    }
+    let mut names = vec![];
+    let mut pending = String::new();
+    let mut pieces = line.split("), ").peekable();
+    while let Some(piece) = pieces.next() {
+        pending.push_str(piece);
+        let entry = if pieces.peek().is_some() {
+            Cow::Owned(format!("{})", pending))
+        } else {
+            Cow::Borrowed(pending.as_str())
+        };
+        let parsed = entry
+            .strip_suffix(')')
+            .and_then(|entry| entry.rsplit_once(" ("))
+            .filter(|(_, locale)| is_locale(locale))
+            .map(|(string, locale)| LocalizedString {
+                string: string.to_string(),
+                locale: locale.to_string(),
+            });
+        match parsed {
+            Some(name) => {
+                names.push(name);
+                pending.clear();
+            }
+            None if pieces.peek().is_some() => pending.push_str("), "),
+            None => return None,
+        }
+    }
+    Some((names, None))
+}
+
+fn face_to_json(face: &FaceSnapshot) -> Json {
+    let mut fields = vec![("names", json::names(&face.names))];
+    if let Some(attributes) = face.attributes {
+        fields.push(("weight", attributes.weight.0.into()));
+        fields.push(("style", attributes.style.to_i32().into()));
+        fields.push(("stretch", attributes.stretch.to_i32().into()));
+        fields.push(("simulations", attributes.simulations.bits().into()));
+    }
+    if let Some(error) = &face.error {
+        fields.push(("error", error.as_str().into()));
+    }
+    Json::object(fields)
+}
+
+// 名前が変わったファミリー．同じ名前を一つでも持つファミリーを同じものとみなす．
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct FamilyRename {
+    pub old: String,
+    pub new: String,
+}
+
+// 別のファミリーへ移ったフォント．フォントはフルネームで対応させる．
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct FaceMove {
+    pub face: String,
+    pub old_family: String,
+    pub new_family: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct FaceRef {
+    pub family: String,
+    pub face: String,
+}
+
+// あるロケールの名前の変化．face が None ならファミリー名．
+// family は新しいスナップショットでの名前．
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct NameChange {
+    pub family: String,
+    pub face: Option<String>,
+    pub locale: String,
+    pub old: Option<String>,
+    pub new: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct AttributeChange {
+    pub family: String,
+    pub face: String,
+    pub old: FaceAttributes,
+    pub new: FaceAttributes,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ErrorChange {
+    pub family: String,
+    pub message: String,
+}
+
+// 二つのスナップショットの違い．
+// 追加や削除されたファミリーのフォントは，他のファミリーへ移ったものだけを faces_moved に含める．
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SnapshotDiff {
+    pub families_added: Vec<String>,
+    pub families_removed: Vec<String>,
+    pub families_renamed: Vec<FamilyRename>,
+    pub faces_moved: Vec<FaceMove>,
+    pub faces_added: Vec<FaceRef>,
+    pub faces_removed: Vec<FaceRef>,
+    pub name_changes: Vec<NameChange>,
+    pub attribute_changes: Vec<AttributeChange>,
+    pub errors_added: Vec<ErrorChange>,
+    pub errors_removed: Vec<ErrorChange>,
+}
+
+impl SnapshotDiff {
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+fn or_dash(s: &Option<String>) -> &str {
+    s.as_deref().unwrap_or("-")
+}
+
+impl fmt::Display for SnapshotDiff {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for family in &self.families_added {
+            writeln!(f, "+ family {}", family)?;
+        }
+        for family in &self.families_removed {
+            writeln!(f, "- family {}", family)?;
+        }
+        for rename in &self.families_renamed {
+            writeln!(f, "~ family {} -> {}", rename.old, rename.new)?;
+        }
+        for face in &self.faces_moved {
+            writeln!(
+                f,
+                "> face {}: {} -> {}",
+                face.face, face.old_family, face.new_family
+            )?;
+        }
+        for face in &self.faces_added {
+            writeln!(f, "+ face {} / {}", face.family, face.face)?;
+        }
+        for face in &self.faces_removed {
+            writeln!(f, "- face {} / {}", face.family, face.face)?;
+        }
+        for change in &self.name_changes {
+            write!(f, "* name {}", change.family)?;
+            if let Some(face) = &change.face {
+                write!(f, " / {}", face)?;
+            }
+            writeln!(
+                f,
+                " ({}): {} -> {}",
+                change.locale,
+                or_dash(&change.old),
+                or_dash(&change.new)
+            )?;
+        }
+        for change in &self.attribute_changes {
+            writeln!(
+                f,
+                "* attributes {} / {}: {} -> {}",
+                change.family, change.face, change.old, change.new
+            )?;
+        }
+        for error in &self.errors_added {
+            writeln!(f, "+ error {}: {}", error.family, error.message)?;
+        }
+        for error in &self.errors_removed {
+            writeln!(f, "- error {}: {}", error.family, error.message)?;
+        }
+        Ok(())
+    }
+}
+
+// ファミリー名とフォント名をつなげたフルネーム．DirectWrite と同じく Regular は省く．
+// WSS の "Arial Black" + "Regular" とタイポグラフィックの "Arial" + "Black" が同じになる．
+fn full_name(family: &str, face: &str) -> String {
+    let mut words: Vec<&str> = family.split_whitespace().collect();
+    words.extend(
+        face.split_whitespace()
+            .filter(|word| !word.eq_ignore_ascii_case("regular")),
+    );
+    words.join(" ")
+}
+
+fn shares_name(old: &FamilySnapshot, new: &FamilySnapshot) -> bool {
+    old.names.iter().any(|a| {
+        new.names
+            .iter()
+            .any(|b| a.string.to_lowercase() == b.string.to_lowercase())
+    })
+}
+
+// ロケールごとに名前を比べる．ロケールは大文字と小文字を区別しない．
+fn diff_names(
+    old: &[LocalizedString],
+    new: &[LocalizedString],
+) -> Vec<(String, Option<String>, Option<String>)> {
+    let find = |names: &[LocalizedString], locale: &str| {
+        names
+            .iter()
+            .find(|name| name.locale.eq_ignore_ascii_case(locale))
+            .map(|name| name.string.clone())
+    };
+    let mut locales: Vec<&str> = vec![];
+    for name in old.iter().chain(new) {
+        if !locales.iter().any(|l| l.eq_ignore_ascii_case(&name.locale)) {
+            locales.push(&name.locale);
+        }
+    }
+    locales
+        .into_iter()
+        .filter_map(|locale| {
+            let (a, b) = (find(old, locale), find(new, locale));
+            (a != b).then(|| (locale.to_string(), a, b))
+        })
+        .collect()
+}
+
+fn diff(old: &[FamilySnapshot], new: &[FamilySnapshot]) -> SnapshotDiff {
+    let mut result = SnapshotDiff::default();
+
+    // 同じ名前のファミリーを対応させ，残りから名前が変わったものを探す．
+    let mut new_by_name: HashMap<&str, usize> = HashMap::new();
+    for (j, family) in new.iter().enumerate() {
+        new_by_name.entry(family.name()).or_insert(j);
+    }
+    let mut old_to_new: HashMap<usize, usize> = HashMap::new();
+    let mut new_matched: HashSet<usize> = HashSet::new();
+    for (i, family) in old.iter().enumerate() {
+        if let Some(&j) = new_by_name.get(family.name()) {
+            if new_matched.insert(j) {
+                old_to_new.insert(i, j);
+            }
+        }
+    }
+    for (i, family) in old.iter().enumerate() {
+        if old_to_new.contains_key(&i) {
+            continue;
+        }
+        let renamed =
+            (0..new.len()).find(|j| !new_matched.contains(j) && shares_name(family, &new[*j]));
+        if let Some(j) = renamed {
+            new_matched.insert(j);
+            old_to_new.insert(i, j);
+            result.families_renamed.push(FamilyRename {
+                old: family.name().to_string(),
+                new: new[j].name().to_string(),
+            });
+        }
+    }
+    for (j, family) in new.iter().enumerate() {
+        if !new_matched.contains(&j) {
+            result.families_added.push(family.name().to_string());
+        }
+    }
+    for (i, family) in old.iter().enumerate() {
+        if !old_to_new.contains_key(&i) {
+            result.families_removed.push(family.name().to_string());
+        }
+    }
+
+    // フルネームが同じで，対応しないファミリーにあるフォントは移ったとみなす．
+    let named_faces = |families: &[FamilySnapshot]| {
+        let mut faces: Vec<(String, usize, usize)> = vec![];
+        for (i, family) in families.iter().enumerate() {
+            for (k, face) in family.faces.iter().enumerate() {
+                if !face.names.is_empty() {
+                    faces.push((full_name(family.name(), face.name()), i, k));
+                }
+            }
+        }
+        faces
+    };
+    let mut new_by_full_name: HashMap<String, (usize, usize)> = HashMap::new();
+    for (name, j, k) in named_faces(new) {
+        new_by_full_name
+            .entry(name.to_lowercase())
+            .or_insert((j, k));
+    }
+    let mut old_moved: HashSet<(usize, usize)> = HashSet::new();
+    let mut new_moved: HashSet<(usize, usize)> = HashSet::new();
+    for (name, i, k) in named_faces(old) {
+        let Some(&(j, l)) = new_by_full_name.get(&name.to_lowercase()) else {
+            continue;
+        };
+        if old_to_new.get(&i) == Some(&j) || !new_moved.insert((j, l)) {
+            continue;
+        }
+        old_moved.insert((i, k));
+        result.faces_moved.push(FaceMove {
+            face: name,
+            old_family: old[i].name().to_string(),
+            new_family: new[j].name().to_string(),
+        });
+    }
+
+    // 対応するファミリーの中では，フォント名でフォントを対応させる．
+    let mut pairs: Vec<(usize, usize)> = old_to_new.into_iter().collect();
+    pairs.sort_by_key(|&(i, j)| (j, i));
+    for (i, j) in pairs {
+        let (old_family, new_family) = (&old[i], &new[j]);
+        let family = new_family.name().to_string();
+        for (locale, a, b) in diff_names(&old_family.names, &new_family.names) {
+            result.name_changes.push(NameChange {
+                family: family.clone(),
+                face: None,
+                locale,
+                old: a,
+                new: b,
+            });
+        }
+
+        let mut used: HashSet<usize> = HashSet::new();
+        for (k, old_face) in old_family.faces.iter().enumerate() {
+            if old_face.names.is_empty() || old_moved.contains(&(i, k)) {
+                continue;
+            }
+            let found = new_family.faces.iter().enumerate().position(|(l, face)| {
+                !face.names.is_empty()
+                    && !used.contains(&l)
+                    && !new_moved.contains(&(j, l))
+                    && face.name() == old_face.name()
+            });
+            let Some(l) = found else {
+                result.faces_removed.push(FaceRef {
+                    family: family.clone(),
+                    face: old_face.name().to_string(),
+                });
+                continue;
+            };
+            used.insert(l);
+            let new_face = &new_family.faces[l];
+            for (locale, a, b) in diff_names(&old_face.names, &new_face.names) {
+                result.name_changes.push(NameChange {
+                    family: family.clone(),
+                    face: Some(new_face.name().to_string()),
+                    locale,
+                    old: a,
+                    new: b,
+                });
+            }
+            if let (Some(a), Some(b)) = (old_face.attributes, new_face.attributes) {
+                if a != b {
+                    result.attribute_changes.push(AttributeChange {
+                        family: family.clone(),
+                        face: new_face.name().to_string(),
+                        old: a,
+                        new: b,
+                    });
+                }
+            }
+        }
+        for (l, new_face) in new_family.faces.iter().enumerate() {
+            if !new_face.names.is_empty() && !used.contains(&l) && !new_moved.contains(&(j, l)) {
+                result.faces_added.push(FaceRef {
+                    family: family.clone(),
+                    face: new_face.name().to_string(),
+                });
+            }
+        }
+
+        // エラーは同じメッセージの数を比べる．
+        let errors = |family: &FamilySnapshot| {
+            let mut errors: Vec<String> = family.error.iter().cloned().collect();
+            errors.extend(family.faces.iter().filter_map(|face| face.error.clone()));
+            errors
+        };
+        let mut old_errors = errors(old_family);
+        for message in errors(new_family) {
+            match old_errors.iter().position(|e| *e == message) {
+                Some(index) => {
+                    old_errors.remove(index);
+                }
+                None => result.errors_added.push(ErrorChange {
+                    family: family.clone(),
+                    message,
+                }),
+            }
+        }
+        for message in old_errors {
+            result.errors_removed.push(ErrorChange {
+                family: family.clone(),
+                message,
+            });
+        }
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn name(string: &str, locale: &str) -> LocalizedString {
+        LocalizedString {
+            string: string.to_string(),
+            locale: locale.to_string(),
+        }
+    }
+
+    // 名前のないファミリーやフォントも読み戻せる．
+    #[test]
+    fn round_trip_without_names() {
+        let snapshot = CollectionSnapshot {
+            families: vec![
+                FamilySnapshot {
+                    names: vec![],
+                    faces: vec![FaceSnapshot {
+                        names: vec![name("Regular", "en-us")],
+                        attributes: None,
+                        error: None,
+                    }],
+                    error: None,
+                },
+                FamilySnapshot {
+                    names: vec![name("Arial", "en-us")],
+                    faces: vec![
+                        FaceSnapshot {
+                            names: vec![],
+                            attributes: Some(FaceAttributes {
+                                weight: Weight(400),
+                                style: Style::Normal,
+                                stretch: Stretch::Normal,
+                                simulations: Simulations::None,
+                            }),
+                            error: None,
+                        },
+                        FaceSnapshot {
+                            names: vec![],
+                            attributes: None,
+                            error: Some("broken".to_string()),
+                        },
+                    ],
+                    error: None,
+                },
+            ],
+        };
+        let text = snapshot.to_text();
+        assert_eq!(
+            text,
+            "family count 2\n(no names)\n\tRegular (en-us)\nArial (en-us)\n\t(no names)\tweight=400 style=0 stretch=5 simulations=0\n\tbroken (Error)\n"
+        );
+        assert_eq!(CollectionSnapshot::parse(&text).unwrap(), snapshot);
+    }
+}
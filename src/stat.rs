@@ -0,0 +1,468 @@
+use crate::{
+    error::Result,
+    font::Font,
+    fvar::{Fvar, NamedInstance},
+    localized_strings::{LocalizedString, LocalizedStringSet, LocalizedStrings},
+    name::NameTable,
+    sfnt::{make_tag, Reader, Sfnt},
+    woff,
+};
+
+// STAT テーブルと，軸の座標からサブファミリー名を組み立てる処理．
+
+pub const STAT: u32 = make_tag(b"STAT");
+
+// AxisValue の flags．
+pub const OLDER_SIBLING_FONT_ATTRIBUTE: u16 = 0x0001;
+pub const ELIDABLE_AXIS_VALUE_NAME: u16 = 0x0002;
+
+// elidedFallbackNameID が無い古いバージョンでは name ID 2 を使う．
+const DEFAULT_ELIDED_FALLBACK_NAME_ID: u16 = 2;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct DesignAxis {
+    pub tag: u32,
+    pub axis_name_id: u16,
+    pub axis_ordering: u16,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum AxisValue {
+    // format 1: 一つの値．
+    Single {
+        axis_index: u16,
+        flags: u16,
+        value_name_id: u16,
+        value: f32,
+    },
+    // format 2: 範囲．
+    Range {
+        axis_index: u16,
+        flags: u16,
+        value_name_id: u16,
+        nominal_value: f32,
+        range_min_value: f32,
+        range_max_value: f32,
+    },
+    // format 3: 太字などの対応する値を持つ．
+    Linked {
+        axis_index: u16,
+        flags: u16,
+        value_name_id: u16,
+        value: f32,
+        linked_value: f32,
+    },
+    // format 4: 複数の軸の組み合わせ．(axis_index, value) の列．
+    Multiple {
+        flags: u16,
+        value_name_id: u16,
+        values: Vec<(u16, f32)>,
+    },
+}
+
+impl AxisValue {
+    pub fn format(&self) -> u16 {
+        match self {
+            Self::Single { .. } => 1,
+            Self::Range { .. } => 2,
+            Self::Linked { .. } => 3,
+            Self::Multiple { .. } => 4,
+        }
+    }
+
+    pub fn flags(&self) -> u16 {
+        match self {
+            Self::Single { flags, .. }
+            | Self::Range { flags, .. }
+            | Self::Linked { flags, .. }
+            | Self::Multiple { flags, .. } => *flags,
+        }
+    }
+
+    pub fn value_name_id(&self) -> u16 {
+        match self {
+            Self::Single { value_name_id, .. }
+            | Self::Range { value_name_id, .. }
+            | Self::Linked { value_name_id, .. }
+            | Self::Multiple { value_name_id, .. } => *value_name_id,
+        }
+    }
+
+    pub fn is_elidable(&self) -> bool {
+        self.flags() & ELIDABLE_AXIS_VALUE_NAME != 0
+    }
+
+    // format 1..=3 の対象の軸．format 4 は None．
+    pub fn axis_index(&self) -> Option<u16> {
+        match self {
+            Self::Single { axis_index, .. }
+            | Self::Range { axis_index, .. }
+            | Self::Linked { axis_index, .. } => Some(*axis_index),
+            Self::Multiple { .. } => None,
+        }
+    }
+
+    // 一つの軸の値 value に当てはまるか．format 4 は常に false．
+    fn matches(&self, value: f32) -> bool {
+        match self {
+            Self::Single { value: v, .. } | Self::Linked { value: v, .. } => *v == value,
+            Self::Range {
+                range_min_value,
+                range_max_value,
+                ..
+            } => *range_min_value <= value && value <= *range_max_value,
+            Self::Multiple { .. } => false,
+        }
+    }
+}
+
+// 知らない format の AxisValue は仕様どおり読み飛ばすので None を返す．
+fn read_axis_value(reader: &mut Reader) -> Result<Option<AxisValue>> {
+    let format = reader.read_u16()?;
+    let axis_value = match format {
+        1 => AxisValue::Single {
+            axis_index: reader.read_u16()?,
+            flags: reader.read_u16()?,
+            value_name_id: reader.read_u16()?,
+            value: reader.read_fixed()?,
+        },
+        2 => AxisValue::Range {
+            axis_index: reader.read_u16()?,
+            flags: reader.read_u16()?,
+            value_name_id: reader.read_u16()?,
+            nominal_value: reader.read_fixed()?,
+            range_min_value: reader.read_fixed()?,
+            range_max_value: reader.read_fixed()?,
+        },
+        3 => AxisValue::Linked {
+            axis_index: reader.read_u16()?,
+            flags: reader.read_u16()?,
+            value_name_id: reader.read_u16()?,
+            value: reader.read_fixed()?,
+            linked_value: reader.read_fixed()?,
+        },
+        4 => {
+            let axis_count = reader.read_u16()?;
+            let flags = reader.read_u16()?;
+            let value_name_id = reader.read_u16()?;
+            let values = (0..axis_count)
+                .map(|_| Ok((reader.read_u16()?, reader.read_fixed()?)))
+                .collect::<Result<Vec<_>>>()?;
+            AxisValue::Multiple {
+                flags,
+                value_name_id,
+                values,
+            }
+        }
+        _ => return Ok(None),
+    };
+    Ok(Some(axis_value))
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Stat {
+    pub major_version: u16,
+    pub minor_version: u16,
+    pub design_axes: Vec<DesignAxis>,
+    pub axis_values: Vec<AxisValue>,
+    // バージョン 1.1 以降．
+    pub elided_fallback_name_id: Option<u16>,
+}
+
+impl Stat {
+    pub fn parse(data: &[u8]) -> Result<Self> {
+        let mut reader = Reader::table(STAT, data);
+        let major_version = reader.read_u16()?;
+        let minor_version = reader.read_u16()?;
+        if major_version != 1 {
+            return Err(reader.error(format!("unknown version {}", major_version)));
+        }
+        let design_axis_size = reader.read_u16()? as usize;
+        let design_axis_count = reader.read_u16()? as usize;
+        let design_axes_offset = reader.read_u32()? as usize;
+        let axis_value_count = reader.read_u16()? as usize;
+        let offset_to_axis_value_offsets = reader.read_u32()? as usize;
+        let elided_fallback_name_id = if minor_version >= 1 {
+            Some(reader.read_u16()?)
+        } else {
+            None
+        };
+        if design_axis_count > 0 && design_axis_size < 8 {
+            return Err(reader.error(format!("invalid design axis size {}", design_axis_size)));
+        }
+
+        let mut design_axes = Vec::with_capacity(design_axis_count);
+        for i in 0..design_axis_count {
+            let mut reader = reader.jump(design_axes_offset + i * design_axis_size)?;
+            design_axes.push(DesignAxis {
+                tag: reader.read_tag()?,
+                axis_name_id: reader.read_u16()?,
+                axis_ordering: reader.read_u16()?,
+            });
+        }
+
+        let mut axis_values = Vec::with_capacity(axis_value_count);
+        let mut offsets = reader.jump(offset_to_axis_value_offsets)?;
+        for _ in 0..axis_value_count {
+            let offset = offsets.read_u16()? as usize;
+            let mut reader = reader.jump(offset_to_axis_value_offsets + offset)?;
+            axis_values.extend(read_axis_value(&mut reader)?);
+        }
+
+        Ok(Self {
+            major_version,
+            minor_version,
+            design_axes,
+            axis_values,
+            elided_fallback_name_id,
+        })
+    }
+
+    pub fn from_font_data(data: &[u8]) -> Result<Option<Self>> {
+        let data = woff::decode_font_data(data)?;
+        match Sfnt::parse(&data)?.get_font_table(STAT)? {
+            Some(table) => Ok(Some(Self::parse(table)?)),
+            None => Ok(None),
+        }
+    }
+
+    pub fn from_font(font: &Font) -> Result<Option<Self>> {
+        match font.get_font_table(STAT)? {
+            Some(table) => Ok(Some(Self::parse(&table)?)),
+            None => Ok(None),
+        }
+    }
+
+    pub fn get_elided_fallback_name_id(&self) -> u16 {
+        self.elided_fallback_name_id
+            .unwrap_or(DEFAULT_ELIDED_FALLBACK_NAME_ID)
+    }
+
+    // 座標 (軸のタグとユーザー座標) に当てはまる AxisValue を axisOrdering の順に返す．
+    // format 4 を優先し，残りの軸は format 1..=3 から探す．座標が無い軸は名前に含めない．
+    pub fn get_axis_values(&self, coordinates: &[(u32, f32)]) -> Vec<&AxisValue> {
+        let get_value = |axis_index: u16| {
+            let axis = self.design_axes.get(axis_index as usize)?;
+            coordinates
+                .iter()
+                .find(|(tag, _)| *tag == axis.tag)
+                .map(|(_, value)| *value)
+        };
+        let get_ordering = |axis_index: u16| {
+            self.design_axes
+                .get(axis_index as usize)
+                .map(|axis| axis.axis_ordering)
+                .unwrap_or(u16::MAX)
+        };
+
+        let mut used = vec![false; self.design_axes.len()];
+        let mut values: Vec<(u16, &AxisValue)> = vec![];
+        for axis_value in &self.axis_values {
+            let AxisValue::Multiple { values: v, .. } = axis_value else {
+                continue;
+            };
+            let matched = !v.is_empty()
+                && v.iter().all(|&(axis_index, value)| {
+                    !used.get(axis_index as usize).copied().unwrap_or(true)
+                        && get_value(axis_index) == Some(value)
+                });
+            if matched {
+                for &(axis_index, _) in v {
+                    used[axis_index as usize] = true;
+                }
+                let ordering = v.iter().map(|&(i, _)| get_ordering(i)).min();
+                values.push((ordering.unwrap_or(u16::MAX), axis_value));
+            }
+        }
+
+        for (axis_index, is_used) in used.iter().enumerate() {
+            if *is_used {
+                continue;
+            }
+            let axis_index = axis_index as u16;
+            let Some(value) = get_value(axis_index) else {
+                continue;
+            };
+            let axis_value = self
+                .axis_values
+                .iter()
+                .find(|v| v.axis_index() == Some(axis_index) && v.matches(value));
+            if let Some(axis_value) = axis_value {
+                values.push((get_ordering(axis_index), axis_value));
+            }
+        }
+        values.sort_by_key(|(ordering, _)| *ordering);
+        values.into_iter().map(|(_, value)| value).collect()
+    }
+
+    // サブファミリー名に使う name ID．省略できる名前は除き，すべて省略された場合は elidedFallbackNameID．
+    pub fn get_style_name_ids(&self, coordinates: &[(u32, f32)]) -> Vec<u16> {
+        let name_ids: Vec<u16> = self
+            .get_axis_values(coordinates)
+            .into_iter()
+            .filter(|value| !value.is_elidable())
+            .map(|value| value.value_name_id())
+            .collect();
+        if name_ids.is_empty() {
+            vec![self.get_elided_fallback_name_id()]
+        } else {
+            name_ids
+        }
+    }
+
+    // "SemiBold Condensed Italic" のように，ロケールごとに名前を空白でつなげる．
+    // そのロケールの名前が無い部分は en-us か，最初の名前を使う．
+    pub fn get_style_names(
+        &self,
+        names: &NameTable,
+        coordinates: &[(u32, f32)],
+    ) -> Vec<LocalizedString> {
+        let parts: Vec<Vec<LocalizedString>> = self
+            .get_style_name_ids(coordinates)
+            .into_iter()
+            .map(|name_id| names.get_names(name_id))
+            .filter(|part| !part.is_empty())
+            .collect();
+
+        let mut locales: Vec<&str> = vec![];
+        for name in parts.iter().flatten() {
+            if !locales.contains(&name.locale.as_str()) {
+                locales.push(&name.locale);
+            }
+        }
+        locales
+            .into_iter()
+            .map(|locale| {
+                let strings: Vec<&str> = parts
+                    .iter()
+                    .map(|part| {
+                        let name = part
+                            .iter()
+                            .find(|name| name.locale == locale)
+                            .or_else(|| part.iter().find(|name| name.locale == "en-us"))
+                            .unwrap_or(&part[0]);
+                        name.string.as_str()
+                    })
+                    .collect();
+                LocalizedString {
+                    string: strings.join(" "),
+                    locale: locale.to_string(),
+                }
+            })
+            .collect()
+    }
+
+    pub fn get_style_strings(
+        &self,
+        names: &NameTable,
+        coordinates: &[(u32, f32)],
+    ) -> Option<LocalizedStrings> {
+        let strings = self.get_style_names(names, coordinates);
+        if strings.is_empty() {
+            None
+        } else {
+            Some(LocalizedStringSet::from(strings).into())
+        }
+    }
+
+    pub fn get_named_instance_style_names(
+        &self,
+        names: &NameTable,
+        fvar: &Fvar,
+        instance: &NamedInstance,
+    ) -> Vec<LocalizedString> {
+        self.get_style_names(names, &get_coordinates(fvar, &instance.coordinates))
+    }
+}
+
+// fvar の軸の順の座標を (タグ, 値) の組にする．
+pub fn get_coordinates(fvar: &Fvar, coordinates: &[f32]) -> Vec<(u32, f32)> {
+    fvar.axes
+        .iter()
+        .zip(coordinates)
+        .map(|(axis, value)| (axis.tag, *value))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        fvar::{FVAR, WDTH, WGHT},
+        name::NAME,
+        test_fonts::{axis_value, build_variable_font, multiple_axis_value, stat},
+    };
+
+    fn get_name(names: &[LocalizedString]) -> Vec<&str> {
+        names.iter().map(|name| name.string.as_str()).collect()
+    }
+
+    #[test]
+    fn compose_style_names() {
+        let data = build_variable_font("Test Var");
+        let sfnt = Sfnt::parse(&data).unwrap();
+        let names = NameTable::parse(sfnt.get_font_table(NAME).unwrap().unwrap()).unwrap();
+        let fvar = Fvar::parse(sfnt.get_font_table(FVAR).unwrap().unwrap()).unwrap();
+        let stat = Stat::parse(sfnt.get_font_table(STAT).unwrap().unwrap()).unwrap();
+        assert_eq!(stat.elided_fallback_name_id, Some(2));
+        assert_eq!(stat.axis_values.len(), 5);
+        let style_names: Vec<_> = fvar
+            .instances
+            .iter()
+            .map(|instance| {
+                let names = stat.get_named_instance_style_names(&names, &fvar, instance);
+                get_name(&names).join(",")
+            })
+            .collect();
+        assert_eq!(style_names, vec!["Light", "Regular", "Bold Condensed"]);
+        // 範囲に当てはまる値も名前にする．
+        assert_eq!(
+            stat.get_style_name_ids(&[(WGHT, 500.0), (WDTH, 72.0)]),
+            vec![261]
+        );
+        // 座標の無い軸は含めない．
+        assert_eq!(stat.get_style_name_ids(&[(WGHT, 300.0)]), vec![258]);
+    }
+
+    #[test]
+    fn multiple_axis_values() {
+        let data = stat(
+            &[(WGHT, 256, 1), (WDTH, 257, 0)],
+            &[
+                axis_value(1, 0, 0, 260, &[700.0]),
+                axis_value(1, 1, 0, 261, &[75.0]),
+                multiple_axis_value(0, 270, &[(0, 700.0), (1, 75.0)]),
+            ],
+            2,
+        );
+        let stat = Stat::parse(&data).unwrap();
+        assert_eq!(
+            stat.get_style_name_ids(&[(WGHT, 700.0), (WDTH, 75.0)]),
+            vec![270]
+        );
+        // format 4 が当てはまらなければ軸ごとの値を axisOrdering の順に並べる．
+        assert_eq!(
+            stat.get_style_name_ids(&[(WGHT, 700.0), (WDTH, 100.0)]),
+            vec![260]
+        );
+        // 途中で切れたテーブルはエラーにする．
+        assert!(Stat::parse(&data[..data.len() - 4]).is_err());
+    }
+
+    // 知らない format の AxisValue は読み飛ばす．
+    #[test]
+    fn skip_unknown_axis_value_format() {
+        let data = stat(
+            &[(WGHT, 256, 0)],
+            &[
+                axis_value(5, 0, 0, 300, &[700.0]),
+                axis_value(1, 0, 0, 260, &[700.0]),
+            ],
+            2,
+        );
+        let stat = Stat::parse(&data).unwrap();
+        assert_eq!(stat.axis_values.len(), 1);
+        assert_eq!(stat.get_style_name_ids(&[(WGHT, 700.0)]), vec![260]);
+        assert_eq!(stat.get_style_name_ids(&[(WGHT, 400.0)]), vec![2]);
+    }
+}
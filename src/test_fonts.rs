@@ -0,0 +1,393 @@
+use crate::{
+    cmap::CMAP,
+    fvar::{AVAR, FVAR, WDTH, WGHT},
+    name::NAME,
+    os2::OS2,
+    sfnt::{build_collection, build_sfnt, make_tag, update_checksum_adjustment, HEAD},
+    stat::{ELIDABLE_AXIS_VALUE_NAME, STAT},
+    woff2::{GLYF, HHEA, HMTX, LOCA, MAXP},
+};
+
+// テストで使う小さな TrueType フォント．
+// グリフは空の .notdef，四角形，重なりのフラグと命令を持つ単純グリフ，二つの部品と命令を持つ複合グリフ．
+// 'A'，'B'，'C' が 1，2，3 番のグリフになる．
+
+pub const POST: u32 = make_tag(b"post");
+
+pub const TRUETYPE: u32 = 0x00010000;
+
+pub const FS_SELECTION_ITALIC: u16 = 0x0001;
+pub const FS_SELECTION_BOLD: u16 = 0x0020;
+pub const FS_SELECTION_REGULAR: u16 = 0x0040;
+
+// glyf は WOFF2 の復元と同じ形で書く．各グリフは 4 バイト境界に揃える．
+const GLYF_DATA: [u8; 84] = [
+    // 1: (100, 0) (500, 0) (500, 700) (100, 700)
+    0x00, 0x01, 0x00, 0x64, 0x00, 0x00, 0x01, 0xF4, 0x02, 0xBC, // numberOfContours, bbox
+    0x00, 0x03, 0x00, 0x00, // endPtsOfContours, instructionLength
+    0x33, 0x21, 0x11, 0x21, // flags
+    0x64, 0x01, 0x90, 0xFE, 0x70, // x
+    0x02, 0xBC, 0x00, 0x00, 0x00, // y, padding
+    // 2: (0, 0) (50, 100) (100, 200) (150, 300)．最初の点に OVERLAP_SIMPLE．
+    0x00, 0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x96, 0x01, 0x2C, // numberOfContours, bbox
+    0x00, 0x03, 0x00, 0x02, 0xB0, 0x00, // endPtsOfContours, instructions
+    0x71, 0x3F, 0x02, // flags (2 回繰り返し)
+    0x32, 0x32, 0x32, // x
+    0x64, 0x64, 0x64, 0x00, 0x00, 0x00, // y, padding
+    // 3: 1 と 2 を (0, 0) と (10, 20) に置く．
+    0xFF, 0xFF, 0x00, 0x00, 0x00, 0x00, 0x01, 0xF4, 0x02, 0xBC, // numberOfContours, bbox
+    0x00, 0x23, 0x00, 0x01, 0x00, 0x00, 0x00, 0x00, // WORDS | XY_VALUES | MORE_COMPONENTS
+    0x01, 0x02, 0x00, 0x02, 0x0A, 0x14, // XY_VALUES | WE_HAVE_INSTRUCTIONS
+    0x00, 0x02, 0xB0, 0x05, // instructions
+];
+
+// 短い形式の loca．
+const LOCA_DATA: [u8; 10] = [0x00, 0x00, 0x00, 0x00, 0x00, 0x0E, 0x00, 0x1C, 0x00, 0x2A];
+
+// lsb は全てグリフの xMin と同じなので，WOFF2 では省略される．
+const HMTX_DATA: [u8; 12] = [
+    0x01, 0xF4, 0x00, 0x00, 0x02, 0x58, 0x00, 0x64, 0x00, 0x00, 0x00, 0x00,
+];
+
+struct Writer(Vec<u8>);
+
+impl Writer {
+    fn u16(&mut self, value: u16) -> &mut Self {
+        self.0.extend_from_slice(&value.to_be_bytes());
+        self
+    }
+
+    fn u32(&mut self, value: u32) -> &mut Self {
+        self.0.extend_from_slice(&value.to_be_bytes());
+        self
+    }
+
+    fn zeros(&mut self, len: usize) -> &mut Self {
+        self.0.resize(self.0.len() + len, 0);
+        self
+    }
+
+    fn fixed(&mut self, value: f32) -> &mut Self {
+        self.u32((value * 65536.0).round() as i32 as u32)
+    }
+
+    fn f2dot14(&mut self, value: f32) -> &mut Self {
+        self.u16((value * 16384.0).round() as i16 as u16)
+    }
+}
+
+fn head(mac_style: u16) -> Vec<u8> {
+    let mut head = Writer(vec![]);
+    head.u32(0x00010000).u32(0x00010000).u32(0).u32(0x5F0F3CF5);
+    head.u16(0x0003).u16(1000).zeros(16);
+    head.u16(0).u16(0).u16(500).u16(700);
+    head.u16(mac_style).u16(8).u16(2).u16(0).u16(0);
+    head.0
+}
+
+fn hhea() -> Vec<u8> {
+    let mut hhea = Writer(vec![]);
+    hhea.u32(0x00010000).u16(800).u16(-200_i16 as u16).u16(0);
+    hhea.u16(600).u16(0).u16(0).u16(500);
+    hhea.u16(1).u16(0).u16(0).zeros(8).u16(0).u16(2);
+    hhea.0
+}
+
+fn maxp() -> Vec<u8> {
+    let mut maxp = Writer(vec![]);
+    maxp.u32(0x00010000).u16(4).zeros(26);
+    maxp.0
+}
+
+// (3, 1) の format 4 で 'A'..='C' を 1..=3 にする．
+fn cmap() -> Vec<u8> {
+    let mut cmap = Writer(vec![]);
+    cmap.u16(0).u16(1).u16(3).u16(1).u32(12);
+    cmap.u16(4).u16(32).u16(0).u16(4).u16(4).u16(1).u16(0);
+    cmap.u16(0x43).u16(0xFFFF).u16(0).u16(0x41).u16(0xFFFF);
+    cmap.u16(1_u16.wrapping_sub(0x41)).u16(1).u16(0).u16(0);
+    cmap.0
+}
+
+pub fn os2(weight_class: u16, width_class: u16, fs_selection: u16) -> Vec<u8> {
+    let mut os2 = Writer(vec![]);
+    os2.u16(4)
+        .u16(500)
+        .u16(weight_class)
+        .u16(width_class)
+        .u16(0);
+    os2.zeros(48).0.extend_from_slice(b"TEST");
+    os2.u16(fs_selection).u16(0x41).u16(0x43);
+    os2.u16(800).u16(-200_i16 as u16).u16(0).u16(800).u16(200);
+    // Latin 1 と Shift JIS．
+    os2.u32(0x0002_0001).u32(0);
+    os2.u16(500).u16(700).u16(0).u16(0x20).u16(2);
+    os2.0
+}
+
+fn post() -> Vec<u8> {
+    let mut post = Writer(vec![]);
+    post.u32(0x00030000).zeros(28);
+    post.0
+}
+
+// Windows の名前．(言語 ID, 名前 ID, 文字列) の列．
+fn name(records: &[(u16, u16, &str)]) -> Vec<u8> {
+    let mut name = Writer(vec![]);
+    let mut storage = vec![];
+    name.u16(0)
+        .u16(records.len() as u16)
+        .u16(6 + 12 * records.len() as u16);
+    for (language_id, name_id, string) in records {
+        let bytes: Vec<u8> = string.encode_utf16().flat_map(u16::to_be_bytes).collect();
+        name.u16(3).u16(1).u16(*language_id).u16(*name_id);
+        name.u16(bytes.len() as u16).u16(storage.len() as u16);
+        storage.extend(bytes);
+    }
+    name.0.extend(storage);
+    name.0
+}
+
+// 英語名と，ファミリー名だけ日本語名．
+fn family_name(family: &str, subfamily: &str) -> Vec<u8> {
+    let full_name = format!("{} {}", family, subfamily);
+    let postscript_name = full_name.replace(' ', "-");
+    name(&[
+        (0x0409, 1, family),
+        (0x0409, 2, subfamily),
+        (0x0409, 4, full_name.as_str()),
+        (0x0409, 6, postscript_name.as_str()),
+        (0x0411, 1, "テスト"),
+    ])
+}
+
+fn mac_style(fs_selection: u16) -> u16 {
+    let bold = (fs_selection & FS_SELECTION_BOLD != 0) as u16;
+    let italic = (fs_selection & FS_SELECTION_ITALIC != 0) as u16;
+    bold | italic << 1
+}
+
+// フェイスごとに変わらないテーブル．
+fn shared_tables() -> Vec<(u32, Vec<u8>)> {
+    vec![
+        (HHEA, hhea()),
+        (MAXP, maxp()),
+        (HMTX, HMTX_DATA.to_vec()),
+        (CMAP, cmap()),
+        (POST, post()),
+        (GLYF, GLYF_DATA.to_vec()),
+        (LOCA, LOCA_DATA.to_vec()),
+    ]
+}
+
+pub fn build_font(family: &str, subfamily: &str, weight_class: u16, fs_selection: u16) -> Vec<u8> {
+    build_font_with_width(family, subfamily, weight_class, 5, fs_selection)
+}
+
+pub fn build_font_with_width(
+    family: &str,
+    subfamily: &str,
+    weight_class: u16,
+    width_class: u16,
+    fs_selection: u16,
+) -> Vec<u8> {
+    build_font_with_tables(
+        family_name(family, subfamily),
+        os2(weight_class, width_class, fs_selection),
+        vec![],
+    )
+}
+
+fn build_font_with_tables(
+    name: Vec<u8>,
+    os2: Vec<u8>,
+    extra_tables: Vec<(u32, Vec<u8>)>,
+) -> Vec<u8> {
+    let fs_selection = u16::from_be_bytes([os2[62], os2[63]]);
+    let mut tables = shared_tables();
+    tables.push((HEAD, head(mac_style(fs_selection))));
+    tables.push((NAME, name));
+    tables.push((OS2, os2));
+    tables.extend(extra_tables);
+    let tables: Vec<_> = tables
+        .iter()
+        .map(|(tag, data)| (*tag, None, &data[..]))
+        .collect();
+    let mut font = build_sfnt(TRUETYPE, &tables);
+    update_checksum_adjustment(&mut font).unwrap();
+    font
+}
+
+// fvar．axes は (タグ, min, default, max, 名前 ID)，instances は (サブファミリー名 ID, 座標)．
+pub fn fvar(axes: &[(u32, f32, f32, f32, u16)], instances: &[(u16, &[f32])]) -> Vec<u8> {
+    let mut fvar = Writer(vec![]);
+    let instance_size = axes.len() as u16 * 4 + 6;
+    fvar.u16(1).u16(0).u16(16).u16(2);
+    fvar.u16(axes.len() as u16).u16(20);
+    fvar.u16(instances.len() as u16).u16(instance_size);
+    for (tag, min, default, max, name_id) in axes {
+        fvar.0.extend_from_slice(&tag.to_le_bytes());
+        fvar.fixed(*min)
+            .fixed(*default)
+            .fixed(*max)
+            .u16(0)
+            .u16(*name_id);
+    }
+    for (name_id, coordinates) in instances {
+        fvar.u16(*name_id).u16(0);
+        for value in *coordinates {
+            fvar.fixed(*value);
+        }
+        fvar.u16(0xFFFF);
+    }
+    fvar.0
+}
+
+// avar．軸ごとの (from, to) の列．
+pub fn avar(segment_maps: &[&[(f32, f32)]]) -> Vec<u8> {
+    let mut avar = Writer(vec![]);
+    avar.u16(1).u16(0).u16(0).u16(segment_maps.len() as u16);
+    for maps in segment_maps {
+        avar.u16(maps.len() as u16);
+        for (from, to) in *maps {
+            avar.f2dot14(*from).f2dot14(*to);
+        }
+    }
+    avar.0
+}
+
+// STAT の AxisValue．format 1..=3 は (軸, flags, 名前 ID) の後に値が続く．
+pub fn axis_value(
+    format: u16,
+    axis_index: u16,
+    flags: u16,
+    name_id: u16,
+    values: &[f32],
+) -> Vec<u8> {
+    let mut axis_value = Writer(vec![]);
+    axis_value
+        .u16(format)
+        .u16(axis_index)
+        .u16(flags)
+        .u16(name_id);
+    for value in values {
+        axis_value.fixed(*value);
+    }
+    axis_value.0
+}
+
+// format 4．values は (軸, 値) の列．
+pub fn multiple_axis_value(flags: u16, name_id: u16, values: &[(u16, f32)]) -> Vec<u8> {
+    let mut axis_value = Writer(vec![]);
+    axis_value
+        .u16(4)
+        .u16(values.len() as u16)
+        .u16(flags)
+        .u16(name_id);
+    for (axis_index, value) in values {
+        axis_value.u16(*axis_index).fixed(*value);
+    }
+    axis_value.0
+}
+
+// STAT 1.1．axes は (タグ, 名前 ID, 並び順)．
+pub fn stat(
+    axes: &[(u32, u16, u16)],
+    axis_values: &[Vec<u8>],
+    elided_fallback_name_id: u16,
+) -> Vec<u8> {
+    let mut stat = Writer(vec![]);
+    let axis_value_offsets = 20 + axes.len() as u32 * 8;
+    stat.u16(1).u16(1).u16(8).u16(axes.len() as u16).u32(20);
+    stat.u16(axis_values.len() as u16).u32(axis_value_offsets);
+    stat.u16(elided_fallback_name_id);
+    for (tag, name_id, ordering) in axes {
+        stat.0.extend_from_slice(&tag.to_le_bytes());
+        stat.u16(*name_id).u16(*ordering);
+    }
+    let mut offset = axis_values.len() * 2;
+    for axis_value in axis_values {
+        stat.u16(offset as u16);
+        offset += axis_value.len();
+    }
+    for axis_value in axis_values {
+        stat.0.extend_from_slice(axis_value);
+    }
+    stat.0
+}
+
+// wght (300..=700) と wdth (75..=100) の軸を持つ可変フォント．
+// 名前付きインスタンスは Light，Regular，Condensed Bold で，STAT からは Light，Regular，Bold Condensed になる．
+pub fn build_variable_font(family: &str) -> Vec<u8> {
+    let names = [
+        (1, family),
+        (2, "Regular"),
+        (256, "Weight"),
+        (257, "Width"),
+        (258, "Light"),
+        (259, "Regular"),
+        (260, "Bold"),
+        (261, "Condensed"),
+        (262, "Normal"),
+        (263, "Condensed Bold"),
+    ];
+    let records: Vec<_> = names
+        .iter()
+        .map(|(name_id, string)| (0x0409, *name_id, *string))
+        .collect();
+    let fvar = fvar(
+        &[
+            (WGHT, 300.0, 400.0, 700.0, 256),
+            (WDTH, 75.0, 100.0, 100.0, 257),
+        ],
+        &[
+            (258, &[300.0, 100.0]),
+            (259, &[400.0, 100.0]),
+            (263, &[700.0, 75.0]),
+        ],
+    );
+    let stat = stat(
+        &[(WGHT, 256, 0), (WDTH, 257, 1)],
+        &[
+            axis_value(1, 0, 0, 258, &[300.0]),
+            axis_value(3, 0, ELIDABLE_AXIS_VALUE_NAME, 259, &[400.0, 700.0]),
+            axis_value(1, 0, 0, 260, &[700.0]),
+            axis_value(1, 1, ELIDABLE_AXIS_VALUE_NAME, 262, &[100.0]),
+            axis_value(2, 1, 0, 261, &[75.0, 70.0, 80.0]),
+        ],
+        2,
+    );
+    let avar = avar(&[
+        &[(-1.0, -1.0), (0.0, 0.0), (1.0, 1.0)],
+        &[(-1.0, -1.0), (0.0, 0.0), (1.0, 1.0)],
+    ]);
+    build_font_with_tables(
+        name(&records),
+        os2(400, 5, FS_SELECTION_REGULAR),
+        vec![(FVAR, fvar), (STAT, stat), (AVAR, avar)],
+    )
+}
+
+// Regular と Bold の二つのフェイスを持つ TrueType コレクション．name と OS/2 以外のテーブルは共有する．
+pub fn build_ttc(family: &str) -> Vec<u8> {
+    let mut tables = shared_tables();
+    tables.push((HEAD, head(0)));
+    let shared: Vec<usize> = (0..tables.len()).collect();
+    let mut fonts = vec![];
+    for (subfamily, weight_class, fs_selection) in [
+        ("Regular", 400, FS_SELECTION_REGULAR),
+        ("Bold", 700, FS_SELECTION_BOLD),
+    ] {
+        let mut indices = shared.clone();
+        indices.push(tables.len());
+        tables.push((NAME, family_name(family, subfamily)));
+        indices.push(tables.len());
+        tables.push((OS2, os2(weight_class, 5, fs_selection)));
+        fonts.push((TRUETYPE, indices));
+    }
+    let tables: Vec<_> = tables
+        .iter()
+        .map(|(tag, data)| (*tag, None, &data[..]))
+        .collect();
+    build_collection(2, &fonts, &tables)
+}
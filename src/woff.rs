@@ -0,0 +1,417 @@
+use std::{
+    borrow::Cow,
+    io::{Read, Write},
+};
+
+use flate2::{read::ZlibDecoder, write::ZlibEncoder, Compression};
+
+use crate::{
+    error::{Error, Result},
+    font::Font,
+    sfnt::{build_sfnt, is_collection, make_tag, tag_to_string, Reader, Sfnt, HEAD},
+    woff2,
+};
+
+// WOFF 1.0 のコンテナ．テーブルを展開して sfnt に戻し，sfnt から WOFF を作る．
+
+pub const WOFF: u32 = make_tag(b"wOFF");
+
+const HEADER_SIZE: usize = 44;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct WoffHeader {
+    // 元の sfnt の sfntVersion．
+    pub flavor: u32,
+    pub length: u32,
+    pub num_tables: u16,
+    pub total_sfnt_size: u32,
+    // フォントのバージョンで，WOFF のバージョンではない．
+    pub major_version: u16,
+    pub minor_version: u16,
+    pub meta_offset: u32,
+    pub meta_length: u32,
+    pub meta_orig_length: u32,
+    pub priv_offset: u32,
+    pub priv_length: u32,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct WoffTableEntry {
+    pub tag: u32,
+    pub offset: u32,
+    pub comp_length: u32,
+    pub orig_length: u32,
+    pub orig_checksum: u32,
+}
+
+impl WoffTableEntry {
+    // 圧縮しても小さくならないテーブルはそのまま格納される．
+    pub fn is_compressed(&self) -> bool {
+        self.comp_length < self.orig_length
+    }
+}
+
+pub fn is_woff(data: &[u8]) -> bool {
+    data.len() >= 4 && make_tag(&[data[0], data[1], data[2], data[3]]) == WOFF
+}
+
+// 展開後の大きさが expected_length と一致しない場合はエラー．
+pub(crate) fn decompress_zlib(data: &[u8], expected_length: usize) -> Result<Vec<u8>> {
+    // 長さはヘッダーの値なので，大きすぎる値で確保しないよう圧縮率で抑え，残りは take に任せる．
+    let mut out = Vec::with_capacity(expected_length.min(data.len().saturating_mul(16)));
+    ZlibDecoder::new(data)
+        .take(expected_length as u64 + 1)
+        .read_to_end(&mut out)
+        .map_err(|e| Error::InvalidFont(format!("zlib: {}", e)))?;
+    if out.len() != expected_length {
+        return Err(Error::InvalidFont(format!(
+            "decompressed size is {} bytes, expected {}",
+            out.len(),
+            expected_length
+        )));
+    }
+    Ok(out)
+}
+
+#[derive(Debug, Clone)]
+pub struct Woff<'a> {
+    data: &'a [u8],
+    pub header: WoffHeader,
+    pub tables: Vec<WoffTableEntry>,
+}
+
+impl<'a> Woff<'a> {
+    pub fn parse(data: &'a [u8]) -> Result<Self> {
+        let mut reader = Reader::new(data);
+        let signature = reader.read_tag()?;
+        if signature != WOFF {
+            return Err(Error::UnsupportedFileType(format!(
+                "not a WOFF file ('{}')",
+                tag_to_string(signature)
+            )));
+        }
+        let flavor = reader.read_u32()?;
+        let length = reader.read_u32()?;
+        let num_tables = reader.read_u16()?;
+        let reserved = reader.read_u16()?;
+        let header = WoffHeader {
+            flavor,
+            length,
+            num_tables,
+            total_sfnt_size: reader.read_u32()?,
+            major_version: reader.read_u16()?,
+            minor_version: reader.read_u16()?,
+            meta_offset: reader.read_u32()?,
+            meta_length: reader.read_u32()?,
+            meta_orig_length: reader.read_u32()?,
+            priv_offset: reader.read_u32()?,
+            priv_length: reader.read_u32()?,
+        };
+        if reserved != 0 {
+            return Err(reader.error(format!("reserved field is {}", reserved)));
+        }
+        if length as usize != data.len() {
+            return Err(reader.error(format!(
+                "length field is {} but the file is {} bytes",
+                length,
+                data.len()
+            )));
+        }
+
+        let mut reader = reader.jump(HEADER_SIZE)?;
+        let mut tables = Vec::with_capacity(num_tables as usize);
+        for _ in 0..num_tables {
+            let entry = WoffTableEntry {
+                tag: reader.read_tag()?,
+                offset: reader.read_u32()?,
+                comp_length: reader.read_u32()?,
+                orig_length: reader.read_u32()?,
+                orig_checksum: reader.read_u32()?,
+            };
+            if entry.comp_length > entry.orig_length {
+                return Err(reader.error(format!(
+                    "'{}' table is larger compressed ({} bytes) than uncompressed ({} bytes)",
+                    tag_to_string(entry.tag),
+                    entry.comp_length,
+                    entry.orig_length
+                )));
+            }
+            Self::get_block(data, entry.offset, entry.comp_length)?;
+            tables.push(entry);
+        }
+
+        let woff = Self {
+            data,
+            header,
+            tables,
+        };
+        woff.get_metadata_block()?;
+        woff.get_private_data()?;
+        Ok(woff)
+    }
+
+    fn get_block(data: &'a [u8], offset: u32, length: u32) -> Result<&'a [u8]> {
+        let start = offset as usize;
+        let end = start + length as usize;
+        data.get(start..end).ok_or_else(|| {
+            Error::InvalidFont(format!(
+                "block {}..{} is out of range ({} bytes)",
+                start,
+                end,
+                data.len()
+            ))
+        })
+    }
+
+    fn get_metadata_block(&self) -> Result<Option<&'a [u8]>> {
+        if self.header.meta_length == 0 {
+            return Ok(None);
+        }
+        Self::get_block(self.data, self.header.meta_offset, self.header.meta_length).map(Some)
+    }
+
+    pub fn get_table_entry(&self, tag: u32) -> Option<&WoffTableEntry> {
+        self.tables.iter().find(|entry| entry.tag == tag)
+    }
+
+    pub fn has_table(&self, tag: u32) -> bool {
+        self.get_table_entry(tag).is_some()
+    }
+
+    // 展開したテーブル．テーブルが存在しない場合は None を返す．
+    pub fn get_font_table(&self, tag: u32) -> Result<Option<Cow<'a, [u8]>>> {
+        let Some(entry) = self.get_table_entry(tag) else {
+            return Ok(None);
+        };
+        let block = Self::get_block(self.data, entry.offset, entry.comp_length)?;
+        if !entry.is_compressed() {
+            return Ok(Some(Cow::Borrowed(block)));
+        }
+        let table = decompress_zlib(block, entry.orig_length as usize)
+            .map_err(|e| Error::malformed_table(tag, e.to_string()))?;
+        Ok(Some(Cow::Owned(table)))
+    }
+
+    // 拡張メタデータの XML．
+    pub fn get_metadata(&self) -> Result<Option<String>> {
+        let Some(block) = self.get_metadata_block()? else {
+            return Ok(None);
+        };
+        let xml = decompress_zlib(block, self.header.meta_orig_length as usize)?;
+        String::from_utf8(xml)
+            .map(Some)
+            .map_err(|e| Error::StringConversion(format!("WOFF metadata: {}", e)))
+    }
+
+    pub fn get_private_data(&self) -> Result<Option<&'a [u8]>> {
+        if self.header.priv_length == 0 {
+            return Ok(None);
+        }
+        Self::get_block(self.data, self.header.priv_offset, self.header.priv_length).map(Some)
+    }
+
+    // 元のチェックサムを保ったまま sfnt を組み立てる．
+    pub fn to_sfnt(&self) -> Result<Vec<u8>> {
+        let tables = self
+            .tables
+            .iter()
+            .map(|entry| {
+                let table = self.get_font_table(entry.tag)?.unwrap_or_default();
+                Ok((entry.tag, entry.orig_checksum, table))
+            })
+            .collect::<Result<Vec<_>>>()?;
+        let tables: Vec<_> = tables
+            .iter()
+            .map(|(tag, checksum, table)| (*tag, Some(*checksum), table.as_ref()))
+            .collect();
+        Ok(build_sfnt(self.header.flavor, &tables))
+    }
+}
+
+pub fn decode(data: &[u8]) -> Result<Vec<u8>> {
+    Woff::parse(data)?.to_sfnt()
+}
+
+// WOFF, WOFF2 の場合は sfnt (WOFF2 のコレクションは TTC) に変換し，それ以外はそのまま返す．
+pub fn decode_font_data(data: &[u8]) -> Result<Cow<'_, [u8]>> {
+    if is_woff(data) {
+        Ok(Cow::Owned(decode(data)?))
+    } else if woff2::is_woff2(data) {
+        Ok(Cow::Owned(woff2::decode(data)?))
+    } else {
+        Ok(Cow::Borrowed(data))
+    }
+}
+
+pub(crate) fn compress_zlib(data: &[u8]) -> Vec<u8> {
+    let mut encoder = ZlibEncoder::new(Vec::new(), Compression::best());
+    // Vec への書き込みは失敗しない．
+    encoder.write_all(data).unwrap();
+    encoder.finish().unwrap()
+}
+
+fn pad(out: &mut Vec<u8>) {
+    out.resize(out.len().next_multiple_of(4), 0);
+}
+
+pub fn encode(data: &[u8]) -> Result<Vec<u8>> {
+    encode_with(data, None, None)
+}
+
+// 一つのフェイスの sfnt から WOFF を作る．WOFF 1.0 はコレクションを格納できない．
+// metadata は拡張メタデータの XML．
+pub fn encode_with(
+    data: &[u8],
+    metadata: Option<&str>,
+    private_data: Option<&[u8]>,
+) -> Result<Vec<u8>> {
+    if is_collection(data) {
+        return Err(Error::NotSupported(
+            "WOFF 1.0 cannot contain a font collection".to_string(),
+        ));
+    }
+    let sfnt = Sfnt::parse(data)?;
+    let mut records = sfnt.tables.clone();
+    records.sort_by_key(|record| record.tag.swap_bytes());
+
+    // フォントのバージョンは head の fontRevision を使う．
+    let (major_version, minor_version) = match sfnt.get_font_table(HEAD)? {
+        Some(head) => {
+            let mut reader = Reader::table(HEAD, head).jump(4)?;
+            (reader.read_u16()?, reader.read_u16()?)
+        }
+        None => (0, 0),
+    };
+
+    let num_tables = records.len();
+    let mut total_sfnt_size = 12 + 16 * num_tables;
+    let mut directory = Vec::with_capacity(num_tables * 20);
+    let mut body = Vec::new();
+    let body_offset = HEADER_SIZE + num_tables * 20;
+    for record in &records {
+        let table = sfnt.get_font_table(record.tag)?.unwrap_or_default();
+        let compressed = compress_zlib(table);
+        let stored = if compressed.len() < table.len() {
+            &compressed[..]
+        } else {
+            table
+        };
+        directory.extend_from_slice(&record.tag.to_le_bytes());
+        directory.extend_from_slice(&((body_offset + body.len()) as u32).to_be_bytes());
+        directory.extend_from_slice(&(stored.len() as u32).to_be_bytes());
+        directory.extend_from_slice(&(table.len() as u32).to_be_bytes());
+        directory.extend_from_slice(&record.checksum.to_be_bytes());
+        body.extend_from_slice(stored);
+        pad(&mut body);
+        total_sfnt_size += table.len().next_multiple_of(4);
+    }
+
+    let (meta_offset, meta_length, meta_orig_length) = match metadata {
+        Some(metadata) => {
+            let compressed = compress_zlib(metadata.as_bytes());
+            let offset = body_offset + body.len();
+            body.extend_from_slice(&compressed);
+            (offset, compressed.len(), metadata.len())
+        }
+        None => (0, 0, 0),
+    };
+    let (priv_offset, priv_length) = match private_data {
+        Some(private_data) => {
+            pad(&mut body);
+            let offset = body_offset + body.len();
+            body.extend_from_slice(private_data);
+            (offset, private_data.len())
+        }
+        None => (0, 0),
+    };
+
+    let length = body_offset + body.len();
+    let mut out = Vec::with_capacity(length);
+    out.extend_from_slice(&WOFF.to_le_bytes());
+    out.extend_from_slice(&sfnt.sfnt_version.to_be_bytes());
+    out.extend_from_slice(&(length as u32).to_be_bytes());
+    out.extend_from_slice(&(num_tables as u16).to_be_bytes());
+    out.extend_from_slice(&0_u16.to_be_bytes());
+    out.extend_from_slice(&(total_sfnt_size as u32).to_be_bytes());
+    out.extend_from_slice(&major_version.to_be_bytes());
+    out.extend_from_slice(&minor_version.to_be_bytes());
+    for value in [
+        meta_offset,
+        meta_length,
+        meta_orig_length,
+        priv_offset,
+        priv_length,
+    ] {
+        out.extend_from_slice(&(value as u32).to_be_bytes());
+    }
+    out.extend(directory);
+    out.extend(body);
+    Ok(out)
+}
+
+// Font::get_filepath のファイルから，そのフェイスの WOFF を作る．
+pub fn encode_font(font: &Font) -> Result<Vec<u8>> {
+    encode(&font.read_face_data()?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_fonts::{build_font, build_ttc, FS_SELECTION_REGULAR};
+
+    // woff を展開したテーブルが sfnt のテーブルと同じバイト列かどうか調べる．
+    fn check_round_trip(sfnt: &[u8], woff: &[u8]) {
+        let sfnt = Sfnt::parse(sfnt).unwrap();
+        let woff = Woff::parse(woff).unwrap();
+        assert_eq!(sfnt.tables.len(), woff.tables.len());
+        for record in &sfnt.tables {
+            let expected = sfnt.get_font_table(record.tag).unwrap();
+            let actual = woff.get_font_table(record.tag).unwrap();
+            assert_eq!(expected, actual.as_deref(), "{}", tag_to_string(record.tag));
+            let entry = woff.get_table_entry(record.tag).unwrap();
+            assert_eq!(entry.orig_checksum, record.checksum);
+        }
+    }
+
+    #[test]
+    fn round_trip() {
+        let font = build_font("Test Sans", "Regular", 400, FS_SELECTION_REGULAR);
+        let woff = encode(&font).unwrap();
+        assert!(is_woff(&woff));
+        check_round_trip(&font, &woff);
+        // 展開した sfnt も同じテーブルを持つ．
+        let decoded = decode(&woff).unwrap();
+        check_round_trip(&decoded, &woff);
+        assert_eq!(decode_font_data(&font).unwrap(), font);
+    }
+
+    #[test]
+    fn round_trip_with_metadata() {
+        let font = build_font("Test Sans", "Regular", 400, FS_SELECTION_REGULAR);
+        let woff =
+            encode_with(&font, Some("<metadata version=\"1.0\"/>"), Some(b"private")).unwrap();
+        check_round_trip(&font, &woff);
+        let woff = Woff::parse(&woff).unwrap();
+        assert_eq!(
+            woff.get_metadata().unwrap().as_deref(),
+            Some("<metadata version=\"1.0\"/>")
+        );
+        assert_eq!(woff.get_private_data().unwrap(), Some(&b"private"[..]));
+    }
+
+    // ヘッダーの長さが大きすぎても，その分を確保せずにエラーにする．
+    #[test]
+    fn decompressed_size_mismatch() {
+        let compressed = compress_zlib(b"abcd");
+        assert_eq!(decompress_zlib(&compressed, 4).unwrap(), b"abcd");
+        assert!(decompress_zlib(&compressed, 5).is_err());
+        assert!(decompress_zlib(&compressed, u32::MAX as usize).is_err());
+        assert!(decompress_zlib(b"not zlib", 16).is_err());
+    }
+
+    #[test]
+    fn collection_is_not_supported() {
+        let ttc = build_ttc("Test Sans");
+        assert!(matches!(encode(&ttc), Err(Error::NotSupported(_))));
+    }
+}
@@ -0,0 +1,1477 @@
+use std::io::Read;
+
+use brotli::enc::{backward_references::BrotliEncoderMode, BrotliCompress, BrotliEncoderParams};
+
+use crate::{
+    error::{Error, Result},
+    font::Font,
+    sfnt::{
+        build_collection, build_sfnt, is_collection, make_tag, tag_to_string,
+        update_checksum_adjustment, Reader, Sfnt, TableRecord, TtcHeader, HEAD,
+    },
+};
+
+// WOFF2 のコンテナ．Brotli で圧縮されたテーブルを展開し，glyf/loca と hmtx の変換を戻して sfnt を作る．
+// 逆に sfnt から，glyf/loca を変換した WOFF2 を作る．
+
+pub const WOFF2: u32 = make_tag(b"wOF2");
+
+pub const GLYF: u32 = make_tag(b"glyf");
+pub const LOCA: u32 = make_tag(b"loca");
+pub const HMTX: u32 = make_tag(b"hmtx");
+pub const HHEA: u32 = make_tag(b"hhea");
+pub const MAXP: u32 = make_tag(b"maxp");
+
+// flavor は sfntVersion と同じくビッグエンディアンの数値として読む．
+const TTCF_FLAVOR: u32 = u32::from_be_bytes(*b"ttcf");
+
+// テーブルディレクトリで 1 バイトで表せるタグ．63 の場合は続く 4 バイトがタグ．
+pub const KNOWN_TAGS: [u32; 63] = [
+    make_tag(b"cmap"),
+    make_tag(b"head"),
+    make_tag(b"hhea"),
+    make_tag(b"hmtx"),
+    make_tag(b"maxp"),
+    make_tag(b"name"),
+    make_tag(b"OS/2"),
+    make_tag(b"post"),
+    make_tag(b"cvt "),
+    make_tag(b"fpgm"),
+    make_tag(b"glyf"),
+    make_tag(b"loca"),
+    make_tag(b"prep"),
+    make_tag(b"CFF "),
+    make_tag(b"VORG"),
+    make_tag(b"EBDT"),
+    make_tag(b"EBLC"),
+    make_tag(b"gasp"),
+    make_tag(b"hdmx"),
+    make_tag(b"kern"),
+    make_tag(b"LTSH"),
+    make_tag(b"PCLT"),
+    make_tag(b"VDMX"),
+    make_tag(b"vhea"),
+    make_tag(b"vmtx"),
+    make_tag(b"BASE"),
+    make_tag(b"GDEF"),
+    make_tag(b"GPOS"),
+    make_tag(b"GSUB"),
+    make_tag(b"EBSC"),
+    make_tag(b"JSTF"),
+    make_tag(b"MATH"),
+    make_tag(b"CBDT"),
+    make_tag(b"CBLC"),
+    make_tag(b"COLR"),
+    make_tag(b"CPAL"),
+    make_tag(b"SVG "),
+    make_tag(b"sbix"),
+    make_tag(b"acnt"),
+    make_tag(b"avar"),
+    make_tag(b"bdat"),
+    make_tag(b"bloc"),
+    make_tag(b"bsln"),
+    make_tag(b"cvar"),
+    make_tag(b"fdsc"),
+    make_tag(b"feat"),
+    make_tag(b"fmtx"),
+    make_tag(b"fvar"),
+    make_tag(b"gvar"),
+    make_tag(b"hsty"),
+    make_tag(b"just"),
+    make_tag(b"lcar"),
+    make_tag(b"mort"),
+    make_tag(b"morx"),
+    make_tag(b"opbd"),
+    make_tag(b"prop"),
+    make_tag(b"trak"),
+    make_tag(b"Zapf"),
+    make_tag(b"Silf"),
+    make_tag(b"Glat"),
+    make_tag(b"Gloc"),
+    make_tag(b"Feat"),
+    make_tag(b"Sill"),
+];
+
+// 単純グリフの点 (x, y, on-curve)．
+type Point = (i32, i32, bool);
+
+// 単純グリフの flags．
+const ON_CURVE_POINT: u8 = 0x01;
+const X_SHORT_VECTOR: u8 = 0x02;
+const Y_SHORT_VECTOR: u8 = 0x04;
+const REPEAT_FLAG: u8 = 0x08;
+const X_IS_SAME_OR_POSITIVE_X_SHORT_VECTOR: u8 = 0x10;
+const Y_IS_SAME_OR_POSITIVE_Y_SHORT_VECTOR: u8 = 0x20;
+const OVERLAP_SIMPLE: u8 = 0x40;
+
+// 複合グリフの flags．
+const ARG_1_AND_2_ARE_WORDS: u16 = 0x0001;
+const WE_HAVE_A_SCALE: u16 = 0x0008;
+const MORE_COMPONENTS: u16 = 0x0020;
+const WE_HAVE_AN_X_AND_Y_SCALE: u16 = 0x0040;
+const WE_HAVE_A_TWO_BY_TWO: u16 = 0x0080;
+const WE_HAVE_INSTRUCTIONS: u16 = 0x0100;
+
+// 変換された glyf の optionFlags．
+const HAS_OVERLAP_SIMPLE_BITMAP: u16 = 0x0001;
+
+pub fn is_woff2(data: &[u8]) -> bool {
+    data.len() >= 4 && make_tag(&[data[0], data[1], data[2], data[3]]) == WOFF2
+}
+
+// UIntBase128．先頭の 0 と 32 ビットを超える値はエラー．
+pub fn read_base128(reader: &mut Reader) -> Result<u32> {
+    let mut value = 0_u32;
+    for i in 0..5 {
+        let byte = reader.read_u8()?;
+        if i == 0 && byte == 0x80 {
+            return Err(reader.error("UIntBase128 has a leading zero"));
+        }
+        if value & 0xFE000000 != 0 {
+            return Err(reader.error("UIntBase128 overflows"));
+        }
+        value = (value << 7) | (byte & 0x7F) as u32;
+        if byte & 0x80 == 0 {
+            return Ok(value);
+        }
+    }
+    Err(reader.error("UIntBase128 is longer than 5 bytes"))
+}
+
+// 255UInt16．
+pub fn read_255_u16(reader: &mut Reader) -> Result<u16> {
+    match reader.read_u8()? {
+        253 => reader.read_u16(),
+        254 => Ok(reader.read_u8()? as u16 + 253 * 2),
+        255 => Ok(reader.read_u8()? as u16 + 253),
+        code => Ok(code as u16),
+    }
+}
+
+pub(crate) fn decompress_brotli(data: &[u8], expected_length: usize) -> Result<Vec<u8>> {
+    // decompress_zlib と同じく，最初に確保する大きさは圧縮率で抑える．
+    let mut out = Vec::with_capacity(expected_length.min(data.len().saturating_mul(16)));
+    brotli::Decompressor::new(data, 4096)
+        .take(expected_length as u64 + 1)
+        .read_to_end(&mut out)
+        .map_err(|e| Error::InvalidFont(format!("brotli: {}", e)))?;
+    if out.len() != expected_length {
+        return Err(Error::InvalidFont(format!(
+            "decompressed size is {} bytes, expected {}",
+            out.len(),
+            expected_length
+        )));
+    }
+    Ok(out)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Woff2Header {
+    pub flavor: u32,
+    pub length: u32,
+    pub num_tables: u16,
+    pub total_sfnt_size: u32,
+    pub total_compressed_size: u32,
+    pub major_version: u16,
+    pub minor_version: u16,
+    pub meta_offset: u32,
+    pub meta_length: u32,
+    pub meta_orig_length: u32,
+    pub priv_offset: u32,
+    pub priv_length: u32,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Woff2TableEntry {
+    pub tag: u32,
+    pub transform_version: u8,
+    pub orig_length: u32,
+    // 変換されたテーブルの大きさ．変換されていない場合は None．
+    pub transform_length: Option<u32>,
+    // 展開したテーブルデータ内の位置．
+    pub offset: u32,
+}
+
+impl Woff2TableEntry {
+    // glyf と loca はバージョン 0 が変換あり，3 が変換なし．その他はバージョン 0 が変換なし．
+    pub fn is_transformed(&self) -> bool {
+        if self.tag == GLYF || self.tag == LOCA {
+            self.transform_version != 3
+        } else {
+            self.transform_version != 0
+        }
+    }
+
+    // 圧縮されたデータ内での大きさ．
+    pub fn stored_length(&self) -> u32 {
+        self.transform_length.unwrap_or(self.orig_length)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Woff2CollectionFont {
+    pub flavor: u32,
+    // テーブルディレクトリの添字．
+    pub table_indices: Vec<u16>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Woff2Collection {
+    pub version: u32,
+    pub fonts: Vec<Woff2CollectionFont>,
+}
+
+#[derive(Debug, Clone)]
+pub struct Woff2<'a> {
+    data: &'a [u8],
+    pub header: Woff2Header,
+    pub tables: Vec<Woff2TableEntry>,
+    pub collection: Option<Woff2Collection>,
+    // 全てのテーブルを展開したデータ．
+    table_data: Vec<u8>,
+}
+
+impl<'a> Woff2<'a> {
+    pub fn parse(data: &'a [u8]) -> Result<Self> {
+        let mut reader = Reader::new(data);
+        let signature = reader.read_tag()?;
+        if signature != WOFF2 {
+            return Err(Error::UnsupportedFileType(format!(
+                "not a WOFF2 file ('{}')",
+                tag_to_string(signature)
+            )));
+        }
+        let flavor = reader.read_u32()?;
+        let length = reader.read_u32()?;
+        let num_tables = reader.read_u16()?;
+        let reserved = reader.read_u16()?;
+        let header = Woff2Header {
+            flavor,
+            length,
+            num_tables,
+            total_sfnt_size: reader.read_u32()?,
+            total_compressed_size: reader.read_u32()?,
+            major_version: reader.read_u16()?,
+            minor_version: reader.read_u16()?,
+            meta_offset: reader.read_u32()?,
+            meta_length: reader.read_u32()?,
+            meta_orig_length: reader.read_u32()?,
+            priv_offset: reader.read_u32()?,
+            priv_length: reader.read_u32()?,
+        };
+        if reserved != 0 {
+            return Err(reader.error(format!("reserved field is {}", reserved)));
+        }
+        if length as usize != data.len() {
+            return Err(reader.error(format!(
+                "length field is {} but the file is {} bytes",
+                length,
+                data.len()
+            )));
+        }
+
+        let mut tables = Vec::with_capacity(num_tables as usize);
+        let mut offset = 0_u32;
+        for _ in 0..num_tables {
+            let flags = reader.read_u8()?;
+            let tag = match flags & 0x3F {
+                63 => reader.read_tag()?,
+                index => KNOWN_TAGS[index as usize],
+            };
+            let transform_version = flags >> 6;
+            let orig_length = read_base128(&mut reader)?;
+            let mut entry = Woff2TableEntry {
+                tag,
+                transform_version,
+                orig_length,
+                transform_length: None,
+                offset,
+            };
+            if entry.is_transformed() {
+                entry.transform_length = Some(read_base128(&mut reader)?);
+            }
+            if tag == LOCA && entry.transform_length.is_some_and(|length| length != 0) {
+                return Err(reader.error("transformed 'loca' table must be empty"));
+            }
+            offset = offset
+                .checked_add(entry.stored_length())
+                .ok_or_else(|| reader.error("table data is too large"))?;
+            tables.push(entry);
+        }
+
+        let collection = if flavor == TTCF_FLAVOR {
+            let version = reader.read_u32()?;
+            let num_fonts = read_255_u16(&mut reader)?;
+            let mut fonts = Vec::with_capacity(num_fonts as usize);
+            for _ in 0..num_fonts {
+                let num_tables = read_255_u16(&mut reader)?;
+                let flavor = reader.read_u32()?;
+                let table_indices = (0..num_tables)
+                    .map(|_| {
+                        let index = read_255_u16(&mut reader)?;
+                        if index as usize >= tables.len() {
+                            return Err(reader.error(format!(
+                                "table index {} is out of range ({} tables)",
+                                index,
+                                tables.len()
+                            )));
+                        }
+                        Ok(index)
+                    })
+                    .collect::<Result<Vec<_>>>()?;
+                fonts.push(Woff2CollectionFont {
+                    flavor,
+                    table_indices,
+                });
+            }
+            Some(Woff2Collection { version, fonts })
+        } else {
+            None
+        };
+
+        let compressed = reader.read_bytes(header.total_compressed_size as usize)?;
+        let table_data = decompress_brotli(compressed, offset as usize)?;
+
+        let woff2 = Self {
+            data,
+            header,
+            tables,
+            collection,
+            table_data,
+        };
+        woff2.get_metadata_block()?;
+        woff2.get_private_data()?;
+        Ok(woff2)
+    }
+
+    fn get_block(&self, offset: u32, length: u32) -> Result<&'a [u8]> {
+        let start = offset as usize;
+        let end = start + length as usize;
+        self.data.get(start..end).ok_or_else(|| {
+            Error::InvalidFont(format!(
+                "block {}..{} is out of range ({} bytes)",
+                start,
+                end,
+                self.data.len()
+            ))
+        })
+    }
+
+    fn get_metadata_block(&self) -> Result<Option<&'a [u8]>> {
+        if self.header.meta_length == 0 {
+            return Ok(None);
+        }
+        self.get_block(self.header.meta_offset, self.header.meta_length)
+            .map(Some)
+    }
+
+    // 拡張メタデータの XML．
+    pub fn get_metadata(&self) -> Result<Option<String>> {
+        let Some(block) = self.get_metadata_block()? else {
+            return Ok(None);
+        };
+        let xml = decompress_brotli(block, self.header.meta_orig_length as usize)?;
+        String::from_utf8(xml)
+            .map(Some)
+            .map_err(|e| Error::StringConversion(format!("WOFF2 metadata: {}", e)))
+    }
+
+    pub fn get_private_data(&self) -> Result<Option<&'a [u8]>> {
+        if self.header.priv_length == 0 {
+            return Ok(None);
+        }
+        self.get_block(self.header.priv_offset, self.header.priv_length)
+            .map(Some)
+    }
+
+    pub fn is_collection(&self) -> bool {
+        self.collection.is_some()
+    }
+
+    // 変換されたままのテーブルデータ．
+    pub fn get_stored_table(&self, index: usize) -> Option<&[u8]> {
+        let entry = self.tables.get(index)?;
+        let start = entry.offset as usize;
+        self.table_data
+            .get(start..start + entry.stored_length() as usize)
+    }
+
+    // 各フェイスの (flavor, テーブルディレクトリの添字)．
+    fn get_fonts(&self) -> Vec<(u32, Vec<usize>)> {
+        match &self.collection {
+            Some(collection) => collection
+                .fonts
+                .iter()
+                .map(|font| {
+                    let indices = font.table_indices.iter().map(|&i| i as usize).collect();
+                    (font.flavor, indices)
+                })
+                .collect(),
+            None => vec![(self.header.flavor, (0..self.tables.len()).collect())],
+        }
+    }
+
+    fn find_table(&self, indices: &[usize], tag: u32) -> Option<usize> {
+        indices.iter().copied().find(|&i| self.tables[i].tag == tag)
+    }
+
+    // 変換を戻したテーブルを組み立てる．コレクションの場合は TTC になる．
+    pub fn to_sfnt(&self) -> Result<Vec<u8>> {
+        let fonts = self.get_fonts();
+        let mut tables: Vec<Option<Vec<u8>>> = vec![None; self.tables.len()];
+        for (_, indices) in &fonts {
+            let glyf = self.find_table(indices, GLYF);
+            let loca = self.find_table(indices, LOCA);
+            let mut x_mins = None;
+            if let Some(glyf) = glyf.filter(|&i| self.tables[i].is_transformed()) {
+                let loca = loca.ok_or(Error::MissingTable(LOCA))?;
+                if tables[glyf].is_none() {
+                    let data = self.get_stored_table(glyf).unwrap_or_default();
+                    let glyphs = reconstruct_glyf(data)?;
+                    let loca_length = self.tables[loca].orig_length as usize;
+                    if glyphs.loca.len() != loca_length {
+                        return Err(Error::malformed_table(
+                            LOCA,
+                            format!(
+                                "reconstructed size is {} bytes, expected {}",
+                                glyphs.loca.len(),
+                                loca_length
+                            ),
+                        ));
+                    }
+                    tables[glyf] = Some(glyphs.glyf);
+                    tables[loca] = Some(glyphs.loca);
+                    x_mins = Some(glyphs.x_mins);
+                }
+            }
+            if let Some(hmtx) = self
+                .find_table(indices, HMTX)
+                .filter(|&i| self.tables[i].is_transformed() && tables[i].is_none())
+            {
+                let x_mins = x_mins.ok_or_else(|| {
+                    Error::malformed_table(HMTX, "transformed without transformed 'glyf' table")
+                })?;
+                let num_glyphs = self.read_u16(indices, MAXP, 4)?;
+                let num_hmetrics = self.read_u16(indices, HHEA, 34)?;
+                let data = self.get_stored_table(hmtx).unwrap_or_default();
+                tables[hmtx] = Some(reconstruct_hmtx(data, num_glyphs, num_hmetrics, &x_mins)?);
+            }
+        }
+        for (i, entry) in self.tables.iter().enumerate() {
+            if tables[i].is_some() {
+                continue;
+            }
+            if entry.is_transformed() {
+                return Err(Error::malformed_table(
+                    entry.tag,
+                    format!("unknown transform version {}", entry.transform_version),
+                ));
+            }
+            tables[i] = Some(self.get_stored_table(i).unwrap_or_default().to_vec());
+        }
+
+        let tables: Vec<_> = self
+            .tables
+            .iter()
+            .zip(&tables)
+            .map(|(entry, data)| (entry.tag, None, data.as_deref().unwrap_or_default()))
+            .collect();
+        match &self.collection {
+            Some(collection) => {
+                let major_version = (collection.version >> 16) as u16;
+                Ok(build_collection(major_version, &fonts, &tables))
+            }
+            None => {
+                let mut font = build_sfnt(self.header.flavor, &tables);
+                update_checksum_adjustment(&mut font)?;
+                Ok(font)
+            }
+        }
+    }
+
+    // 変換されていないテーブルから数値を読む．
+    fn read_u16(&self, indices: &[usize], tag: u32, offset: usize) -> Result<u16> {
+        let index = self
+            .find_table(indices, tag)
+            .ok_or(Error::MissingTable(tag))?;
+        let data = self.get_stored_table(index).unwrap_or_default();
+        Reader::table(tag, data).jump(offset)?.read_u16()
+    }
+}
+
+pub struct ReconstructedGlyf {
+    pub glyf: Vec<u8>,
+    pub loca: Vec<u8>,
+    // hmtx の変換を戻すときに使う各グリフの xMin．
+    pub x_mins: Vec<i16>,
+}
+
+fn is_bit_set(bitmap: &[u8], index: usize) -> bool {
+    bitmap[index >> 3] & (0x80 >> (index & 7)) != 0
+}
+
+fn with_sign(flag: u8, value: i32) -> i32 {
+    if flag & 1 != 0 {
+        value
+    } else {
+        -value
+    }
+}
+
+// flags の下位 7 ビットに応じて glyph ストリームから (dx, dy) を読む．
+fn read_triplet(flag: u8, reader: &mut Reader) -> Result<(i32, i32)> {
+    let flag = flag & 0x7F;
+    Ok(if flag < 10 {
+        let b0 = reader.read_u8()? as i32;
+        (0, with_sign(flag, (((flag & 14) as i32) << 7) + b0))
+    } else if flag < 20 {
+        let b0 = reader.read_u8()? as i32;
+        (with_sign(flag, ((((flag - 10) & 14) as i32) << 7) + b0), 0)
+    } else if flag < 84 {
+        let b0 = reader.read_u8()? as i32;
+        let f = (flag - 20) as i32;
+        (
+            with_sign(flag, 1 + (f & 0x30) + (b0 >> 4)),
+            with_sign(flag >> 1, 1 + ((f & 0x0C) << 2) + (b0 & 0x0F)),
+        )
+    } else if flag < 120 {
+        let b0 = reader.read_u8()? as i32;
+        let b1 = reader.read_u8()? as i32;
+        let f = (flag - 84) as i32;
+        (
+            with_sign(flag, 1 + ((f / 12) << 8) + b0),
+            with_sign(flag >> 1, 1 + (((f % 12) >> 2) << 8) + b1),
+        )
+    } else if flag < 124 {
+        let b0 = reader.read_u8()? as i32;
+        let b1 = reader.read_u8()? as i32;
+        let b2 = reader.read_u8()? as i32;
+        (
+            with_sign(flag, (b0 << 4) + (b1 >> 4)),
+            with_sign(flag >> 1, ((b1 & 0x0F) << 8) + b2),
+        )
+    } else {
+        let dx = reader.read_u16()? as i32;
+        let dy = reader.read_u16()? as i32;
+        (with_sign(flag, dx), with_sign(flag >> 1, dy))
+    })
+}
+
+// 点を繰り返しと短いベクトルを使って flags, x, y の配列に書く．
+fn write_points(out: &mut Vec<u8>, points: &[Point], overlap: bool) {
+    let mut runs: Vec<(u8, u8)> = vec![];
+    let mut xs = vec![];
+    let mut ys = vec![];
+    let (mut last_x, mut last_y) = (0, 0);
+    for (i, &(x, y, on_curve)) in points.iter().enumerate() {
+        let mut flag = if on_curve { ON_CURVE_POINT } else { 0 };
+        if i == 0 && overlap {
+            flag |= OVERLAP_SIMPLE;
+        }
+        let (dx, dy) = (x - last_x, y - last_y);
+        (last_x, last_y) = (x, y);
+        if dx == 0 {
+            flag |= X_IS_SAME_OR_POSITIVE_X_SHORT_VECTOR;
+        } else if (-255..=255).contains(&dx) {
+            flag |= X_SHORT_VECTOR;
+            if dx > 0 {
+                flag |= X_IS_SAME_OR_POSITIVE_X_SHORT_VECTOR;
+            }
+            xs.push(dx.unsigned_abs() as u8);
+        } else {
+            xs.extend_from_slice(&(dx as i16).to_be_bytes());
+        }
+        if dy == 0 {
+            flag |= Y_IS_SAME_OR_POSITIVE_Y_SHORT_VECTOR;
+        } else if (-255..=255).contains(&dy) {
+            flag |= Y_SHORT_VECTOR;
+            if dy > 0 {
+                flag |= Y_IS_SAME_OR_POSITIVE_Y_SHORT_VECTOR;
+            }
+            ys.push(dy.unsigned_abs() as u8);
+        } else {
+            ys.extend_from_slice(&(dy as i16).to_be_bytes());
+        }
+        match runs.last_mut() {
+            Some((last_flag, repeat)) if *last_flag == flag && *repeat < 255 => *repeat += 1,
+            _ => runs.push((flag, 0)),
+        }
+    }
+    for (flag, repeat) in runs {
+        match repeat {
+            0 => out.push(flag),
+            1 => out.extend_from_slice(&[flag, flag]),
+            _ => out.extend_from_slice(&[flag | REPEAT_FLAG, repeat]),
+        }
+    }
+    out.extend(xs);
+    out.extend(ys);
+}
+
+// 変換された glyf から glyf と loca を作る．
+pub fn reconstruct_glyf(data: &[u8]) -> Result<ReconstructedGlyf> {
+    let mut reader = Reader::table(GLYF, data);
+    // reserved
+    reader.skip(2)?;
+    let option_flags = reader.read_u16()?;
+    let num_glyphs = reader.read_u16()? as usize;
+    let index_format = reader.read_u16()?;
+    let mut stream_sizes = [0_usize; 7];
+    for size in &mut stream_sizes {
+        *size = reader.read_u32()? as usize;
+    }
+    let mut streams = stream_sizes
+        .iter()
+        .map(|&size| Ok(Reader::table(GLYF, reader.read_bytes(size)?)))
+        .collect::<Result<Vec<_>>>()?;
+    let [n_contours, n_points, flags, glyphs, composites, bboxes, instructions] = &mut streams[..]
+    else {
+        unreachable!();
+    };
+    let bbox_bitmap = bboxes.read_bytes(num_glyphs.div_ceil(32) * 4)?;
+    let overlap_bitmap = if option_flags & HAS_OVERLAP_SIMPLE_BITMAP != 0 {
+        Some(reader.read_bytes(num_glyphs.div_ceil(8))?)
+    } else {
+        None
+    };
+
+    let mut glyf = Vec::new();
+    let mut offsets = Vec::with_capacity(num_glyphs + 1);
+    let mut x_mins = Vec::with_capacity(num_glyphs);
+    for index in 0..num_glyphs {
+        offsets.push(glyf.len());
+        let number_of_contours = n_contours.read_i16()?;
+        let has_bbox = is_bit_set(bbox_bitmap, index);
+        let mut read_bbox = || -> Result<[i16; 4]> {
+            Ok([
+                bboxes.read_i16()?,
+                bboxes.read_i16()?,
+                bboxes.read_i16()?,
+                bboxes.read_i16()?,
+            ])
+        };
+        if number_of_contours == 0 {
+            if has_bbox {
+                return Err(reader.error(format!("empty glyph {} has a bounding box", index)));
+            }
+            x_mins.push(0);
+            continue;
+        }
+
+        if number_of_contours > 0 {
+            let mut end_points = Vec::with_capacity(number_of_contours as usize);
+            let mut total = 0_u32;
+            for _ in 0..number_of_contours {
+                total += read_255_u16(n_points)? as u32;
+                let end_point = total
+                    .checked_sub(1)
+                    .filter(|&end_point| end_point <= u16::MAX as u32)
+                    .ok_or_else(|| reader.error(format!("glyph {} has invalid points", index)))?;
+                end_points.push(end_point as u16);
+            }
+            let mut points = Vec::with_capacity(total as usize);
+            // 座標は glyf に i16 で書くので，その範囲を超える点は壊れたデータとして扱う．
+            let add = |value: i32, delta: i32| {
+                value
+                    .checked_add(delta)
+                    .filter(|value| i16::try_from(*value).is_ok())
+                    .ok_or_else(|| {
+                        reader.error(format!("glyph {} has a coordinate out of range", index))
+                    })
+            };
+            let (mut x, mut y) = (0, 0);
+            for _ in 0..total {
+                let flag = flags.read_u8()?;
+                let (dx, dy) = read_triplet(flag, glyphs)?;
+                x = add(x, dx)?;
+                y = add(y, dy)?;
+                points.push((x, y, flag & 0x80 == 0));
+            }
+            let instruction_length = read_255_u16(glyphs)?;
+            let instruction_data = instructions.read_bytes(instruction_length as usize)?;
+            let bbox = if has_bbox {
+                read_bbox()?
+            } else {
+                let x_min = points.iter().map(|p| p.0).min().unwrap_or(0);
+                let y_min = points.iter().map(|p| p.1).min().unwrap_or(0);
+                let x_max = points.iter().map(|p| p.0).max().unwrap_or(0);
+                let y_max = points.iter().map(|p| p.1).max().unwrap_or(0);
+                // 全ての点が i16 の範囲にあるので，切り詰められることはない．
+                [x_min as i16, y_min as i16, x_max as i16, y_max as i16]
+            };
+            let overlap = overlap_bitmap.is_some_and(|bitmap| is_bit_set(bitmap, index));
+
+            glyf.extend_from_slice(&number_of_contours.to_be_bytes());
+            for value in bbox {
+                glyf.extend_from_slice(&value.to_be_bytes());
+            }
+            for end_point in end_points {
+                glyf.extend_from_slice(&end_point.to_be_bytes());
+            }
+            glyf.extend_from_slice(&instruction_length.to_be_bytes());
+            glyf.extend_from_slice(instruction_data);
+            write_points(&mut glyf, &points, overlap);
+            x_mins.push(bbox[0]);
+        } else {
+            // 複合グリフは bbox が必須．
+            if !has_bbox {
+                return Err(reader.error(format!("composite glyph {} has no bounding box", index)));
+            }
+            let bbox = read_bbox()?;
+            let start = composites.offset();
+            let mut have_instructions = false;
+            loop {
+                let flags = composites.read_u16()?;
+                let argument_size = if flags & ARG_1_AND_2_ARE_WORDS != 0 {
+                    4
+                } else {
+                    2
+                };
+                let scale_size = if flags & WE_HAVE_A_SCALE != 0 {
+                    2
+                } else if flags & WE_HAVE_AN_X_AND_Y_SCALE != 0 {
+                    4
+                } else if flags & WE_HAVE_A_TWO_BY_TWO != 0 {
+                    8
+                } else {
+                    0
+                };
+                // glyphIndex, arguments, scale
+                composites.skip(2 + argument_size + scale_size)?;
+                have_instructions |= flags & WE_HAVE_INSTRUCTIONS != 0;
+                if flags & MORE_COMPONENTS == 0 {
+                    break;
+                }
+            }
+            let components = &composites.data()[start..composites.offset()];
+
+            glyf.extend_from_slice(&number_of_contours.to_be_bytes());
+            for value in bbox {
+                glyf.extend_from_slice(&value.to_be_bytes());
+            }
+            glyf.extend_from_slice(components);
+            if have_instructions {
+                let instruction_length = read_255_u16(glyphs)?;
+                glyf.extend_from_slice(&instruction_length.to_be_bytes());
+                glyf.extend_from_slice(instructions.read_bytes(instruction_length as usize)?);
+            }
+            x_mins.push(bbox[0]);
+        }
+        glyf.resize(glyf.len().next_multiple_of(4), 0);
+    }
+    offsets.push(glyf.len());
+
+    let mut loca = Vec::with_capacity(offsets.len() * 4);
+    for offset in offsets {
+        if index_format == 0 {
+            let offset = u16::try_from(offset / 2)
+                .map_err(|_| Error::malformed_table(LOCA, "glyf is too large for short offsets"))?;
+            loca.extend_from_slice(&offset.to_be_bytes());
+        } else {
+            loca.extend_from_slice(&(offset as u32).to_be_bytes());
+        }
+    }
+    Ok(ReconstructedGlyf { glyf, loca, x_mins })
+}
+
+// 変換された hmtx から hmtx を作る．省略された lsb はグリフの xMin になる．
+pub fn reconstruct_hmtx(
+    data: &[u8],
+    num_glyphs: u16,
+    num_hmetrics: u16,
+    x_mins: &[i16],
+) -> Result<Vec<u8>> {
+    let mut reader = Reader::table(HMTX, data);
+    let flags = reader.read_u8()?;
+    if flags & 0xFC != 0 {
+        return Err(reader.error(format!("reserved flags are set ({:#04x})", flags)));
+    }
+    if num_hmetrics > num_glyphs || x_mins.len() < num_glyphs as usize {
+        return Err(reader.error(format!(
+            "inconsistent glyph count (numberOfHMetrics {}, numGlyphs {}, glyf {})",
+            num_hmetrics,
+            num_glyphs,
+            x_mins.len()
+        )));
+    }
+    let advances = (0..num_hmetrics)
+        .map(|_| reader.read_u16())
+        .collect::<Result<Vec<_>>>()?;
+    let mut read_lsb = |index: usize, present: bool| {
+        if present {
+            reader.read_i16()
+        } else {
+            Ok(x_mins[index])
+        }
+    };
+    let mut hmtx = Vec::with_capacity(num_hmetrics as usize * 2 + num_glyphs as usize * 2);
+    for (index, advance) in advances.into_iter().enumerate() {
+        let lsb = read_lsb(index, flags & 0x01 == 0)?;
+        hmtx.extend_from_slice(&advance.to_be_bytes());
+        hmtx.extend_from_slice(&lsb.to_be_bytes());
+    }
+    for index in num_hmetrics as usize..num_glyphs as usize {
+        let lsb = read_lsb(index, flags & 0x02 == 0)?;
+        hmtx.extend_from_slice(&lsb.to_be_bytes());
+    }
+    Ok(hmtx)
+}
+
+pub fn decode(data: &[u8]) -> Result<Vec<u8>> {
+    Woff2::parse(data)?.to_sfnt()
+}
+
+pub fn write_base128(out: &mut Vec<u8>, value: u32) {
+    let length = (1..5).find(|&i| value >> (7 * i) == 0).unwrap_or(5);
+    for i in (0..length).rev() {
+        let byte = ((value >> (7 * i)) & 0x7F) as u8;
+        out.push(if i == 0 { byte } else { byte | 0x80 });
+    }
+}
+
+pub fn write_255_u16(out: &mut Vec<u8>, value: u16) {
+    match value {
+        0..=252 => out.push(value as u8),
+        253..=505 => out.extend_from_slice(&[255, (value - 253) as u8]),
+        506..=761 => out.extend_from_slice(&[254, (value - 506) as u8]),
+        _ => {
+            out.push(253);
+            out.extend_from_slice(&value.to_be_bytes());
+        }
+    }
+}
+
+pub(crate) fn compress_brotli(data: &[u8], is_font_data: bool) -> Vec<u8> {
+    let mut params = BrotliEncoderParams {
+        quality: 11,
+        lgwin: 22,
+        ..Default::default()
+    };
+    if is_font_data {
+        params.mode = BrotliEncoderMode::BROTLI_MODE_FONT;
+    }
+    let mut out = Vec::new();
+    // Vec への書き込みは失敗しない．
+    BrotliCompress(&mut &data[..], &mut out, &params).unwrap();
+    out
+}
+
+// read_triplet の逆．
+fn write_triplet(flags: &mut Vec<u8>, glyphs: &mut Vec<u8>, on_curve: bool, dx: i32, dy: i32) {
+    let on_curve_bit = if on_curve { 0 } else { 0x80 };
+    let (abs_x, abs_y) = (dx.unsigned_abs(), dy.unsigned_abs());
+    let x_sign = (dx >= 0) as u32;
+    let y_sign = (dy >= 0) as u32;
+    let xy_signs = x_sign + 2 * y_sign;
+    let flag = if dx == 0 && abs_y < 1280 {
+        glyphs.push((abs_y & 0xFF) as u8);
+        ((abs_y & 0xF00) >> 7) + y_sign
+    } else if dy == 0 && abs_x < 1280 {
+        glyphs.push((abs_x & 0xFF) as u8);
+        10 + ((abs_x & 0xF00) >> 7) + x_sign
+    } else if abs_x < 65 && abs_y < 65 {
+        glyphs.push((((abs_x - 1) & 0x0F) << 4 | ((abs_y - 1) & 0x0F)) as u8);
+        20 + ((abs_x - 1) & 0x30) + (((abs_y - 1) & 0x30) >> 2) + xy_signs
+    } else if abs_x < 769 && abs_y < 769 {
+        glyphs.push(((abs_x - 1) & 0xFF) as u8);
+        glyphs.push(((abs_y - 1) & 0xFF) as u8);
+        84 + 12 * (((abs_x - 1) & 0x300) >> 8) + (((abs_y - 1) & 0x300) >> 6) + xy_signs
+    } else if abs_x < 4096 && abs_y < 4096 {
+        glyphs.push((abs_x >> 4) as u8);
+        glyphs.push((((abs_x & 0x0F) << 4) | (abs_y >> 8)) as u8);
+        glyphs.push((abs_y & 0xFF) as u8);
+        120 + xy_signs
+    } else {
+        glyphs.extend_from_slice(&(abs_x as u16).to_be_bytes());
+        glyphs.extend_from_slice(&(abs_y as u16).to_be_bytes());
+        124 + xy_signs
+    };
+    flags.push(on_curve_bit | flag as u8);
+}
+
+fn set_bit(bitmap: &mut [u8], index: usize) {
+    bitmap[index >> 3] |= 0x80 >> (index & 7);
+}
+
+// 単純グリフの flags と座標を絶対座標の点にする．
+fn read_points(reader: &mut Reader, num_points: usize) -> Result<(Vec<Point>, bool)> {
+    let mut flags = Vec::with_capacity(num_points);
+    while flags.len() < num_points {
+        let flag = reader.read_u8()?;
+        flags.push(flag);
+        if flag & REPEAT_FLAG != 0 {
+            for _ in 0..reader.read_u8()? {
+                flags.push(flag);
+            }
+        }
+    }
+    if flags.len() > num_points {
+        return Err(reader.error("flags are repeated past the last point"));
+    }
+    let mut read_coordinates = |short: u8, same_or_positive: u8| {
+        let mut value = 0;
+        flags
+            .iter()
+            .map(|&flag| {
+                if flag & short != 0 {
+                    let delta = reader.read_u8()? as i32;
+                    value += if flag & same_or_positive != 0 {
+                        delta
+                    } else {
+                        -delta
+                    };
+                } else if flag & same_or_positive == 0 {
+                    value += reader.read_i16()? as i32;
+                }
+                Ok(value)
+            })
+            .collect::<Result<Vec<_>>>()
+    };
+    let xs = read_coordinates(X_SHORT_VECTOR, X_IS_SAME_OR_POSITIVE_X_SHORT_VECTOR)?;
+    let ys = read_coordinates(Y_SHORT_VECTOR, Y_IS_SAME_OR_POSITIVE_Y_SHORT_VECTOR)?;
+    let overlap = flags.first().is_some_and(|flag| flag & OVERLAP_SIMPLE != 0);
+    let points = flags
+        .iter()
+        .zip(xs.into_iter().zip(ys))
+        .map(|(flag, (x, y))| (x, y, flag & ON_CURVE_POINT != 0))
+        .collect();
+    Ok((points, overlap))
+}
+
+// glyf と loca を WOFF2 の変換された glyf にする．index_format は head の indexToLocFormat．
+pub fn transform_glyf(
+    glyf: &[u8],
+    loca: &[u8],
+    index_format: u16,
+    num_glyphs: u16,
+) -> Result<Vec<u8>> {
+    let num_glyphs = num_glyphs as usize;
+    let mut loca_reader = Reader::table(LOCA, loca);
+    let offsets = (0..=num_glyphs)
+        .map(|_| {
+            Ok(if index_format == 0 {
+                loca_reader.read_u16()? as usize * 2
+            } else {
+                loca_reader.read_u32()? as usize
+            })
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    let mut n_contours = Vec::new();
+    let mut n_points = Vec::new();
+    let mut flags = Vec::new();
+    let mut glyphs = Vec::new();
+    let mut composites = Vec::new();
+    let mut bbox_bitmap = vec![0; num_glyphs.div_ceil(32) * 4];
+    let mut bboxes = Vec::new();
+    let mut instructions = Vec::new();
+    let mut overlap_bitmap = vec![0; num_glyphs.div_ceil(8)];
+    let mut has_overlap = false;
+
+    for index in 0..num_glyphs {
+        let (start, end) = (offsets[index], offsets[index + 1]);
+        let data = match glyf.get(start..end) {
+            Some(data) => data,
+            None => {
+                return Err(Error::malformed_table(
+                    GLYF,
+                    format!("glyph {} is out of range ({}..{})", index, start, end),
+                ))
+            }
+        };
+        if data.is_empty() {
+            n_contours.extend_from_slice(&0_i16.to_be_bytes());
+            continue;
+        }
+        let mut reader = Reader::table(GLYF, data);
+        let number_of_contours = reader.read_i16()?;
+        let bbox = [
+            reader.read_i16()?,
+            reader.read_i16()?,
+            reader.read_i16()?,
+            reader.read_i16()?,
+        ];
+        n_contours.extend_from_slice(&number_of_contours.to_be_bytes());
+
+        if number_of_contours >= 0 {
+            let mut last = -1;
+            for _ in 0..number_of_contours {
+                let end_point = reader.read_u16()? as i32;
+                if end_point <= last {
+                    return Err(reader.error(format!("glyph {} has invalid contours", index)));
+                }
+                write_255_u16(&mut n_points, (end_point - last) as u16);
+                last = end_point;
+            }
+            let instruction_length = reader.read_u16()?;
+            let instruction_data = reader.read_bytes(instruction_length as usize)?;
+            let (points, overlap) = read_points(&mut reader, (last + 1) as usize)?;
+
+            let (mut x, mut y) = (0, 0);
+            for &(px, py, on_curve) in &points {
+                write_triplet(&mut flags, &mut glyphs, on_curve, px - x, py - y);
+                (x, y) = (px, py);
+            }
+            write_255_u16(&mut glyphs, instruction_length);
+            instructions.extend_from_slice(instruction_data);
+
+            // 点から計算した bbox と異なる場合だけ格納する．
+            let computed = [
+                points.iter().map(|p| p.0).min().unwrap_or(0) as i16,
+                points.iter().map(|p| p.1).min().unwrap_or(0) as i16,
+                points.iter().map(|p| p.0).max().unwrap_or(0) as i16,
+                points.iter().map(|p| p.1).max().unwrap_or(0) as i16,
+            ];
+            if bbox != computed || points.is_empty() {
+                set_bit(&mut bbox_bitmap, index);
+                for value in bbox {
+                    bboxes.extend_from_slice(&value.to_be_bytes());
+                }
+            }
+            if overlap {
+                set_bit(&mut overlap_bitmap, index);
+                has_overlap = true;
+            }
+        } else {
+            let start = reader.offset();
+            let mut have_instructions = false;
+            loop {
+                let flags = reader.read_u16()?;
+                let argument_size = if flags & ARG_1_AND_2_ARE_WORDS != 0 {
+                    4
+                } else {
+                    2
+                };
+                let scale_size = if flags & WE_HAVE_A_SCALE != 0 {
+                    2
+                } else if flags & WE_HAVE_AN_X_AND_Y_SCALE != 0 {
+                    4
+                } else if flags & WE_HAVE_A_TWO_BY_TWO != 0 {
+                    8
+                } else {
+                    0
+                };
+                reader.skip(2 + argument_size + scale_size)?;
+                have_instructions |= flags & WE_HAVE_INSTRUCTIONS != 0;
+                if flags & MORE_COMPONENTS == 0 {
+                    break;
+                }
+            }
+            composites.extend_from_slice(&data[start..reader.offset()]);
+            if have_instructions {
+                let instruction_length = reader.read_u16()?;
+                write_255_u16(&mut glyphs, instruction_length);
+                instructions.extend_from_slice(reader.read_bytes(instruction_length as usize)?);
+            }
+            set_bit(&mut bbox_bitmap, index);
+            for value in bbox {
+                bboxes.extend_from_slice(&value.to_be_bytes());
+            }
+        }
+    }
+
+    let mut bbox_stream = bbox_bitmap;
+    bbox_stream.extend(bboxes);
+    let streams = [
+        n_contours,
+        n_points,
+        flags,
+        glyphs,
+        composites,
+        bbox_stream,
+        instructions,
+    ];
+    let option_flags = if has_overlap {
+        HAS_OVERLAP_SIMPLE_BITMAP
+    } else {
+        0
+    };
+    let mut out = Vec::new();
+    out.extend_from_slice(&0_u16.to_be_bytes());
+    out.extend_from_slice(&option_flags.to_be_bytes());
+    out.extend_from_slice(&(num_glyphs as u16).to_be_bytes());
+    out.extend_from_slice(&index_format.to_be_bytes());
+    for stream in &streams {
+        out.extend_from_slice(&(stream.len() as u32).to_be_bytes());
+    }
+    for stream in streams {
+        out.extend(stream);
+    }
+    if has_overlap {
+        out.extend(overlap_bitmap);
+    }
+    Ok(out)
+}
+
+// フェイスの glyf と loca を変換する．どちらかが無い場合は None．
+fn transform_face_glyf(sfnt: &Sfnt) -> Result<Option<Vec<u8>>> {
+    let (Some(glyf), Some(loca)) = (sfnt.get_font_table(GLYF)?, sfnt.get_font_table(LOCA)?) else {
+        return Ok(None);
+    };
+    let head = sfnt
+        .get_font_table(HEAD)?
+        .ok_or(Error::MissingTable(HEAD))?;
+    let maxp = sfnt
+        .get_font_table(MAXP)?
+        .ok_or(Error::MissingTable(MAXP))?;
+    let index_format = Reader::table(HEAD, head).jump(50)?.read_u16()?;
+    let num_glyphs = Reader::table(MAXP, maxp).jump(4)?.read_u16()?;
+    transform_glyf(glyf, loca, index_format, num_glyphs).map(Some)
+}
+
+// 単体の sfnt でもコレクションでも，全てのフェイスを読む．
+fn parse_faces(data: &[u8]) -> Result<Vec<Sfnt<'_>>> {
+    if is_collection(data) {
+        TtcHeader::parse(data)?
+            .table_directory_offsets
+            .iter()
+            .map(|&offset| Sfnt::parse_at(data, offset as usize))
+            .collect()
+    } else {
+        Ok(vec![Sfnt::parse(data)?])
+    }
+}
+
+// head の flags の bit 11 は，変換によってフォントが変わったことを表す．
+const HEAD_FLAG_LOSSLESS_TRANSFORMED: u16 = 1 << 11;
+
+pub fn encode(data: &[u8]) -> Result<Vec<u8>> {
+    encode_with(data, None, None)
+}
+
+// sfnt またはコレクションから WOFF2 を作る．glyf と loca は変換し，その他のテーブルは変換しない．
+// コレクションで複数のフェイスが共有しているテーブルは一度だけ格納する．
+pub fn encode_with(
+    data: &[u8],
+    metadata: Option<&str>,
+    private_data: Option<&[u8]>,
+) -> Result<Vec<u8>> {
+    let faces = parse_faces(data)?;
+
+    // 全てのフェイスのテーブルを，同じ位置のものはまとめて並べる．glyf の直後に loca を置く．
+    let mut records: Vec<TableRecord> = vec![];
+    let mut face_indices = vec![];
+    let mut glyf_faces = vec![];
+    for (face_index, face) in faces.iter().enumerate() {
+        let mut face_records = face.tables.clone();
+        face_records.sort_by_key(|record| match record.tag {
+            LOCA => (GLYF.swap_bytes(), 1),
+            tag => (tag.swap_bytes(), 0),
+        });
+        let mut indices = Vec::with_capacity(face_records.len());
+        for record in face_records {
+            let index = match records
+                .iter()
+                .position(|r| r.tag == record.tag && r.offset == record.offset)
+            {
+                Some(index) => index,
+                None => {
+                    records.push(record);
+                    if record.tag == GLYF {
+                        glyf_faces.push((records.len() - 1, face_index));
+                    }
+                    records.len() - 1
+                }
+            };
+            indices.push(index);
+        }
+        face_indices.push(indices);
+    }
+
+    // 格納するデータと，変換した場合はその大きさ．
+    let mut tables: Vec<(Vec<u8>, Option<u32>)> = records
+        .iter()
+        .map(|record| {
+            let start = record.offset as usize;
+            let end = start + record.length as usize;
+            data.get(start..end)
+                .map(|table| (table.to_vec(), None))
+                .ok_or_else(|| {
+                    Error::InvalidFont(format!(
+                        "'{}' table is out of range",
+                        tag_to_string(record.tag)
+                    ))
+                })
+        })
+        .collect::<Result<_>>()?;
+    let mut orig_lengths: Vec<u32> = records.iter().map(|record| record.length).collect();
+    let mut is_transformed = false;
+    for (glyf_index, face_index) in glyf_faces {
+        let face = &faces[face_index];
+        let Some(transformed) = transform_face_glyf(face)? else {
+            continue;
+        };
+        let loca_index = face_indices[face_index]
+            .iter()
+            .copied()
+            .find(|&i| records[i].tag == LOCA)
+            .ok_or(Error::MissingTable(LOCA))?;
+        // 復元した loca はグリフ数 + 1 個のオフセットになる．
+        let head = face.get_font_table(HEAD)?.unwrap_or_default();
+        let maxp = face.get_font_table(MAXP)?.unwrap_or_default();
+        let index_format = Reader::table(HEAD, head).jump(50)?.read_u16()?;
+        let num_glyphs = Reader::table(MAXP, maxp).jump(4)?.read_u16()? as u32;
+        orig_lengths[loca_index] = (num_glyphs + 1) * if index_format == 0 { 2 } else { 4 };
+        tables[glyf_index].1 = Some(transformed.len() as u32);
+        tables[glyf_index].0 = transformed;
+        tables[loca_index] = (vec![], Some(0));
+        is_transformed = true;
+    }
+    if is_transformed {
+        for (record, (table, _)) in records.iter().zip(&mut tables) {
+            if record.tag == HEAD && table.len() >= 18 {
+                let flags = u16::from_be_bytes([table[16], table[17]]);
+                let flags = flags | HEAD_FLAG_LOSSLESS_TRANSFORMED;
+                table[16..18].copy_from_slice(&flags.to_be_bytes());
+            }
+        }
+    }
+
+    let mut directory = Vec::new();
+    for ((record, (_, transform_length)), &orig_length) in
+        records.iter().zip(&tables).zip(&orig_lengths)
+    {
+        let known_index = KNOWN_TAGS.iter().position(|&tag| tag == record.tag);
+        // glyf と loca はバージョン 3 が変換なし．
+        let transform_version = match (record.tag, transform_length) {
+            (GLYF | LOCA, None) => 3,
+            _ => 0,
+        };
+        directory.push((transform_version << 6) | known_index.unwrap_or(63) as u8);
+        if known_index.is_none() {
+            directory.extend_from_slice(&record.tag.to_le_bytes());
+        }
+        write_base128(&mut directory, orig_length);
+        if let Some(transform_length) = transform_length {
+            write_base128(&mut directory, *transform_length);
+        }
+    }
+
+    let flavor = if is_collection(data) {
+        let header = TtcHeader::parse(data)?;
+        let version = ((header.major_version as u32) << 16) | header.minor_version as u32;
+        directory.extend_from_slice(&version.to_be_bytes());
+        write_255_u16(&mut directory, faces.len() as u16);
+        for (face, indices) in faces.iter().zip(&face_indices) {
+            write_255_u16(&mut directory, indices.len() as u16);
+            directory.extend_from_slice(&face.sfnt_version.to_be_bytes());
+            for &index in indices {
+                write_255_u16(&mut directory, index as u16);
+            }
+        }
+        TTCF_FLAVOR
+    } else {
+        faces[0].sfnt_version
+    };
+
+    // 展開後の sfnt の大きさ．
+    let mut total_sfnt_size: usize = orig_lengths
+        .iter()
+        .map(|&length| (length as usize).next_multiple_of(4))
+        .sum();
+    total_sfnt_size += face_indices
+        .iter()
+        .map(|indices| 12 + 16 * indices.len())
+        .sum::<usize>();
+    if flavor == TTCF_FLAVOR {
+        total_sfnt_size += 12 + 4 * faces.len() + 12;
+    }
+
+    let table_data: Vec<u8> = tables.into_iter().flat_map(|(table, _)| table).collect();
+    let mut body = compress_brotli(&table_data, true);
+    let total_compressed_size = body.len();
+
+    let (major_version, minor_version) = match faces[0].get_font_table(HEAD)? {
+        Some(head) => {
+            let mut reader = Reader::table(HEAD, head).jump(4)?;
+            (reader.read_u16()?, reader.read_u16()?)
+        }
+        None => (0, 0),
+    };
+
+    let body_offset = 48 + directory.len();
+    let (meta_offset, meta_length, meta_orig_length) = match metadata {
+        Some(metadata) => {
+            body.resize(body.len().next_multiple_of(4), 0);
+            let compressed = compress_brotli(metadata.as_bytes(), false);
+            let offset = body_offset + body.len();
+            body.extend_from_slice(&compressed);
+            (offset, compressed.len(), metadata.len())
+        }
+        None => (0, 0, 0),
+    };
+    let (priv_offset, priv_length) = match private_data {
+        Some(private_data) => {
+            body.resize(body.len().next_multiple_of(4), 0);
+            let offset = body_offset + body.len();
+            body.extend_from_slice(private_data);
+            (offset, private_data.len())
+        }
+        None => (0, 0),
+    };
+
+    let length = body_offset + body.len();
+    let mut out = Vec::with_capacity(length);
+    out.extend_from_slice(&WOFF2.to_le_bytes());
+    out.extend_from_slice(&flavor.to_be_bytes());
+    out.extend_from_slice(&(length as u32).to_be_bytes());
+    out.extend_from_slice(&(records.len() as u16).to_be_bytes());
+    out.extend_from_slice(&0_u16.to_be_bytes());
+    out.extend_from_slice(&(total_sfnt_size as u32).to_be_bytes());
+    out.extend_from_slice(&(total_compressed_size as u32).to_be_bytes());
+    out.extend_from_slice(&major_version.to_be_bytes());
+    out.extend_from_slice(&minor_version.to_be_bytes());
+    for value in [
+        meta_offset,
+        meta_length,
+        meta_orig_length,
+        priv_offset,
+        priv_length,
+    ] {
+        out.extend_from_slice(&(value as u32).to_be_bytes());
+    }
+    out.extend(directory);
+    out.extend(body);
+    Ok(out)
+}
+
+// Font::get_filepath のファイルから，そのフェイスの WOFF2 を作る．
+pub fn encode_font(font: &Font) -> Result<Vec<u8>> {
+    encode(&font.read_face_data()?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        name::NAME,
+        os2::OS2,
+        sfnt::calc_table_checksum,
+        test_fonts::{build_font, build_ttc, FS_SELECTION_ITALIC},
+    };
+
+    // checksumAdjustment と flags の bit 11 は比較しない．
+    fn normalize_head(head: &[u8]) -> Vec<u8> {
+        let mut head = head.to_vec();
+        head[8..12].fill(0);
+        let flags = u16::from_be_bytes([head[16], head[17]]) & !HEAD_FLAG_LOSSLESS_TRANSFORMED;
+        head[16..18].copy_from_slice(&flags.to_be_bytes());
+        head
+    }
+
+    // woff2 を展開した各フェイスのテーブルが sfnt のテーブルと同じバイト列かどうか調べる．
+    fn check_round_trip(sfnt: &[u8], woff2: &[u8]) {
+        let decoded = decode(woff2).unwrap();
+        let expected_faces = parse_faces(sfnt).unwrap();
+        let actual_faces = parse_faces(&decoded).unwrap();
+        assert_eq!(expected_faces.len(), actual_faces.len());
+        for (expected, actual) in expected_faces.iter().zip(&actual_faces) {
+            assert_eq!(expected.tables.len(), actual.tables.len());
+            for record in &expected.tables {
+                let expected_table = expected.get_font_table(record.tag).unwrap().unwrap();
+                let actual_table = actual.get_font_table(record.tag).unwrap().unwrap();
+                if record.tag == HEAD {
+                    assert_eq!(normalize_head(expected_table), normalize_head(actual_table));
+                } else {
+                    assert_eq!(
+                        expected_table,
+                        actual_table,
+                        "{}",
+                        tag_to_string(record.tag)
+                    );
+                }
+            }
+        }
+        if !is_collection(&decoded) {
+            assert_eq!(calc_table_checksum(0, &decoded), 0xB1B0AFBA);
+        }
+    }
+
+    #[test]
+    fn decompressed_size_mismatch() {
+        let compressed = compress_brotli(b"abcd", false);
+        assert_eq!(decompress_brotli(&compressed, 4).unwrap(), b"abcd");
+        assert!(decompress_brotli(&compressed, 3).is_err());
+        assert!(decompress_brotli(&compressed, u32::MAX as usize).is_err());
+        assert!(decompress_brotli(b"not brotli", 16).is_err());
+    }
+
+    #[test]
+    fn round_trip_transformed_glyf() {
+        let font = build_font("Test Serif", "Italic", 400, FS_SELECTION_ITALIC);
+        let woff2 = encode(&font).unwrap();
+        assert!(is_woff2(&woff2));
+        let parsed = Woff2::parse(&woff2).unwrap();
+        for tag in [GLYF, LOCA] {
+            let entry = parsed.tables.iter().find(|entry| entry.tag == tag).unwrap();
+            assert!(entry.is_transformed());
+        }
+        check_round_trip(&font, &woff2);
+        let decoded = decode(&woff2).unwrap();
+        let head = Sfnt::parse(&decoded)
+            .unwrap()
+            .get_font_table(HEAD)
+            .unwrap()
+            .unwrap();
+        let flags = u16::from_be_bytes([head[16], head[17]]);
+        assert_ne!(flags & HEAD_FLAG_LOSSLESS_TRANSFORMED, 0);
+    }
+
+    // 変換した glyf から復元したグリフは，重なりのフラグ，命令，複合グリフの部品を保つ．
+    #[test]
+    fn reconstruct_transformed_glyf() {
+        let font = build_font("Test Serif", "Regular", 400, 0);
+        let sfnt = Sfnt::parse(&font).unwrap();
+        let glyf = sfnt.get_font_table(GLYF).unwrap().unwrap();
+        let loca = sfnt.get_font_table(LOCA).unwrap().unwrap();
+        let transformed = transform_face_glyf(&sfnt).unwrap().unwrap();
+        let reconstructed = reconstruct_glyf(&transformed).unwrap();
+        assert_eq!(reconstructed.glyf, glyf);
+        assert_eq!(reconstructed.loca, loca);
+        assert_eq!(reconstructed.x_mins, vec![0, 100, 0, 0]);
+    }
+
+    #[test]
+    fn coordinate_out_of_range() {
+        let mut data = vec![0, 0, 0, 0, 0, 1, 0, 0];
+        // nContours, nPoints, flags, glyphs, composites, bboxes, instructions
+        for size in [2_u32, 1, 2, 9, 0, 4, 0] {
+            data.extend_from_slice(&size.to_be_bytes());
+        }
+        data.extend_from_slice(&[0, 1, 2, 0x7F, 0x7F]);
+        data.extend_from_slice(&[0x7F, 0xFF, 0, 0, 0x00, 0x01, 0, 0, 0]);
+        data.extend_from_slice(&[0, 0, 0, 0]);
+        // (32767, 0) の次の点は i16 の範囲を超える．
+        assert!(matches!(
+            reconstruct_glyf(&data),
+            Err(Error::MalformedTable { tag: GLYF, .. })
+        ));
+        data[45..47].copy_from_slice(&[0, 0]);
+        assert!(reconstruct_glyf(&data).is_ok());
+    }
+
+    #[test]
+    fn round_trip_collection() {
+        let ttc = build_ttc("Test Sans");
+        let woff2 = encode(&ttc).unwrap();
+        let parsed = Woff2::parse(&woff2).unwrap();
+        // name と OS/2 以外は共有するので，テーブルは一つずつしか格納しない．
+        let shared = parsed
+            .tables
+            .iter()
+            .filter(|entry| entry.tag != NAME && entry.tag != OS2)
+            .count();
+        assert_eq!(parsed.tables.len(), shared + 4);
+        assert_eq!(parsed.collection.as_ref().unwrap().fonts.len(), 2);
+        check_round_trip(&ttc, &woff2);
+
+        // 展開したコレクションでも，二つのフェイスは同じ位置のテーブルを使う．
+        let decoded = decode(&woff2).unwrap();
+        let faces = parse_faces(&decoded).unwrap();
+        for record in &faces[0].tables {
+            let other = faces[1].get_table_record(record.tag).unwrap();
+            let is_shared = record.tag != NAME && record.tag != OS2;
+            assert_eq!(record.offset == other.offset, is_shared);
+        }
+    }
+}